@@ -6,11 +6,14 @@ use syn::{parse_macro_input, punctuated::Punctuated, ItemStruct, Lit, MetaNameVa
 
 /// Create necessary handler, validator and meta functions for foreign data wrapper
 ///
-/// This macro will create three functions which can be used in Postgres.
+/// This macro will create four functions which can be used in Postgres.
 ///
 /// 1. `<snake_case_fdw_name>_fdw_handler()` - foreign data wrapper handler function
-/// 2. `<snake_case_fdw_name>_fdw_validator()` - foreign data wrapper validator function
+/// 2. `<snake_case_fdw_name>_fdw_validator()` - foreign data wrapper validator function, which
+///    checks the options against `option_defs()` and then calls `validator()`
 /// 3. `<snake_case_fdw_name>_fdw_meta()` - function to return a table contains fdw metadata
+/// 4. `<snake_case_fdw_name>_fdw_options()` - function to return a table contains the options
+///    defined by `option_defs()`
 ///
 /// # Example
 ///
@@ -35,6 +38,7 @@ use syn::{parse_macro_input, punctuated::Punctuated, ItemStruct, Lit, MetaNameVa
 ///   validator hello_world_fdw_validator;
 ///
 /// select * from hello_world_fdw_meta();
+/// select * from hello_world_fdw_options();
 /// ```
 #[proc_macro_attribute]
 pub fn wrappers_fdw(attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -76,6 +80,7 @@ pub fn wrappers_fdw(attr: TokenStream, item: TokenStream) -> TokenStream {
     let fn_validator_ident = format_ident!("{}_validator", ident_snake);
     let fn_meta_ident = format_ident!("{}_meta", ident_snake);
     let fn_get_meta_ident = format_ident!("{}_get_meta", ident_snake);
+    let fn_options_ident = format_ident!("{}_options", ident_snake);
 
     let quoted = quote! {
         #item_tokens
@@ -94,6 +99,9 @@ pub fn wrappers_fdw(attr: TokenStream, item: TokenStream) -> TokenStream {
 
             #[pg_extern(create_or_replace)]
             fn #fn_validator_ident(options: Vec<Option<String>>, catalog: Option<pg_sys::Oid>) {
+                validate_options(&#ident::option_defs(), &options, catalog)
+                    .map_err(ErrorReport::from)
+                    .unwrap_or_report();
                 #ident::validator(options, catalog)
                     .map_err(|e| <super::#error_type_ident as Into<ErrorReport>>::into(e))
                     .unwrap_or_report();
@@ -121,6 +129,33 @@ pub fn wrappers_fdw(attr: TokenStream, item: TokenStream) -> TokenStream {
                     meta.get("website").map(|s| s.to_owned()),
                 )].into_iter())
             }
+
+            #[pg_extern(create_or_replace)]
+            fn #fn_options_ident() -> TableIterator<'static, (
+                name!(name, String),
+                name!(contexts, Vec<String>),
+                name!(value_type, String),
+                name!(default_value, Option<String>),
+                name!(allowed_values, Vec<String>),
+                name!(required, bool),
+                name!(secret, bool),
+                name!(description, String)
+            )> {
+                let defs = #ident::option_defs()
+                    .into_iter()
+                    .chain(common_option_defs());
+
+                TableIterator::new(defs.map(|def| (
+                    def.name.to_owned(),
+                    def.contexts.iter().map(|ctx| ctx.name().to_owned()).collect(),
+                    def.opt_type.name().to_owned(),
+                    def.default.map(|s| s.to_owned()),
+                    def.allowed.iter().map(|s| (*s).to_owned()).collect(),
+                    def.required,
+                    def.secret,
+                    def.description.to_owned(),
+                )).collect::<Vec<_>>().into_iter())
+            }
         }
 
     };
@@ -0,0 +1,172 @@
+use crate::interface::{Aggregate, AggregateKind, Column};
+use crate::qual::unnest_clause;
+use pgrx::{is_a, pg_sys, PgList};
+use std::ffi::CStr;
+use std::ptr;
+
+// get an aggregate function kind from its function oid, only the built-in
+// aggregate functions in pg_catalog are supported
+unsafe fn get_aggregate_kind(fn_oid: pg_sys::Oid) -> Option<AggregateKind> {
+    let nsp_name = pg_sys::get_namespace_name(pg_sys::get_func_namespace(fn_oid));
+    if nsp_name.is_null() || CStr::from_ptr(nsp_name).to_str() != Ok("pg_catalog") {
+        return None;
+    }
+
+    let fn_name = pg_sys::get_func_name(fn_oid);
+    if fn_name.is_null() {
+        return None;
+    }
+
+    match CStr::from_ptr(fn_name).to_str() {
+        Ok("count") => Some(AggregateKind::Count),
+        Ok("sum") => Some(AggregateKind::Sum),
+        Ok("avg") => Some(AggregateKind::Avg),
+        Ok("min") => Some(AggregateKind::Min),
+        Ok("max") => Some(AggregateKind::Max),
+        _ => None,
+    }
+}
+
+// create a column from a plain Var which belongs to the base relation
+unsafe fn create_column(
+    node: *mut pg_sys::Node,
+    baserel: *mut pg_sys::RelOptInfo,
+    baserel_id: pg_sys::Oid,
+) -> Option<Column> {
+    let node = unnest_clause(node);
+    if !is_a(node, pg_sys::NodeTag::T_Var) {
+        return None;
+    }
+
+    let var = node as *mut pg_sys::Var;
+    if (*var).varattno < 1 || (*var).varno as pg_sys::Index != (*baserel).relid {
+        return None;
+    }
+
+    let attname = pg_sys::get_attname(baserel_id, (*var).varattno, true);
+    if attname.is_null() {
+        return None;
+    }
+
+    Some(Column {
+        name: CStr::from_ptr(attname).to_str().unwrap().to_owned(),
+        num: (*var).varattno as usize,
+        type_oid: (*var).vartype,
+    })
+}
+
+// create an aggregate from an Aggref node, only simple aggregates like
+// `count(*)` or `sum(col)` are supported
+unsafe fn create_aggregate(
+    aggref: *mut pg_sys::Aggref,
+    baserel: *mut pg_sys::RelOptInfo,
+    baserel_id: pg_sys::Oid,
+    alias: String,
+) -> Option<Aggregate> {
+    if (*aggref).aggkind != pg_sys::AGGKIND_NORMAL as std::os::raw::c_char
+        || (*aggref).aggsplit != pg_sys::AggSplit::AGGSPLIT_SIMPLE
+        || !(*aggref).aggfilter.is_null()
+        || !(*aggref).aggdistinct.is_null()
+        || !(*aggref).aggorder.is_null()
+    {
+        return None;
+    }
+
+    let kind = get_aggregate_kind((*aggref).aggfnoid)?;
+
+    let args = PgList::<pg_sys::TargetEntry>::from_pg((*aggref).args);
+    let column = if (*aggref).aggstar {
+        if kind != AggregateKind::Count {
+            return None;
+        }
+        None
+    } else {
+        if args.len() != 1 {
+            return None;
+        }
+        let tle = args.get_ptr(0)?;
+        Some(create_column((*tle).expr as _, baserel, baserel_id)?)
+    };
+
+    Some(Aggregate {
+        kind,
+        column,
+        type_oid: (*aggref).aggtype,
+        alias,
+    })
+}
+
+// extract group by columns and aggregates from the grouping target of an
+// upper relation, return None if the grouping cannot be pushed down
+//
+// The returned target list is used as `fdw_scan_tlist` of the foreign scan,
+// it contains the group by columns first and then the aggregates, which is
+// the same order as the cells returned by the FDW.
+pub(crate) unsafe fn extract_aggregates(
+    root: *mut pg_sys::PlannerInfo,
+    baserel: *mut pg_sys::RelOptInfo,
+    baserel_id: pg_sys::Oid,
+    grouped_rel: *mut pg_sys::RelOptInfo,
+) -> Option<(Vec<Column>, Vec<Aggregate>, *mut pg_sys::List)> {
+    let parse = (*root).parse;
+
+    // grouping sets and HAVING clause are not supported
+    if !(*parse).groupingSets.is_null() || !(*parse).havingQual.is_null() {
+        return None;
+    }
+
+    let target = (*grouped_rel).reltarget;
+    let exprs = PgList::<pg_sys::Node>::from_pg((*target).exprs);
+
+    let mut group_by = Vec::new();
+    let mut group_by_exprs = Vec::new();
+    let mut aggregates = Vec::new();
+    let mut aggregate_exprs = Vec::new();
+
+    for (i, expr) in exprs.iter_ptr().enumerate() {
+        let sgref = if (*target).sortgrouprefs.is_null() {
+            0
+        } else {
+            *(*target).sortgrouprefs.add(i)
+        };
+
+        if sgref != 0
+            && !pg_sys::get_sortgroupref_clause_noerr(sgref, (*parse).groupClause).is_null()
+        {
+            // this is a group by expression, which must be a plain column
+            let col = create_column(expr, baserel, baserel_id)?;
+            group_by.push(col);
+            group_by_exprs.push(expr);
+        } else if is_a(expr, pg_sys::NodeTag::T_Aggref) {
+            let alias = format!("_wrappers_agg_{}", aggregates.len() + 1);
+            let agg = create_aggregate(expr as _, baserel, baserel_id, alias)?;
+            aggregates.push(agg);
+            aggregate_exprs.push(expr);
+        } else {
+            // other expressions, like `count(*) + 1`, are not supported
+            return None;
+        }
+    }
+
+    if aggregates.is_empty() && group_by.is_empty() {
+        return None;
+    }
+
+    // make the foreign scan target list
+    let mut tlist: *mut pg_sys::List = ptr::null_mut();
+    for (resno, expr) in group_by_exprs
+        .into_iter()
+        .chain(aggregate_exprs)
+        .enumerate()
+    {
+        let tle = pg_sys::makeTargetEntry(
+            expr as _,
+            (resno + 1) as pg_sys::AttrNumber,
+            ptr::null_mut(),
+            false,
+        );
+        tlist = pg_sys::lappend(tlist, tle as _);
+    }
+
+    Some((group_by, aggregates, tlist))
+}
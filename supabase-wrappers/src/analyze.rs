@@ -0,0 +1,106 @@
+use pgrx::pg_sys::panic::ErrorReport;
+use pgrx::{debug2, prelude::*, tupdesc::PgTupleDesc, IntoDatum};
+use std::os::raw::c_int;
+
+use crate::prelude::*;
+
+use super::instance;
+
+#[pg_guard]
+pub(super) extern "C-unwind" fn analyze_foreign_table<
+    E: Into<ErrorReport>,
+    W: ForeignDataWrapper<E>,
+>(
+    relation: pg_sys::Relation,
+    func: *mut pg_sys::AcquireSampleRowsFunc,
+    totalpages: *mut pg_sys::BlockNumber,
+) -> bool {
+    debug2!("---> analyze_foreign_table");
+
+    // Postgres skips the foreign table with a warning if false is returned
+    if !W::is_analyze_supported() {
+        return false;
+    }
+
+    unsafe {
+        *func = Some(acquire_sample_rows::<E, W>);
+
+        // foreign table has no local pages, keep whatever is in pg_class
+        *totalpages = (*(*relation).rd_rel).relpages as _;
+    }
+    true
+}
+
+#[pg_guard]
+extern "C-unwind" fn acquire_sample_rows<E: Into<ErrorReport>, W: ForeignDataWrapper<E>>(
+    relation: pg_sys::Relation,
+    _elevel: c_int,
+    rows: *mut pg_sys::HeapTuple,
+    targrows: c_int,
+    totalrows: *mut f64,
+    totaldeadrows: *mut f64,
+) -> c_int {
+    debug2!("---> acquire_sample_rows");
+    unsafe {
+        let ftable_id = (*relation).rd_id;
+        let ftable = pg_sys::GetForeignTable(ftable_id);
+        let opts = options_to_hashmap((*ftable).options).report_unwrap();
+
+        // all columns of the foreign table
+        let tup_desc = PgTupleDesc::from_pg_copy((*relation).rd_att);
+        let columns = tup_desc
+            .iter()
+            .filter(|a| !a.attisdropped)
+            .map(|a| Column {
+                name: pgrx::name_data_to_str(&a.attname).to_string(),
+                num: a.attnum as usize,
+                type_oid: a.atttypid,
+            })
+            .collect::<Vec<Column>>();
+
+        // use the table owner's user mapping, like postgres_fdw does
+        let mut instance = instance::create_fdw_instance_from_table_id::<E, W>(
+            ftable_id,
+            (*(*relation).rd_rel).relowner,
+        );
+        let result = instance
+            .analyze(&columns, targrows as usize, &opts)
+            .report_unwrap();
+
+        *totaldeadrows = 0.0;
+
+        let Some((total, sample)) = result else {
+            report_warning(&format!(
+                "skipping analyze of foreign table \"{}\", it is not supported by the foreign data wrapper",
+                pgrx::name_data_to_str(&(*(*relation).rd_rel).relname)
+            ));
+
+            // keep the existing row count
+            *totalrows = (*(*relation).rd_rel).reltuples as f64;
+            return 0;
+        };
+
+        // convert sample rows to heap tuples
+        let natts = tup_desc.len();
+        let mut cnt = 0;
+        for row in sample.into_iter().take(targrows as usize) {
+            let mut values = vec![0.into_datum().unwrap(); natts];
+            let mut nulls = vec![true; natts];
+            for (col_name, cell) in row.cols.iter().zip(row.cells) {
+                let col = columns.iter().find(|c| &c.name == col_name);
+                if let (Some(col), Some(cell)) = (col, cell) {
+                    values[col.num - 1] = cell.into_datum().unwrap();
+                    nulls[col.num - 1] = false;
+                }
+            }
+            *rows.add(cnt) =
+                pg_sys::heap_form_tuple((*relation).rd_att, values.as_ptr(), nulls.as_ptr());
+            cnt += 1;
+        }
+
+        // total row count cannot be less than sample rows
+        *totalrows = (total as f64).max(cnt as f64);
+
+        cnt as c_int
+    }
+}
@@ -0,0 +1,130 @@
+use pgrx::{pg_guard, pg_sys};
+use std::any::{Any, TypeId};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::os::raw::c_int;
+
+// key of a cached value, the value type is part of the key so different
+// FDWs and values can share one cache
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct CacheKey {
+    pub(crate) server_id: pg_sys::Oid,
+    pub(crate) user_mapping_id: pg_sys::Oid,
+    pub(crate) options_hash: u64,
+    pub(crate) type_id: TypeId,
+}
+
+struct CacheEntry {
+    // syscache hash values of the server and user mapping, used to find the
+    // entries to be invalidated
+    server_hashvalue: u32,
+    user_mapping_hashvalue: u32,
+    value: Box<dyn Any>,
+}
+
+thread_local! {
+    // backend-lifetime cache of long-lived values, like connections or clients
+    static CACHE: RefCell<HashMap<CacheKey, CacheEntry>> = RefCell::new(HashMap::new());
+
+    static IS_CALLBACK_REGISTERED: Cell<bool> = const { Cell::new(false) };
+}
+
+// syscache callback, invalidate the cached values whose server or user mapping
+// is changed or dropped
+#[pg_guard]
+extern "C-unwind" fn invalidate_cache(_arg: pg_sys::Datum, cacheid: c_int, hashvalue: u32) {
+    let is_server = cacheid == pg_sys::SysCacheIdentifier::FOREIGNSERVEROID as c_int;
+
+    let removed = CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let keys = cache
+            .iter()
+            .filter(|(_, entry)| {
+                let entry_hashvalue = if is_server {
+                    entry.server_hashvalue
+                } else {
+                    entry.user_mapping_hashvalue
+                };
+                // hash value 0 means all entries need to be invalidated
+                hashvalue == 0 || entry_hashvalue == hashvalue
+            })
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        keys.iter()
+            .filter_map(|key| cache.remove(key))
+            .collect::<Vec<_>>()
+    });
+
+    // drop the values after cache is released, so dropping a value cannot
+    // cause the cache to be borrowed twice
+    drop(removed);
+}
+
+unsafe fn register_callbacks() {
+    if IS_CALLBACK_REGISTERED.get() {
+        return;
+    }
+    for cache_id in [
+        pg_sys::SysCacheIdentifier::FOREIGNSERVEROID as c_int,
+        pg_sys::SysCacheIdentifier::USERMAPPINGOID as c_int,
+    ] {
+        pg_sys::CacheRegisterSyscacheCallback(
+            cache_id,
+            Some(invalidate_cache),
+            pg_sys::Datum::from(0usize),
+        );
+    }
+    IS_CALLBACK_REGISTERED.set(true);
+}
+
+unsafe fn get_hashvalue(cache_id: c_int, oid: pg_sys::Oid) -> u32 {
+    pg_sys::GetSysCacheHashValue(
+        cache_id,
+        oid.into(),
+        pg_sys::Datum::from(0usize),
+        pg_sys::Datum::from(0usize),
+        pg_sys::Datum::from(0usize),
+    )
+}
+
+// get the cached value, or create a new one and save it in cache if it is not
+// cached yet
+pub(crate) fn get_or_create<T, E, F>(key: CacheKey, create: F) -> Result<T, E>
+where
+    T: Clone + 'static,
+    F: FnOnce() -> Result<T, E>,
+{
+    let cached = CACHE.with(|cache| {
+        cache
+            .borrow()
+            .get(&key)
+            .and_then(|entry| entry.value.downcast_ref::<T>())
+            .cloned()
+    });
+    if let Some(value) = cached {
+        return Ok(value);
+    }
+
+    // the value is created without cache borrowing, because creating it may
+    // accept invalidation messages which will trigger the callback
+    let value = create()?;
+
+    unsafe {
+        register_callbacks();
+        let entry = CacheEntry {
+            server_hashvalue: get_hashvalue(
+                pg_sys::SysCacheIdentifier::FOREIGNSERVEROID as c_int,
+                key.server_id,
+            ),
+            user_mapping_hashvalue: get_hashvalue(
+                pg_sys::SysCacheIdentifier::USERMAPPINGOID as c_int,
+                key.user_mapping_id,
+            ),
+            value: Box::new(value.clone()),
+        };
+        let old = CACHE.with(|cache| cache.borrow_mut().insert(key, entry));
+        drop(old);
+    }
+
+    Ok(value)
+}
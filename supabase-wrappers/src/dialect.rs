@@ -0,0 +1,241 @@
+//! Provides SQL dialects to deparse quals into the remote SQL of SQL-backed
+//! foreign data wrappers
+//!
+
+use crate::interface::{Cell, CellFormatter, DefaultFormatter, Filter, Join, Qual, Value};
+
+// comparison operators which are same in all dialects
+const COMPARISON_OPERATORS: &[&str] = &["=", "<>", "<", "<=", ">", ">="];
+
+/// SQL dialect of a remote database
+///
+/// It translates Postgres operators in [`Qual`]s into the equivalent remote
+/// SQL, operators which have no equivalent cannot be pushed down and should
+/// be applied locally.
+///
+/// | Postgres        | ClickHouse                  | T-SQL                          | BigQuery                          |
+/// | --------------- | --------------------------- | ------------------------------ | --------------------------------- |
+/// | `~~` (like)     | `like`                      | `like` with `\` escape         | `like`                            |
+/// | `~~*` (ilike)   | `ilike`                     | -                              | `lower(a) like lower(b)`          |
+/// | `<>`            | `<>`                        | `<>`                           | `<>`                              |
+/// | `@>`, `<@`      | `hasAll(a, b)`              | -                              | -                                 |
+/// | `&&`            | `hasAny(a, b)`              | -                              | -                                 |
+///
+/// The negated operators `!~~` and `!~~*` are translated in the same way,
+/// except `!~~` is not pushed down in T-SQL. Regular expressions are not
+/// pushed down, as the remote regex engines differ from Postgres. The array
+/// operators are only pushed down when compared with a numeric or boolean
+/// array constant.
+///
+/// For example,
+///
+/// ```rust,no_run
+/// # use supabase_wrappers::prelude::*;
+/// # fn deparse(quals: &[Qual]) -> String {
+/// let cond = quals
+///     .iter()
+///     .filter_map(|q| SqlDialect::ClickHouse.deparse_qual(q))
+///     .collect::<Vec<String>>()
+///     .join(" and ");
+/// # cond
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlDialect {
+    ClickHouse,
+    TSql,
+    BigQuery,
+}
+
+impl SqlDialect {
+    /// Get the template of a Postgres operator in this dialect, in which `{0}`
+    /// is replaced with the column and `{1}` with the value. Return `None` if
+    /// the operator cannot be pushed down.
+    pub fn operator_template(&self, operator: &str) -> Option<&'static str> {
+        if COMPARISON_OPERATORS.contains(&operator) {
+            return match operator {
+                "=" => Some("{0} = {1}"),
+                "<>" => Some("{0} <> {1}"),
+                "<" => Some("{0} < {1}"),
+                "<=" => Some("{0} <= {1}"),
+                ">" => Some("{0} > {1}"),
+                _ => Some("{0} >= {1}"),
+            };
+        }
+
+        match self {
+            SqlDialect::ClickHouse => match operator {
+                "~~" => Some("{0} like {1}"),
+                "!~~" => Some("{0} not like {1}"),
+                "~~*" => Some("{0} ilike {1}"),
+                "!~~*" => Some("{0} not ilike {1}"),
+                "@>" => Some("hasAll({0}, {1})"),
+                "<@" => Some("hasAll({1}, {0})"),
+                "&&" => Some("hasAny({0}, {1})"),
+                _ => None,
+            },
+            // SQL Server has no default escape character for like, and its
+            // case sensitivity depends on collation, so ilike is not supported.
+            // Negated like is not pushed down either, as the collation may
+            // exclude rows which Postgres would keep.
+            SqlDialect::TSql => match operator {
+                "~~" => Some("{0} like {1} escape '\\'"),
+                _ => None,
+            },
+            SqlDialect::BigQuery => match operator {
+                "~~" => Some("{0} like {1}"),
+                "!~~" => Some("{0} not like {1}"),
+                "~~*" => Some("lower({0}) like lower({1})"),
+                "!~~*" => Some("lower({0}) not like lower({1})"),
+                _ => None,
+            },
+        }
+    }
+
+    /// Check if the qual can be pushed down in this dialect
+    pub fn is_pushable(&self, qual: &Qual) -> bool {
+        self.deparse_qual(qual).is_some()
+    }
+
+    /// Check if all the quals in the filter can be pushed down in this dialect
+    pub fn is_filter_pushable(&self, filter: &Filter) -> bool {
+        filter.quals().iter().all(|qual| self.is_pushable(qual))
+    }
+
+    /// Check if the join conditions and all the relation restrictions can be
+    /// pushed down in this dialect
+    pub fn is_join_pushable(&self, join: &Join) -> bool {
+        join.quals
+            .iter()
+            .all(|q| COMPARISON_OPERATORS.contains(&q.operator.as_str()))
+            && join
+                .outer
+                .quals
+                .iter()
+                .chain(join.inner.quals.iter())
+                .all(|qual| self.is_pushable(qual))
+    }
+
+    /// Deparse the qual in this dialect, return `None` if it cannot be pushed down
+    pub fn deparse_qual(&self, qual: &Qual) -> Option<String> {
+        let mut formatter = DefaultFormatter::new();
+        self.deparse_qual_with_fmt(qual, &mut formatter)
+    }
+
+    /// Deparse the qual in this dialect using the cell formatter, return `None`
+    /// if it cannot be pushed down
+    pub fn deparse_qual_with_fmt<T: CellFormatter>(
+        &self,
+        qual: &Qual,
+        t: &mut T,
+    ) -> Option<String> {
+        let field = match &qual.field_expr {
+            Some(expr) => expr.deparse_with_fmt(t),
+            None => qual.field.clone(),
+        };
+
+        match &qual.value {
+            Value::Cell(cell) => match qual.operator.as_str() {
+                "is" | "is not" => self.deparse_bool_test(&field, &qual.operator, cell, t),
+                operator => self.deparse_operator(&field, operator, cell, t),
+            },
+            Value::Array(cells) if !cells.is_empty() => {
+                let conds = cells
+                    .iter()
+                    .map(|cell| self.deparse_operator(&field, &qual.operator, cell, t))
+                    .collect::<Option<Vec<_>>>()?;
+                let sep = if qual.use_or { " or " } else { " and " };
+                Some(format!("({})", conds.join(sep)))
+            }
+            Value::Array(_) => None,
+        }
+    }
+
+    /// Deparse the filter in this dialect, return `None` if any of its quals
+    /// cannot be pushed down
+    pub fn deparse_filter(&self, filter: &Filter) -> Option<String> {
+        let mut formatter = DefaultFormatter::new();
+        self.deparse_filter_with_fmt(filter, &mut formatter)
+    }
+
+    /// Deparse the filter in this dialect using the cell formatter, return
+    /// `None` if any of its quals cannot be pushed down
+    pub fn deparse_filter_with_fmt<T: CellFormatter>(
+        &self,
+        filter: &Filter,
+        t: &mut T,
+    ) -> Option<String> {
+        let mut is_pushable = true;
+        let sql = filter.deparse_with(|qual| {
+            self.deparse_qual_with_fmt(qual, t).unwrap_or_else(|| {
+                is_pushable = false;
+                String::new()
+            })
+        });
+        is_pushable.then_some(sql)
+    }
+
+    fn deparse_operator<T: CellFormatter>(
+        &self,
+        field: &str,
+        operator: &str,
+        cell: &Cell,
+        t: &mut T,
+    ) -> Option<String> {
+        let template = self.operator_template(operator)?;
+        let value = match (self, operator, cell) {
+            // literal '[' is a wildcard in SQL Server like pattern
+            (SqlDialect::TSql, "~~", Cell::String(pattern)) => {
+                t.fmt_cell(&Cell::String(pattern.replace('[', "[[]")))
+            }
+            (_, "@>" | "<@" | "&&", cell) if !is_numeric_array(cell) => return None,
+            _ => t.fmt_cell(cell),
+        };
+        Some(fill_template(template, field, &value))
+    }
+
+    // deparse `is` and `is not` test of null or boolean value
+    fn deparse_bool_test<T: CellFormatter>(
+        &self,
+        field: &str,
+        operator: &str,
+        cell: &Cell,
+        t: &mut T,
+    ) -> Option<String> {
+        match cell {
+            Cell::String(s) if s == "null" => Some(format!("{} {} null", field, operator)),
+            Cell::Bool(_) if *self == SqlDialect::BigQuery => {
+                Some(format!("{} {} {}", field, operator, t.fmt_cell(cell)))
+            }
+            // `is not` includes null values, which `<>` doesn't
+            Cell::Bool(_) if operator == "is" => Some(format!("{} = {}", field, t.fmt_cell(cell))),
+            Cell::Bool(_) => Some(format!(
+                "({0} <> {1} or {0} is null)",
+                field,
+                t.fmt_cell(cell)
+            )),
+            _ => None,
+        }
+    }
+}
+
+// fill operator template with the column and the value
+fn fill_template(template: &str, field: &str, value: &str) -> String {
+    template
+        .split("{1}")
+        .map(|part| part.replace("{0}", field))
+        .collect::<Vec<_>>()
+        .join(value)
+}
+
+fn is_numeric_array(cell: &Cell) -> bool {
+    matches!(
+        cell,
+        Cell::BoolArray(_)
+            | Cell::I16Array(_)
+            | Cell::I32Array(_)
+            | Cell::I64Array(_)
+            | Cell::F32Array(_)
+            | Cell::F64Array(_)
+    )
+}
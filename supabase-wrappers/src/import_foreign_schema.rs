@@ -6,7 +6,7 @@ use std::ffi::c_void;
 use std::marker::PhantomData;
 
 use crate::instance;
-use crate::options::options_to_hashmap;
+use crate::options::{options_to_hashmap, validate_option_pairs, OptionContext};
 use crate::prelude::ForeignDataWrapper;
 use crate::utils::ReportableError;
 
@@ -20,7 +20,10 @@ struct FdwState<E: Into<ErrorReport>, W: ForeignDataWrapper<E>> {
 impl<E: Into<ErrorReport>, W: ForeignDataWrapper<E>> FdwState<E, W> {
     unsafe fn new(foreignserverid: Oid) -> Self {
         Self {
-            instance: instance::create_fdw_instance_from_server_id(foreignserverid),
+            instance: instance::create_fdw_instance_from_server_id(
+                foreignserverid,
+                pg_sys::InvalidOid,
+            ),
             _phantom: PhantomData,
         }
     }
@@ -112,6 +115,16 @@ pub(super) extern "C-unwind" fn import_foreign_schema<
             options: options_to_hashmap((*stmt).options).unwrap(),
         };
 
+        validate_option_pairs(
+            &W::option_defs(),
+            OptionContext::Import,
+            import_foreign_schema_stmt
+                .options
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str())),
+        )
+        .report_unwrap();
+
         let mut state = FdwState::<E, W>::new(server_oid);
         create_stmts = state
             .instance
@@ -1,24 +1,181 @@
+use std::any::TypeId;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::ffi::CStr;
+use std::hash::{Hash, Hasher};
 
 use crate::prelude::*;
 use pgrx::pg_sys::panic::ErrorReport;
 use pgrx::prelude::*;
 
+use crate::cache::{self, CacheKey};
+
+/// A foreign server, passed to [`new`](crate::interface::ForeignDataWrapper::new)
+///
+/// The struct is non-exhaustive as more fields may be added, use
+/// [`ForeignServer::new`] to create it outside this crate.
 #[derive(Debug, Clone, Default)]
+#[non_exhaustive]
 pub struct ForeignServer {
     pub server_name: String,
     pub server_type: Option<String>,
     pub server_version: Option<String>,
     pub options: HashMap<String, String>,
+    // options of the user mapping for the user who runs the query, or the
+    // PUBLIC user mapping if that user has no user mapping
+    pub user_options: HashMap<String, String>,
+    // ids of the server and user mapping, used as cache key
+    pub(crate) server_id: Option<pg_sys::Oid>,
+    pub(crate) user_mapping_id: Option<pg_sys::Oid>,
 }
 
-// create a fdw instance from its id
+impl ForeignServer {
+    /// Create a foreign server with its name and options, for example to
+    /// create a foreign data wrapper instance in tests
+    pub fn new(server_name: &str, options: HashMap<String, String>) -> Self {
+        Self {
+            server_name: server_name.to_owned(),
+            options,
+            ..Default::default()
+        }
+    }
+
+    /// Get the options to resolve credentials from
+    ///
+    /// User mapping options are used if any of the credential options is in
+    /// the user mapping, otherwise server options are used. This makes sure
+    /// the credentials are never mixed up between user mapping and server.
+    ///
+    /// For example,
+    ///
+    /// ```rust,no_run
+    /// # use supabase_wrappers::prelude::*;
+    /// # fn new(server: ForeignServer) -> Result<(), OptionsError> {
+    /// let opts = server.credential_options(&["api_key", "api_key_id"]);
+    /// let api_key = match opts.get("api_key") {
+    ///     Some(api_key) => Some(api_key.to_owned()),
+    ///     None => get_vault_secret(require_option("api_key_id", opts)?),
+    /// };
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn credential_options(&self, opt_names: &[&str]) -> &HashMap<String, String> {
+        if opt_names
+            .iter()
+            .any(|opt_name| self.user_options.contains_key(*opt_name))
+        {
+            &self.user_options
+        } else {
+            &self.options
+        }
+    }
+
+    /// Get a cached value for this server, or create and cache it
+    ///
+    /// The value is cached for the backend lifetime, it is shared by all the
+    /// statements using the same server, user mapping, options and
+    /// `credentials`. This can be used to reuse a long-lived connection or
+    /// client across statements. The cached value is invalidated when the
+    /// server or user mapping is altered or dropped.
+    ///
+    /// The `credentials` should be the resolved credentials used to create the
+    /// value, like the API key read from Vault, so a new value is created
+    /// when a Vault secret is rotated without changing the options.
+    ///
+    /// The value type is part of the cache key, so it should be a type only
+    /// used by the FDW itself, and it should be cheap to clone, like a type
+    /// wrapped by `Arc`.
+    ///
+    /// For example,
+    ///
+    /// ```rust,no_run
+    /// # use supabase_wrappers::prelude::*;
+    /// # use std::sync::Arc;
+    /// # #[derive(Clone)]
+    /// # struct Client {}
+    /// # fn create_client(api_key: &str) -> Result<Client, CreateRuntimeError> { Ok(Client {}) }
+    /// # fn new(server: ForeignServer, api_key: String) -> Result<(), CreateRuntimeError> {
+    /// let (rt, client) = server.get_or_create_cached(&api_key, || -> Result<_, CreateRuntimeError> {
+    ///     // the runtime is cached together with client, because the client's
+    ///     // connections are bound to it
+    ///     Ok((Arc::new(create_async_runtime()?), create_client(&api_key)?))
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_or_create_cached<C, T, E, F>(&self, credentials: &C, create: F) -> Result<T, E>
+    where
+        C: Hash + ?Sized,
+        T: Clone + 'static,
+        F: FnOnce() -> Result<T, E>,
+    {
+        let Some(server_id) = self.server_id else {
+            return create();
+        };
+        let key = CacheKey {
+            server_id,
+            user_mapping_id: self.user_mapping_id.unwrap_or(pg_sys::InvalidOid),
+            options_hash: self.options_hash(credentials),
+            type_id: TypeId::of::<T>(),
+        };
+        cache::get_or_create(key, create)
+    }
+
+    fn options_hash<C: Hash + ?Sized>(&self, credentials: &C) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        credentials.hash(&mut hasher);
+        for options in [&self.options, &self.user_options] {
+            let mut options = options.iter().collect::<Vec<_>>();
+            options.sort();
+            options.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+// get id and options of the user mapping for the user, fall back to the
+// PUBLIC user mapping, return empty options if neither of them exists
+unsafe fn get_user_mapping_options(
+    fserver_id: pg_sys::Oid,
+    user_id: pg_sys::Oid,
+) -> (Option<pg_sys::Oid>, HashMap<String, String>) {
+    let cache_id = pg_sys::SysCacheIdentifier::USERMAPPINGUSERSERVER
+        .try_into()
+        .unwrap();
+    for user_id in [user_id, pg_sys::InvalidOid] {
+        let htup = pg_sys::SearchSysCache2(cache_id, user_id.into(), fserver_id.into());
+        if htup.is_null() {
+            continue;
+        }
+
+        let umid = (*(pg_sys::GETSTRUCT(htup) as pg_sys::Form_pg_user_mapping)).oid;
+        let mut is_null = false;
+        let datum = pg_sys::SysCacheGetAttr(
+            cache_id,
+            htup,
+            pg_sys::Anum_pg_user_mapping_umoptions as _,
+            &mut is_null,
+        );
+        let options = if is_null {
+            Ok(HashMap::new())
+        } else {
+            options_to_hashmap(pg_sys::untransformRelOptions(datum))
+        };
+        pg_sys::ReleaseSysCache(htup);
+        return (Some(umid), options.report_unwrap());
+    }
+    (None, HashMap::new())
+}
+
+// create a fdw instance from its id, the user mapping options are of the
+// user to check access as, like the view owner when a view is queried, it
+// falls back to current user if the user id is invalid
 pub(super) unsafe fn create_fdw_instance_from_server_id<
     E: Into<ErrorReport>,
     W: ForeignDataWrapper<E>,
 >(
     fserver_id: pg_sys::Oid,
+    user_id: pg_sys::Oid,
 ) -> W {
     let to_string = |raw: *mut std::ffi::c_char| -> Option<String> {
         if raw.is_null() {
@@ -37,11 +194,20 @@ pub(super) unsafe fn create_fdw_instance_from_server_id<
         Some(value)
     };
     let fserver = pg_sys::GetForeignServer(fserver_id);
+    let user_id = if user_id == pg_sys::InvalidOid {
+        pg_sys::GetUserId()
+    } else {
+        user_id
+    };
+    let (user_mapping_id, user_options) = get_user_mapping_options(fserver_id, user_id);
     let server = ForeignServer {
         server_name: to_string((*fserver).servername).unwrap(),
         server_type: to_string((*fserver).servertype),
         server_version: to_string((*fserver).serverversion),
         options: options_to_hashmap((*fserver).options).report_unwrap(),
+        user_options,
+        server_id: Some(fserver_id),
+        user_mapping_id,
     };
     let wrapper = W::new(server);
     wrapper.report_unwrap()
@@ -53,7 +219,8 @@ pub(super) unsafe fn create_fdw_instance_from_table_id<
     W: ForeignDataWrapper<E>,
 >(
     ftable_id: pg_sys::Oid,
+    user_id: pg_sys::Oid,
 ) -> W {
     let ftable = pg_sys::GetForeignTable(ftable_id);
-    create_fdw_instance_from_server_id((*ftable).serverid)
+    create_fdw_instance_from_server_id((*ftable).serverid, user_id)
 }
@@ -2,21 +2,29 @@
 //!
 
 use crate::instance::ForeignServer;
+use crate::options::{require_option_f64_or, OptionContext, OptionDef};
+use crate::utils::{report_error, ReportableError};
 use crate::FdwRoutine;
 use pgrx::pg_sys::panic::ErrorReport;
 use pgrx::prelude::{Date, Interval, Time, Timestamp, TimestampWithTimeZone};
 use pgrx::{
-    datum::Uuid,
+    datum::{Range, Uuid},
     fcinfo,
     pg_sys::{self, bytea, BuiltinOid, Datum, Oid},
-    AllocatedByRust, AnyNumeric, FromDatum, IntoDatum, JsonB, PgBuiltInOids, PgOid,
+    tupdesc::PgTupleDesc,
+    AllocatedByRust, AnyNumeric, FromDatum, IntoDatum, JsonB, PgBuiltInOids, PgOid, PgSqlErrorCode,
 };
 use std::collections::HashMap;
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
 use std::fmt;
+use std::io::Write;
 use std::iter::Zip;
 use std::mem;
-use std::slice::Iter;
+use std::os::raw::c_char;
+use std::os::unix::net::UnixStream;
+use std::slice::{self, Iter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 // fdw system catalog oids
 // https://doxygen.postgresql.org/pg__foreign__data__wrapper_8h.html
@@ -32,6 +40,15 @@ pub const FOREIGN_SERVER_RELATION_ID: Oid = BuiltinOid::ForeignServerRelationId.
 /// Constant can be used in [validator](ForeignDataWrapper::validator)
 pub const FOREIGN_TABLE_RELATION_ID: Oid = BuiltinOid::ForeignTableRelationId.value();
 
+/// Constant can be used in [validator](ForeignDataWrapper::validator)
+pub const USER_MAPPING_RELATION_ID: Oid = BuiltinOid::UserMappingRelationId.value();
+
+/// Default cost of starting up a foreign scan, can be changed by `fdw_startup_cost` option
+pub const DEFAULT_FDW_STARTUP_COST: f64 = 0.0;
+
+/// Default cost of processing one row in a foreign scan, can be changed by `fdw_tuple_cost` option
+pub const DEFAULT_FDW_TUPLE_COST: f64 = 1.0;
+
 /// A data cell in a data row
 #[derive(Debug)]
 pub enum Cell {
@@ -59,6 +76,27 @@ pub enum Cell {
     F32Array(Vec<Option<f32>>),
     F64Array(Vec<Option<f64>>),
     StringArray(Vec<Option<String>>),
+    NumericArray(Vec<Option<AnyNumeric>>),
+    DateArray(Vec<Option<Date>>),
+    TimeArray(Vec<Option<Time>>),
+    TimestampArray(Vec<Option<Timestamp>>),
+    TimestamptzArray(Vec<Option<TimestampWithTimeZone>>),
+    UuidArray(Vec<Option<Uuid>>),
+    JsonArray(Vec<Option<JsonB>>),
+    ByteaArray(Vec<Option<Vec<u8>>>),
+    Composite(Composite),
+    Inet(String),
+    Cidr(String),
+    MacAddr(String),
+    I32Range(Range<i32>),
+    I64Range(Range<i64>),
+    TimestampRange(Range<Timestamp>),
+    TimestamptzRange(Range<TimestampWithTimeZone>),
+    /// enum value with the enum type OID and its label
+    Enum(Oid, String),
+    /// value of any type with the type OID and its text representation, the
+    /// type's input function is used to convert the text to the value
+    TypedText(Oid, String),
 }
 
 unsafe impl Send for Cell {}
@@ -90,10 +128,62 @@ impl Clone for Cell {
             Cell::F32Array(v) => Cell::F32Array(v.clone()),
             Cell::F64Array(v) => Cell::F64Array(v.clone()),
             Cell::StringArray(v) => Cell::StringArray(v.clone()),
+            Cell::NumericArray(v) => Cell::NumericArray(v.clone()),
+            Cell::DateArray(v) => Cell::DateArray(v.clone()),
+            Cell::TimeArray(v) => Cell::TimeArray(v.clone()),
+            Cell::TimestampArray(v) => Cell::TimestampArray(v.clone()),
+            Cell::TimestamptzArray(v) => Cell::TimestamptzArray(v.clone()),
+            Cell::UuidArray(v) => Cell::UuidArray(v.clone()),
+            Cell::JsonArray(v) => Cell::JsonArray(
+                v.iter()
+                    .map(|e| e.as_ref().map(|j| JsonB(j.0.clone())))
+                    .collect(),
+            ),
+            Cell::ByteaArray(v) => Cell::ByteaArray(v.clone()),
+            Cell::Composite(v) => Cell::Composite(v.clone()),
+            Cell::Inet(v) => Cell::Inet(v.clone()),
+            Cell::Cidr(v) => Cell::Cidr(v.clone()),
+            Cell::MacAddr(v) => Cell::MacAddr(v.clone()),
+            Cell::I32Range(v) => Cell::I32Range(v.clone()),
+            Cell::I64Range(v) => Cell::I64Range(v.clone()),
+            Cell::TimestampRange(v) => Cell::TimestampRange(v.clone()),
+            Cell::TimestamptzRange(v) => Cell::TimestamptzRange(v.clone()),
+            Cell::Enum(oid, v) => Cell::Enum(*oid, v.clone()),
+            Cell::TypedText(oid, v) => Cell::TypedText(*oid, v.clone()),
         }
     }
 }
 
+// convert text to datum using the input function of the type
+unsafe fn text_to_datum(type_oid: Oid, text: &str) -> Datum {
+    let mut typinput = Oid::INVALID;
+    let mut typioparam = Oid::INVALID;
+    pg_sys::getTypeInputInfo(type_oid, &mut typinput, &mut typioparam);
+    let text = CString::new(text).expect("text should not contain nul byte");
+    pg_sys::OidInputFunctionCall(typinput, text.as_ptr() as *mut c_char, typioparam, -1)
+}
+
+// convert datum to text using the output function of the type
+unsafe fn datum_to_text(datum: Datum, type_oid: Oid) -> String {
+    let mut typoutput = Oid::INVALID;
+    let mut typisvarlena = false;
+    pg_sys::getTypeOutputInfo(type_oid, &mut typoutput, &mut typisvarlena);
+    let text = pg_sys::OidOutputFunctionCall(typoutput, datum);
+    CStr::from_ptr(text).to_string_lossy().into_owned()
+}
+
+// write value in its text representation
+fn write_value_text<T: IntoDatum + Clone>(
+    value: &T,
+    type_oid: Oid,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    match value.clone().into_datum() {
+        Some(datum) => write!(f, "'{}'", unsafe { datum_to_text(datum, type_oid) }),
+        None => write!(f, "null"),
+    }
+}
+
 fn write_array<T: std::fmt::Display>(
     array: &[Option<T>],
     f: &mut fmt::Formatter<'_>,
@@ -109,6 +199,32 @@ fn write_array<T: std::fmt::Display>(
     write!(f, "[{}]", res)
 }
 
+// write array whose elements are formatted same as their scalar cells
+fn write_cell_array<T>(
+    array: &[Option<T>],
+    to_cell: impl Fn(&T) -> Cell,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    let cells = array
+        .iter()
+        .map(|e| e.as_ref().map(&to_cell))
+        .collect::<Vec<_>>();
+    write_array(&cells, f)
+}
+
+fn bytes_to_hex(bytes: &[u8]) -> String {
+    let hex = bytes
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<String>>()
+        .join("");
+    if hex.is_empty() {
+        "''".to_string()
+    } else {
+        format!(r#"'\x{}'"#, hex)
+    }
+}
+
 impl fmt::Display for Cell {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -177,16 +293,7 @@ impl fmt::Display for Cell {
             Cell::Json(v) => write!(f, "{:?}", v),
             Cell::Bytea(v) => {
                 let byte_u8 = unsafe { pgrx::varlena::varlena_to_byte_slice(*v) };
-                let hex = byte_u8
-                    .iter()
-                    .map(|b| format!("{:02X}", b))
-                    .collect::<Vec<String>>()
-                    .join("");
-                if hex.is_empty() {
-                    write!(f, "''")
-                } else {
-                    write!(f, r#"'\x{}'"#, hex)
-                }
+                write!(f, "{}", bytes_to_hex(byte_u8))
             }
             Cell::Uuid(v) => write!(f, "{}", v),
             Cell::BoolArray(v) => write_array(v, f),
@@ -196,6 +303,30 @@ impl fmt::Display for Cell {
             Cell::F32Array(v) => write_array(v, f),
             Cell::F64Array(v) => write_array(v, f),
             Cell::StringArray(v) => write_array(v, f),
+            Cell::NumericArray(v) => write_array(v, f),
+            Cell::DateArray(v) => write_cell_array(v, |e| Cell::Date(*e), f),
+            Cell::TimeArray(v) => write_cell_array(v, |e| Cell::Time(*e), f),
+            Cell::TimestampArray(v) => write_cell_array(v, |e| Cell::Timestamp(*e), f),
+            Cell::TimestamptzArray(v) => write_cell_array(v, |e| Cell::Timestamptz(*e), f),
+            Cell::UuidArray(v) => write_array(v, f),
+            Cell::JsonArray(v) => write_cell_array(v, |e| Cell::Json(JsonB(e.0.clone())), f),
+            Cell::ByteaArray(v) => {
+                let hexes = v
+                    .iter()
+                    .map(|e| e.as_deref().map(bytes_to_hex))
+                    .collect::<Vec<_>>();
+                write_array(&hexes, f)
+            }
+            Cell::Composite(v) => write!(f, "{}", v),
+            Cell::Inet(v) => write!(f, "'{}'", v),
+            Cell::Cidr(v) => write!(f, "'{}'", v),
+            Cell::MacAddr(v) => write!(f, "'{}'", v),
+            Cell::I32Range(v) => write_value_text(v, pg_sys::INT4RANGEOID, f),
+            Cell::I64Range(v) => write_value_text(v, pg_sys::INT8RANGEOID, f),
+            Cell::TimestampRange(v) => write_value_text(v, pg_sys::TSRANGEOID, f),
+            Cell::TimestamptzRange(v) => write_value_text(v, pg_sys::TSTZRANGEOID, f),
+            Cell::Enum(_, v) => write!(f, "'{}'", v),
+            Cell::TypedText(_, v) => write!(f, "'{}'", v),
         }
     }
 }
@@ -227,6 +358,24 @@ impl IntoDatum for Cell {
             Cell::F32Array(v) => v.into_datum(),
             Cell::F64Array(v) => v.into_datum(),
             Cell::StringArray(v) => v.into_datum(),
+            Cell::NumericArray(v) => v.into_datum(),
+            Cell::DateArray(v) => v.into_datum(),
+            Cell::TimeArray(v) => v.into_datum(),
+            Cell::TimestampArray(v) => v.into_datum(),
+            Cell::TimestamptzArray(v) => v.into_datum(),
+            Cell::UuidArray(v) => v.into_datum(),
+            Cell::JsonArray(v) => v.into_datum(),
+            Cell::ByteaArray(v) => v.into_datum(),
+            Cell::Composite(v) => v.into_datum(),
+            Cell::Inet(v) => Some(unsafe { text_to_datum(pg_sys::INETOID, &v) }),
+            Cell::Cidr(v) => Some(unsafe { text_to_datum(pg_sys::CIDROID, &v) }),
+            Cell::MacAddr(v) => Some(unsafe { text_to_datum(pg_sys::MACADDROID, &v) }),
+            Cell::I32Range(v) => v.into_datum(),
+            Cell::I64Range(v) => v.into_datum(),
+            Cell::TimestampRange(v) => v.into_datum(),
+            Cell::TimestamptzRange(v) => v.into_datum(),
+            Cell::Enum(oid, v) => Some(unsafe { text_to_datum(oid, &v) }),
+            Cell::TypedText(oid, v) => Some(unsafe { text_to_datum(oid, &v) }),
         }
     }
 
@@ -260,6 +409,21 @@ impl IntoDatum for Cell {
             || other == pg_sys::FLOAT4ARRAYOID
             || other == pg_sys::FLOAT8ARRAYOID
             || other == pg_sys::TEXTARRAYOID
+            || other == pg_sys::NUMERICARRAYOID
+            || other == pg_sys::DATEARRAYOID
+            || other == pg_sys::TIMEARRAYOID
+            || other == pg_sys::TIMESTAMPARRAYOID
+            || other == pg_sys::TIMESTAMPTZARRAYOID
+            || other == pg_sys::UUIDARRAYOID
+            || other == pg_sys::JSONBARRAYOID
+            || other == pg_sys::BYTEAARRAYOID
+            || other == pg_sys::INETOID
+            || other == pg_sys::CIDROID
+            || other == pg_sys::MACADDROID
+            || other == pg_sys::INT4RANGEOID
+            || other == pg_sys::INT8RANGEOID
+            || other == pg_sys::TSRANGEOID
+            || other == pg_sys::TSTZRANGEOID
     }
 }
 
@@ -340,19 +504,198 @@ impl FromDatum for Cell {
             PgOid::BuiltIn(PgBuiltInOids::TEXTARRAYOID) => {
                 Vec::<Option<String>>::from_datum(datum, false).map(Cell::StringArray)
             }
+            PgOid::BuiltIn(PgBuiltInOids::NUMERICARRAYOID) => {
+                Vec::<Option<AnyNumeric>>::from_datum(datum, false).map(Cell::NumericArray)
+            }
+            PgOid::BuiltIn(PgBuiltInOids::DATEARRAYOID) => {
+                Vec::<Option<Date>>::from_datum(datum, false).map(Cell::DateArray)
+            }
+            PgOid::BuiltIn(PgBuiltInOids::TIMEARRAYOID) => {
+                Vec::<Option<Time>>::from_datum(datum, false).map(Cell::TimeArray)
+            }
+            PgOid::BuiltIn(PgBuiltInOids::TIMESTAMPARRAYOID) => {
+                Vec::<Option<Timestamp>>::from_datum(datum, false).map(Cell::TimestampArray)
+            }
+            PgOid::BuiltIn(PgBuiltInOids::TIMESTAMPTZARRAYOID) => {
+                Vec::<Option<TimestampWithTimeZone>>::from_datum(datum, false)
+                    .map(Cell::TimestamptzArray)
+            }
+            PgOid::BuiltIn(PgBuiltInOids::UUIDARRAYOID) => {
+                Vec::<Option<Uuid>>::from_datum(datum, false).map(Cell::UuidArray)
+            }
+            PgOid::BuiltIn(PgBuiltInOids::JSONBARRAYOID) => {
+                Vec::<Option<JsonB>>::from_datum(datum, false).map(Cell::JsonArray)
+            }
+            PgOid::BuiltIn(PgBuiltInOids::BYTEAARRAYOID) => {
+                Vec::<Option<Vec<u8>>>::from_datum(datum, false).map(Cell::ByteaArray)
+            }
+            PgOid::BuiltIn(PgBuiltInOids::INETOID) if !is_null => {
+                Some(Cell::Inet(datum_to_text(datum, typoid)))
+            }
+            PgOid::BuiltIn(PgBuiltInOids::CIDROID) if !is_null => {
+                Some(Cell::Cidr(datum_to_text(datum, typoid)))
+            }
+            PgOid::BuiltIn(PgBuiltInOids::MACADDROID) if !is_null => {
+                Some(Cell::MacAddr(datum_to_text(datum, typoid)))
+            }
+            PgOid::BuiltIn(PgBuiltInOids::INT4RANGEOID) => {
+                Range::<i32>::from_datum(datum, is_null).map(Cell::I32Range)
+            }
+            PgOid::BuiltIn(PgBuiltInOids::INT8RANGEOID) => {
+                Range::<i64>::from_datum(datum, is_null).map(Cell::I64Range)
+            }
+            PgOid::BuiltIn(PgBuiltInOids::TSRANGEOID) => {
+                Range::<Timestamp>::from_datum(datum, is_null).map(Cell::TimestampRange)
+            }
+            PgOid::BuiltIn(PgBuiltInOids::TSTZRANGEOID) => {
+                Range::<TimestampWithTimeZone>::from_datum(datum, is_null)
+                    .map(Cell::TimestamptzRange)
+            }
+            _ if !is_null && pg_sys::type_is_rowtype(typoid) => {
+                Composite::from_composite_datum(datum).map(Cell::Composite)
+            }
+            _ if !is_null && pg_sys::get_typtype(typoid) == pg_sys::TYPTYPE_ENUM as c_char => {
+                Some(Cell::Enum(typoid, datum_to_text(datum, typoid)))
+            }
             _ => None,
         }
     }
 }
 
+/// A composite value, such as a record or struct
+///
+/// The fields are matched to the attributes of the composite type by name,
+/// attributes without a matched field are set to null.
+#[derive(Debug, Clone)]
+pub struct Composite {
+    /// composite type oid, usually it is the target column's type oid
+    pub type_oid: Oid,
+
+    /// field names and cells
+    pub fields: Vec<(String, Option<Cell>)>,
+}
+
+impl Composite {
+    /// Create an empty composite value of the composite type
+    pub fn new(type_oid: Oid) -> Self {
+        Self {
+            type_oid,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Push a field with its name to this composite value
+    pub fn push(&mut self, name: &str, cell: Option<Cell>) {
+        self.fields.push((name.to_owned(), cell));
+    }
+
+    /// Get the attributes of a composite type as a column list, so they can
+    /// be used as the target fields to be fetched from remote
+    pub fn type_columns(type_oid: Oid) -> Vec<Column> {
+        unsafe {
+            let tupdesc = pg_sys::lookup_rowtype_tupdesc_copy(type_oid, -1);
+            let tup_desc = PgTupleDesc::from_pg_unchecked(tupdesc);
+            tup_desc
+                .iter()
+                .filter(|a| !a.attisdropped)
+                .map(|a| Column {
+                    name: pgrx::name_data_to_str(&a.attname).to_string(),
+                    num: a.attnum as usize,
+                    type_oid: a.atttypid,
+                })
+                .collect()
+        }
+    }
+
+    unsafe fn from_composite_datum(datum: Datum) -> Option<Self> {
+        let htup_header = pg_sys::pg_detoast_datum(datum.cast_mut_ptr()) as pg_sys::HeapTupleHeader;
+        let type_oid = (*htup_header).t_choice.t_datum.datum_typeid;
+        let typmod = (*htup_header).t_choice.t_datum.datum_typmod;
+        let tupdesc = pg_sys::lookup_rowtype_tupdesc_copy(type_oid, typmod);
+        let tup_desc = PgTupleDesc::from_pg_unchecked(tupdesc);
+
+        let mut tuple: pg_sys::HeapTupleData = mem::zeroed();
+        tuple.t_len = pgrx::varlena::varsize_any(htup_header as *const pg_sys::varlena) as u32;
+        tuple.t_data = htup_header;
+
+        let natts = tup_desc.len();
+        let mut values = vec![Datum::from(0usize); natts];
+        let mut nulls = vec![true; natts];
+        pg_sys::heap_deform_tuple(&mut tuple, tupdesc, values.as_mut_ptr(), nulls.as_mut_ptr());
+
+        let mut composite = Composite::new(type_oid);
+        for (i, attr) in tup_desc.iter().enumerate() {
+            if attr.attisdropped {
+                continue;
+            }
+            let cell = if nulls[i] {
+                None
+            } else {
+                // field of unsupported type cannot be converted
+                Some(Cell::from_polymorphic_datum(
+                    values[i],
+                    false,
+                    attr.atttypid,
+                )?)
+            };
+            composite.push(pgrx::name_data_to_str(&attr.attname), cell);
+        }
+        Some(composite)
+    }
+}
+
+impl fmt::Display for Composite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let res = self
+            .fields
+            .iter()
+            .map(|(_, cell)| match cell {
+                Some(cell) => format!("{}", cell),
+                None => "null".to_owned(),
+            })
+            .collect::<Vec<String>>()
+            .join(",");
+        write!(f, "({})", res)
+    }
+}
+
+impl IntoDatum for Composite {
+    fn into_datum(self) -> Option<Datum> {
+        unsafe {
+            let tupdesc = pg_sys::lookup_rowtype_tupdesc_copy(self.type_oid, -1);
+            let tup_desc = PgTupleDesc::from_pg_unchecked(tupdesc);
+            let natts = tup_desc.len();
+            let mut values = vec![Datum::from(0usize); natts];
+            let mut nulls = vec![true; natts];
+
+            for (name, cell) in self.fields {
+                let att_idx = tup_desc
+                    .iter()
+                    .position(|a| !a.attisdropped && pgrx::name_data_to_str(&a.attname) == name);
+                if let (Some(att_idx), Some(cell)) = (att_idx, cell) {
+                    values[att_idx] = cell.into_datum()?;
+                    nulls[att_idx] = false;
+                }
+            }
+
+            let tuple = pg_sys::heap_form_tuple(tupdesc, values.as_mut_ptr(), nulls.as_mut_ptr());
+            Some(pg_sys::heap_copy_tuple_as_datum(tuple, tupdesc))
+        }
+    }
+
+    fn type_oid() -> Oid {
+        pg_sys::RECORDOID
+    }
+}
+
 pub trait CellFormatter {
     fn fmt_cell(&mut self, cell: &Cell) -> String;
 }
 
-struct DefaultFormatter {}
+pub(crate) struct DefaultFormatter {}
 
 impl DefaultFormatter {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {}
     }
 }
@@ -363,6 +706,22 @@ impl CellFormatter for DefaultFormatter {
     }
 }
 
+/// An error of converting source data into a [`Row`]
+///
+/// It is handled according to the `on_error` foreign table option, see
+/// [`Row::push_or_reject`] and [`Row::reject`].
+#[derive(Debug, Clone)]
+pub struct RowError {
+    /// column name, or `None` if the whole source record is malformed
+    pub column: Option<String>,
+
+    /// raw source data of the column or the record
+    pub raw: String,
+
+    /// error message
+    pub message: String,
+}
+
 /// A data row in a table
 ///
 /// The row contains a column name list and cell list with same number of
@@ -374,6 +733,9 @@ pub struct Row {
 
     /// column cell list, should match with cols
     pub cells: Vec<Option<Cell>>,
+
+    /// errors of converting source data into this row
+    pub errors: Vec<RowError>,
 }
 
 impl Row {
@@ -388,6 +750,59 @@ impl Row {
         self.cells.push(cell);
     }
 
+    /// Push a cell converted from the raw source data with column name to this row
+    ///
+    /// If the conversion is failed, a null cell is pushed and the error is
+    /// handled according to the `on_error` foreign table option:
+    ///
+    /// - `stop` - raise the error and abort the query, this is the default
+    /// - `null` - set the cell to null
+    /// - `skip` - skip the row
+    ///
+    /// For example,
+    ///
+    /// ```rust,no_run
+    /// # use supabase_wrappers::prelude::*;
+    /// # let mut row = Row::new();
+    /// let raw = "12a";
+    /// row.push_or_reject("id", raw, raw.parse::<i64>().map(|v| Some(Cell::I64(v))));
+    /// ```
+    pub fn push_or_reject<R: fmt::Display, E: fmt::Display>(
+        &mut self,
+        col: &str,
+        raw: R,
+        cell: Result<Option<Cell>, E>,
+    ) {
+        match cell {
+            Ok(cell) => self.push(col, cell),
+            Err(err) => {
+                self.errors.push(RowError {
+                    column: Some(col.to_owned()),
+                    raw: raw.to_string(),
+                    message: err.to_string(),
+                });
+                self.push(col, None);
+            }
+        }
+    }
+
+    /// Reject this row because its raw source record is malformed
+    ///
+    /// The row is skipped, unless the `on_error` foreign table option is
+    /// `stop` and then the error is raised.
+    pub fn reject<R: fmt::Display, E: fmt::Display>(&mut self, raw: R, err: E) {
+        self.errors.push(RowError {
+            column: None,
+            raw: raw.to_string(),
+            message: err.to_string(),
+        });
+    }
+
+    /// Check if this row is rejected as a whole, see [`Row::reject`]
+    pub fn is_rejected(&self) -> bool {
+        self.errors.iter().any(|err| err.column.is_none())
+    }
+
     /// Return a zipped <column_name, cell> iterator
     pub fn iter(&self) -> Zip<Iter<'_, String>, Iter<'_, Option<Cell>>> {
         self.cols.iter().zip(self.cells.iter())
@@ -411,10 +826,11 @@ impl Row {
         let _ = mem::replace(self, src);
     }
 
-    /// Clear the row, removing all column names and cells
+    /// Clear the row, removing all column names, cells and errors
     pub fn clear(&mut self) {
         self.cols.clear();
         self.cells.clear();
+        self.errors.clear();
     }
 }
 
@@ -440,6 +856,20 @@ pub enum Value {
     Array(Vec<Cell>),
 }
 
+/// Query parameter kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    /// external parameter, like `$1` in prepared statement
+    External,
+
+    /// parameter supplied by the outer relation of a nested loop join
+    Exec,
+
+    /// stable expression which doesn't reference any columns, like `now()` or
+    /// `current_date - 7`, it is evaluated once when the scan is begun
+    Stable,
+}
+
 /// Query parameter
 #[derive(Debug, Clone)]
 pub struct Param {
@@ -448,6 +878,20 @@ pub struct Param {
 
     /// parameter type OID
     pub type_oid: Oid,
+
+    /// parameter kind
+    pub kind: ParamKind,
+}
+
+impl Param {
+    /// Create an external parameter, like `$1` in prepared statement
+    pub fn new(id: usize, type_oid: Oid) -> Self {
+        Self {
+            id,
+            type_oid,
+            kind: ParamKind::External,
+        }
+    }
 }
 
 /// Query restrictions, a.k.a conditions in `WHERE` clause
@@ -493,6 +937,25 @@ pub struct Param {
 /// --   Qual { field: "col", operator: "=", value: Cell(String("foo")), use_or: false }
 /// -- ]
 /// ```
+///
+/// Stable expressions without any column references are evaluated when the
+/// scan is begun, and their values are assigned to the quals with [`ParamKind::Stable`]
+/// parameters.
+///
+/// ```sql
+/// where created > now() - interval '7 days';
+/// -- [Qual { field: "created", operator: ">", value: Cell(Timestamptz(...)), use_or: false }]
+/// ```
+///
+/// An expression on the column side is extracted as [`FieldExpr`] if the FDW
+/// allows its functions or operators in [`field_expr_allowlist`](ForeignDataWrapper::field_expr_allowlist),
+/// in which case `field` is the name of the column in the expression.
+///
+/// ```sql
+/// where attrs->>'plan' = 'pro';
+/// -- [Qual { field: "attrs", operator: "=", value: Cell(String("pro")), use_or: false,
+/// --   field_expr: Some(JsonPath { column: "attrs", path: ["plan"], as_text: true }) }]
+/// ```
 #[derive(Debug, Clone)]
 pub struct Qual {
     pub field: String,
@@ -500,6 +963,7 @@ pub struct Qual {
     pub value: Value,
     pub use_or: bool,
     pub param: Option<Param>,
+    pub field_expr: Option<FieldExpr>,
 }
 
 impl Qual {
@@ -509,15 +973,17 @@ impl Qual {
     }
 
     pub fn deparse_with_fmt<T: CellFormatter>(&self, t: &mut T) -> String {
+        let field = match &self.field_expr {
+            Some(expr) => expr.deparse_with_fmt(t),
+            None => self.field.clone(),
+        };
         if self.use_or {
             match &self.value {
                 Value::Cell(_) => unreachable!(),
                 Value::Array(cells) => {
                     let conds: Vec<String> = cells
                         .iter()
-                        .map(|cell| {
-                            format!("{} {} {}", self.field, self.operator, t.fmt_cell(cell))
-                        })
+                        .map(|cell| format!("{} {} {}", field, self.operator, t.fmt_cell(cell)))
                         .collect();
                     conds.join(" or ")
                 }
@@ -527,13 +993,13 @@ impl Qual {
                 Value::Cell(cell) => match self.operator.as_str() {
                     "is" | "is not" => match cell {
                         Cell::String(cell) if cell == "null" => {
-                            format!("{} {} null", self.field, self.operator)
+                            format!("{} {} null", field, self.operator)
                         }
-                        _ => format!("{} {} {}", self.field, self.operator, t.fmt_cell(cell)),
+                        _ => format!("{} {} {}", field, self.operator, t.fmt_cell(cell)),
                     },
-                    "~~" => format!("{} like {}", self.field, t.fmt_cell(cell)),
-                    "!~~" => format!("{} not like {}", self.field, t.fmt_cell(cell)),
-                    _ => format!("{} {} {}", self.field, self.operator, t.fmt_cell(cell)),
+                    "~~" => format!("{} like {}", field, t.fmt_cell(cell)),
+                    "!~~" => format!("{} not like {}", field, t.fmt_cell(cell)),
+                    _ => format!("{} {} {}", field, self.operator, t.fmt_cell(cell)),
                 },
                 Value::Array(_) => unreachable!(),
             }
@@ -541,31 +1007,226 @@ impl Qual {
     }
 }
 
+/// An expression on the column side of a [`Qual`]
+///
+/// ## Examples
+///
+/// ```sql
+/// where lower(email) = 'jo@example.com';
+/// -- Func { name: "lower", args: [Column("email")] }
+/// ```
+///
+/// ```sql
+/// where date_trunc('day', created) = '2025-01-01';
+/// -- Func { name: "date_trunc", args: [Cell(String("day")), Column("created")] }
+/// ```
+///
+/// ```sql
+/// where attrs->'plan'->>'name' = 'pro';
+/// -- JsonPath { column: "attrs", path: ["plan", "name"], as_text: true }
+/// ```
+#[derive(Debug, Clone)]
+pub enum FieldExpr {
+    /// function call, whose arguments include at least one column
+    Func { name: String, args: Vec<FieldArg> },
+
+    /// JSON path extraction on a json or jsonb column, by the `->`, `->>`,
+    /// `#>` or `#>>` operators, `as_text` is true if the result is text
+    JsonPath {
+        column: String,
+        path: Vec<String>,
+        as_text: bool,
+    },
+}
+
+/// An argument of function call in [`FieldExpr`]
+#[derive(Debug, Clone)]
+pub enum FieldArg {
+    Column(String),
+    Cell(Cell),
+    Expr(FieldExpr),
+}
+
+impl FieldExpr {
+    pub fn deparse(&self) -> String {
+        let mut formatter = DefaultFormatter::new();
+        self.deparse_with_fmt(&mut formatter)
+    }
+
+    pub fn deparse_with_fmt<T: CellFormatter>(&self, t: &mut T) -> String {
+        match self {
+            FieldExpr::Func { name, args } => {
+                let args = args
+                    .iter()
+                    .map(|arg| match arg {
+                        FieldArg::Column(col) => col.clone(),
+                        FieldArg::Cell(cell) => t.fmt_cell(cell),
+                        FieldArg::Expr(expr) => expr.deparse_with_fmt(t),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{}({})", name, args)
+            }
+            FieldExpr::JsonPath {
+                column,
+                path,
+                as_text,
+            } => {
+                let (opr, path_opr) = if *as_text {
+                    ("->>", "#>>")
+                } else {
+                    ("->", "#>")
+                };
+                if let [key] = path.as_slice() {
+                    format!("{} {} '{}'", column, opr, key.replace('\'', "''"))
+                } else {
+                    // text array literal of the path, like '{a,"b c"}'
+                    let path = path
+                        .iter()
+                        .map(|key| {
+                            format!("\"{}\"", key.replace('\\', "\\\\").replace('"', "\\\""))
+                        })
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    format!("{} {} '{{{}}}'", column, path_opr, path.replace('\'', "''"))
+                }
+            }
+        }
+    }
+
+    /// Get the first column in the expression
+    pub fn column(&self) -> Option<&str> {
+        match self {
+            FieldExpr::Func { args, .. } => args.iter().find_map(|arg| match arg {
+                FieldArg::Column(col) => Some(col.as_str()),
+                FieldArg::Cell(_) => None,
+                FieldArg::Expr(expr) => expr.column(),
+            }),
+            FieldExpr::JsonPath { column, .. } => Some(column),
+        }
+    }
+
+    // qualify all the columns in the expression with relation alias
+    pub(crate) fn qualify_columns(&mut self, alias: &str) {
+        match self {
+            FieldExpr::Func { args, .. } => {
+                for arg in args.iter_mut() {
+                    match arg {
+                        FieldArg::Column(col) => *col = format!("{}.{}", alias, col),
+                        FieldArg::Cell(_) => {}
+                        FieldArg::Expr(expr) => expr.qualify_columns(alias),
+                    }
+                }
+            }
+            FieldExpr::JsonPath { column, .. } => *column = format!("{}.{}", alias, column),
+        }
+    }
+}
+
+/// A boolean expression of [`Qual`]s in `WHERE` clause, which cannot be
+/// represented by the flat qual list, see [`get_filter_pushdown`](ForeignDataWrapper::get_filter_pushdown)
+///
+/// ## Examples
+///
+/// ```sql
+/// where status = 'paid' or customer = 'cus_1';
+/// -- Or([
+/// --   Qual(Qual { field: "status", operator: "=", value: Cell(String("paid")), use_or: false }),
+/// --   Qual(Qual { field: "customer", operator: "=", value: Cell(String("cus_1")), use_or: false })
+/// -- ])
+/// ```
+///
+/// ```sql
+/// where not (id > 1 and col is null);
+/// -- Not(And([
+/// --   Qual(Qual { field: "id", operator: ">", value: Cell(I32(1)), use_or: false }),
+/// --   Qual(Qual { field: "col", operator: "is", value: Cell(String("null")), use_or: false })
+/// -- ]))
+/// ```
+#[derive(Debug, Clone)]
+pub enum Filter {
+    Qual(Qual),
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    pub fn deparse(&self) -> String {
+        let mut formatter = DefaultFormatter::new();
+        self.deparse_with_fmt(&mut formatter)
+    }
+
+    pub fn deparse_with_fmt<T: CellFormatter>(&self, t: &mut T) -> String {
+        self.deparse_with(|qual| qual.deparse_with_fmt(t))
+    }
+
+    /// Deparse the filter using `deparse_qual` to deparse each qual, the
+    /// operands of `and`, `or` and `not` are enclosed in parentheses
+    pub fn deparse_with<F>(&self, mut deparse_qual: F) -> String
+    where
+        F: FnMut(&Qual) -> String,
+    {
+        self.deparse_inner(&mut deparse_qual)
+    }
+
+    fn deparse_inner(&self, deparse_qual: &mut dyn FnMut(&Qual) -> String) -> String {
+        match self {
+            Filter::Qual(qual) => deparse_qual(qual),
+            Filter::And(args) => Self::deparse_args(args, " and ", deparse_qual),
+            Filter::Or(args) => Self::deparse_args(args, " or ", deparse_qual),
+            Filter::Not(arg) => format!("not ({})", arg.deparse_inner(deparse_qual)),
+        }
+    }
+
+    fn deparse_args(
+        args: &[Filter],
+        sep: &str,
+        deparse_qual: &mut dyn FnMut(&Qual) -> String,
+    ) -> String {
+        args.iter()
+            .map(|arg| format!("({})", arg.deparse_inner(deparse_qual)))
+            .collect::<Vec<_>>()
+            .join(sep)
+    }
+
+    /// Get all the quals in the filter
+    pub fn quals(&self) -> Vec<&Qual> {
+        match self {
+            Filter::Qual(qual) => vec![qual],
+            Filter::And(args) | Filter::Or(args) => {
+                args.iter().flat_map(|arg| arg.quals()).collect()
+            }
+            Filter::Not(arg) => arg.quals(),
+        }
+    }
+}
+
 /// Query sort, a.k.a `ORDER BY` clause
 ///
 /// ## Examples
 ///
 /// ```sql
 /// order by id;
-/// -- [Sort { field: "id", field_no: 1, reversed: false, nulls_first: false, collate: None]
+/// -- [Sort { field: "id", field_no: 1, reversed: false, nulls_first: false, collate: None, collation: None]
 /// ```
 ///
 /// ```sql
 /// order by id desc;
-/// -- [Sort { field: "id", field_no: 1, reversed: true, nulls_first: true, collate: None]
+/// -- [Sort { field: "id", field_no: 1, reversed: true, nulls_first: true, collate: None, collation: None]
 /// ```
 ///
 /// ```sql
 /// order by id desc, col;
 /// -- [
-/// --   Sort { field: "id", field_no: 1, reversed: true, nulls_first: true, collate: None },
-/// --   Sort { field: "col", field_no: 2, reversed: false, nulls_first: false, collate: None }
+/// --   Sort { field: "id", field_no: 1, reversed: true, nulls_first: true, collate: None, collation: None },
+/// --   Sort { field: "col", field_no: 2, reversed: false, nulls_first: false, collate: None, collation: Some("default") }
 /// -- ]
 /// ```
 ///
 /// ```sql
 /// order by id collate "de_DE";
-/// -- [Sort { field: "col", field_no: 2, reversed: false, nulls_first: false, collate: Some("de_DE") }]
+/// -- [Sort { field: "col", field_no: 2, reversed: false, nulls_first: false, collate: Some("de_DE"), collation: Some("de_DE") }]
 /// ```
 #[derive(Debug, Clone, Default)]
 pub struct Sort {
@@ -574,6 +1235,8 @@ pub struct Sort {
     pub reversed: bool,
     pub nulls_first: bool,
     pub collate: Option<String>,
+    /// collation the column is sorted by, `None` if the column is not collatable
+    pub collation: Option<String>,
 }
 
 impl Sort {
@@ -595,6 +1258,17 @@ impl Sort {
         sql
     }
 
+    /// Check if the sort order doesn't depend on the locale, that is the column
+    /// is not collatable or is sorted by "C" or "POSIX" collation. Remote
+    /// servers are not aware of Postgres collations, so only such sorts can be
+    /// pushed down safely.
+    pub fn is_locale_independent(&self) -> bool {
+        match &self.collation {
+            Some(collation) => collation == "C" || collation == "POSIX",
+            None => true,
+        }
+    }
+
     pub fn deparse_with_collate(&self) -> String {
         let mut sql = self.deparse();
 
@@ -631,27 +1305,443 @@ impl Limit {
     }
 }
 
-/// The Foreign Data Wrapper trait
+/// Notifier of an asynchronous foreign scan, see [`begin_async_scan`](ForeignDataWrapper::begin_async_scan)
 ///
-/// This is the main interface for your foreign data wrapper. Required functions
-/// are listed below, all the others are optional.
+/// It can be cloned and sent to other threads, call [`notify`](Self::notify) to
+/// tell Postgres the scan result is ready.
+#[derive(Debug, Clone)]
+pub struct AsyncNotifier {
+    sender: Arc<UnixStream>,
+}
+
+impl AsyncNotifier {
+    // create a notifier and its receiver, which becomes readable when notified
+    #[cfg(not(feature = "pg13"))]
+    pub(crate) fn new() -> std::io::Result<(Self, UnixStream)> {
+        let (sender, receiver) = UnixStream::pair()?;
+        receiver.set_nonblocking(true)?;
+        let notifier = Self {
+            sender: Arc::new(sender),
+        };
+        Ok((notifier, receiver))
+    }
+
+    /// Notify Postgres that rows can be read by [`iter_scan`](ForeignDataWrapper::iter_scan)
+    /// without waiting
+    pub fn notify(&self) {
+        // the receiver is closed if the scan is already ended, it is safe to
+        // ignore the error
+        let _ = (&*self.sender).write_all(&[1]);
+    }
+}
+
+// shared state of a parallel foreign scan in dynamic shared memory, it is
+// followed by the data made by the leader
+#[repr(C)]
+pub(crate) struct ParallelScanShared {
+    next_unit: AtomicUsize,
+    data_len: usize,
+}
+
+/// Shared state of a parallel foreign scan, see [`begin_parallel_scan`](ForeignDataWrapper::begin_parallel_scan)
 ///
-/// 1. new
-/// 2. begin_scan
-/// 3. iter_scan
-/// 4. end_scan
+/// It lives in dynamic shared memory and is shared by the leader and all the
+/// parallel workers, it must not be used after [`end_scan`](ForeignDataWrapper::end_scan).
+#[derive(Debug, Clone)]
+pub struct ParallelScan {
+    shared: *mut ParallelScanShared,
+}
+
+impl ParallelScan {
+    // the size of shared memory needed to keep the data
+    pub(crate) fn estimate_size(data_len: usize) -> usize {
+        mem::size_of::<ParallelScanShared>() + data_len
+    }
+
+    // initialize the shared memory at `coordinate` and copy the data into it
+    pub(crate) unsafe fn initialize(coordinate: *mut std::ffi::c_void, data: &[u8]) -> Self {
+        let shared = coordinate as *mut ParallelScanShared;
+        shared.write(ParallelScanShared {
+            next_unit: AtomicUsize::new(0),
+            data_len: data.len(),
+        });
+        std::ptr::copy_nonoverlapping(data.as_ptr(), shared.add(1) as *mut u8, data.len());
+        Self { shared }
+    }
+
+    // attach to the shared memory initialized by the leader
+    pub(crate) fn attach(coordinate: *mut std::ffi::c_void) -> Self {
+        Self {
+            shared: coordinate as *mut ParallelScanShared,
+        }
+    }
+
+    // reset the work units so they can be claimed again in rescan
+    pub(crate) fn reset(&self) {
+        unsafe { (*self.shared).next_unit.store(0, Ordering::SeqCst) }
+    }
+
+    /// The data made by [`init_parallel_scan`](ForeignDataWrapper::init_parallel_scan)
+    pub fn data(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.shared.add(1) as *const u8, (*self.shared).data_len) }
+    }
+
+    /// Claim the next work unit out of `total` units, the returned index is
+    /// unique among the leader and all the workers
+    ///
+    /// Returns `None` when all the work units have been claimed.
+    pub fn claim_unit(&self, total: usize) -> Option<usize> {
+        let unit = unsafe { (*self.shared).next_unit.fetch_add(1, Ordering::SeqCst) };
+        (unit < total).then_some(unit)
+    }
+}
+
+/// Query pushdown capability of a foreign scan, which is reported by
+/// [`get_scan_pushdown`](ForeignDataWrapper::get_scan_pushdown)
 ///
-/// See the module-level document for more details.
+/// ## Examples
 ///
-pub trait ForeignDataWrapper<E: Into<ErrorReport>> {
-    /// Create a FDW instance
-    ///
-    /// `options` is the key-value pairs defined in `CREATE SERVER` SQL. For example,
-    ///
-    /// ```sql
-    /// create server my_helloworld_server
-    ///   foreign data wrapper wrappers_helloworld
-    ///   options (
+/// ```sql
+/// where id = 42 and name like '%foo' order by id limit 10;
+/// -- if only `id = 42` and the sort can be applied remotely
+/// -- Pushdown { quals: [0], sorts: true, limit: false }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Pushdown {
+    /// indexes of the quals applied remotely
+    pub quals: Vec<usize>,
+
+    /// if the rows are returned in the order of the sorts
+    pub sorts: bool,
+
+    /// if the limit is applied remotely
+    pub limit: bool,
+}
+
+/// The `EXPLAIN` options, see [`ForeignDataWrapper::explain_scan`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExplainOptions {
+    /// if `VERBOSE` is specified
+    pub verbose: bool,
+
+    /// if `ANALYZE` is specified, the scan has been executed
+    pub analyze: bool,
+}
+
+/// A property of foreign scan shown in `EXPLAIN` output, see
+/// [`ForeignDataWrapper::explain_scan`]
+///
+/// For example,
+///
+/// ```rust,no_run
+/// # use supabase_wrappers::prelude::*;
+/// let props = vec![
+///     ExplainProperty::Text("Remote SQL".to_string(), "select id from foo".to_string()),
+///     ExplainProperty::Integer("Pages Fetched".to_string(), 2),
+/// ];
+/// ```
+#[derive(Debug, Clone)]
+pub enum ExplainProperty {
+    /// a text property with label and value
+    Text(String, String),
+
+    /// an integer property with label and value
+    Integer(String, i64),
+}
+
+/// Aggregate function kind, see [`Aggregate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateKind {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AggregateKind::Count => "count",
+            AggregateKind::Sum => "sum",
+            AggregateKind::Avg => "avg",
+            AggregateKind::Min => "min",
+            AggregateKind::Max => "max",
+        }
+    }
+}
+
+/// Query aggregate, a.k.a aggregate function call in `SELECT` list
+///
+/// Only simple aggregates on a plain column or `count(*)` are supported, aggregates
+/// with `DISTINCT`, `FILTER` or `ORDER BY` are not pushed down.
+///
+/// ## Examples
+///
+/// ```sql
+/// select count(*), sum(amount) from orders group by customer;
+/// -- [
+/// --   Aggregate { kind: Count, column: None, type_oid: 20, alias: "_wrappers_agg_1" },
+/// --   Aggregate { kind: Sum, column: Some(Column { name: "amount", num: 2, type_oid: 20 }), type_oid: 1700, alias: "_wrappers_agg_2" }
+/// -- ]
+/// ```
+#[derive(Debug, Clone)]
+pub struct Aggregate {
+    /// aggregate function kind
+    pub kind: AggregateKind,
+
+    /// aggregated column, `None` for `count(*)`
+    pub column: Option<Column>,
+
+    /// aggregate result type OID
+    pub type_oid: Oid,
+
+    /// unique alias of the aggregate result, can be used as column alias in remote query
+    pub alias: String,
+}
+
+impl Aggregate {
+    pub fn deparse(&self) -> String {
+        match &self.column {
+            Some(col) => format!("{}({})", self.kind.name(), col.name),
+            None => format!("{}(*)", self.kind.name()),
+        }
+    }
+
+    pub fn deparse_with_alias(&self) -> String {
+        format!("{} as {}", self.deparse(), self.alias)
+    }
+
+    /// Return the aggregate result as a column, which is named by the alias
+    pub fn as_column(&self) -> Column {
+        Column {
+            name: self.alias.clone(),
+            num: 0,
+            type_oid: self.type_oid,
+        }
+    }
+}
+
+/// Join type, see [`Join`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinType {
+    Inner,
+    Left,
+    Right,
+    Full,
+}
+
+impl JoinType {
+    pub fn deparse(&self) -> &'static str {
+        match self {
+            JoinType::Inner => "inner join",
+            JoinType::Left => "left join",
+            JoinType::Right => "right join",
+            JoinType::Full => "full join",
+        }
+    }
+}
+
+/// A foreign table participating in a join, see [`Join`]
+#[derive(Debug, Clone)]
+pub struct JoinRelation {
+    /// relation alias used in remote query, e.g. `_wrappers_outer`
+    pub alias: String,
+
+    /// restrictions on this relation, the field names are qualified by the alias
+    pub quals: Vec<Qual>,
+
+    /// the options defined when `CREATE FOREIGN TABLE`
+    pub options: HashMap<String, String>,
+}
+
+/// Join condition between the outer and inner relations, see [`Join`]
+///
+/// ## Examples
+///
+/// ```sql
+/// select * from orders o join customers c on o.cust_id = c.id;
+/// -- JoinQual { outer_field: "cust_id", operator: "=", inner_field: "id" }
+/// ```
+#[derive(Debug, Clone)]
+pub struct JoinQual {
+    /// field name of the outer relation
+    pub outer_field: String,
+    pub operator: String,
+    /// field name of the inner relation
+    pub inner_field: String,
+}
+
+/// Target column of a join, see [`Join`]
+#[derive(Debug, Clone)]
+pub struct JoinColumn {
+    /// column of the foreign table
+    pub column: Column,
+
+    /// `true` if the column belongs to the outer relation, otherwise the inner one
+    pub is_outer: bool,
+
+    /// unique alias of the column, can be used as column alias in remote query
+    pub alias: String,
+}
+
+/// Query join between two foreign tables on the same foreign server
+///
+/// Only joins with plain column conditions are supported, the join is not pushed
+/// down if any of the join or relation restrictions cannot be pushed down.
+///
+/// ## Examples
+///
+/// ```sql
+/// select o.id, c.name from orders o left join customers c on o.cust_id = c.id;
+/// -- Join {
+/// --   join_type: Left,
+/// --   outer: JoinRelation { alias: "_wrappers_outer", quals: [], options: {"table": "orders"} },
+/// --   inner: JoinRelation { alias: "_wrappers_inner", quals: [], options: {"table": "customers"} },
+/// --   quals: [JoinQual { outer_field: "cust_id", operator: "=", inner_field: "id" }],
+/// --   columns: [
+/// --     JoinColumn { column: Column { name: "id", num: 1, type_oid: 20 }, is_outer: true, alias: "_wrappers_col_1" },
+/// --     JoinColumn { column: Column { name: "name", num: 2, type_oid: 25 }, is_outer: false, alias: "_wrappers_col_2" }
+/// --   ]
+/// -- }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Join {
+    pub join_type: JoinType,
+
+    /// outer (left) relation
+    pub outer: JoinRelation,
+
+    /// inner (right) relation
+    pub inner: JoinRelation,
+
+    /// join conditions, a.k.a `ON` clause
+    pub quals: Vec<JoinQual>,
+
+    /// target columns of the join
+    pub columns: Vec<JoinColumn>,
+}
+
+impl Join {
+    /// Deparse the target list, e.g. `_wrappers_outer.id as _wrappers_col_1`,
+    /// or `null` if no column is needed
+    pub fn deparse_columns(&self) -> String {
+        if self.columns.is_empty() {
+            return "null".to_string();
+        }
+        self.columns
+            .iter()
+            .map(|col| {
+                let rel = if col.is_outer {
+                    &self.outer
+                } else {
+                    &self.inner
+                };
+                format!("{}.{} as {}", rel.alias, col.column.name, col.alias)
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    /// Deparse the `FROM` clause without the `FROM` keyword, restrictions on the
+    /// nullable side of an outer join are deparsed into the `ON` clause
+    ///
+    /// - `outer_table` - remote table name of the outer relation
+    /// - `inner_table` - remote table name of the inner relation
+    /// - `deparse_qual` - function to deparse a relation restriction
+    pub fn deparse_from<F>(
+        &self,
+        outer_table: &str,
+        inner_table: &str,
+        mut deparse_qual: F,
+    ) -> String
+    where
+        F: FnMut(&Qual) -> String,
+    {
+        let mut conds = self
+            .quals
+            .iter()
+            .map(|q| {
+                format!(
+                    "{}.{} {} {}.{}",
+                    self.outer.alias, q.outer_field, q.operator, self.inner.alias, q.inner_field
+                )
+            })
+            .collect::<Vec<String>>();
+        if matches!(self.join_type, JoinType::Right | JoinType::Full) {
+            conds.extend(self.outer.quals.iter().map(&mut deparse_qual));
+        }
+        if matches!(self.join_type, JoinType::Left | JoinType::Full) {
+            conds.extend(self.inner.quals.iter().map(&mut deparse_qual));
+        }
+
+        format!(
+            "{} as {} {} {} as {} on {}",
+            outer_table,
+            self.outer.alias,
+            self.join_type.deparse(),
+            inner_table,
+            self.inner.alias,
+            conds.join(" and ")
+        )
+    }
+
+    /// Deparse the `WHERE` clause without the `WHERE` keyword, only restrictions
+    /// on the non-nullable side are included, return `None` if there is none
+    ///
+    /// - `deparse_qual` - function to deparse a relation restriction
+    pub fn deparse_where<F>(&self, mut deparse_qual: F) -> Option<String>
+    where
+        F: FnMut(&Qual) -> String,
+    {
+        let mut conds = Vec::new();
+        if matches!(self.join_type, JoinType::Inner | JoinType::Left) {
+            conds.extend(self.outer.quals.iter().map(&mut deparse_qual));
+        }
+        if matches!(self.join_type, JoinType::Inner | JoinType::Right) {
+            conds.extend(self.inner.quals.iter().map(&mut deparse_qual));
+        }
+        if conds.is_empty() {
+            None
+        } else {
+            Some(conds.join(" and "))
+        }
+    }
+
+    /// Return the target columns as output columns, which are named by the aliases
+    pub fn output_columns(&self) -> Vec<Column> {
+        self.columns
+            .iter()
+            .map(|col| Column {
+                name: col.alias.clone(),
+                num: 0,
+                type_oid: col.column.type_oid,
+            })
+            .collect()
+    }
+}
+
+/// The Foreign Data Wrapper trait
+///
+/// This is the main interface for your foreign data wrapper. Required functions
+/// are listed below, all the others are optional.
+///
+/// 1. new
+/// 2. begin_scan
+/// 3. iter_scan
+/// 4. end_scan
+///
+/// See the module-level document for more details.
+///
+pub trait ForeignDataWrapper<E: Into<ErrorReport>> {
+    /// Create a FDW instance
+    ///
+    /// `options` is the key-value pairs defined in `CREATE SERVER` SQL. For example,
+    ///
+    /// ```sql
+    /// create server my_helloworld_server
+    ///   foreign data wrapper wrappers_helloworld
+    ///   options (
     ///     foo 'bar'
     /// );
     /// ```
@@ -682,6 +1772,81 @@ pub trait ForeignDataWrapper<E: Into<ErrorReport>> {
         Ok((0, 0))
     }
 
+    /// Obtain the columns which can be used to look up rows by value
+    ///
+    /// - `options` - the options defined when `CREATE FOREIGN TABLE`
+    ///
+    /// When a join clause, like `ftbl.id = tbl.ftbl_id`, is on one of these
+    /// columns, the foreign table can be the inner side of a nested loop join.
+    /// For each row of the outer relation, [`begin_scan`](Self::begin_scan) is
+    /// called with a [`Qual`] whose `param` kind is [`ParamKind::Exec`] and value
+    /// is taken from the outer row.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-planning.html).
+    fn get_lookup_columns(&mut self, _options: &HashMap<String, String>) -> Result<Vec<String>, E> {
+        Ok(Vec::new())
+    }
+
+    /// Obtain the quals, sorts and limit which can be applied by the foreign source
+    ///
+    /// - `quals` - `WHERE` clause to be pushed down
+    /// - `sorts` - `ORDER BY` clause to be pushed down
+    /// - `limit` - `LIMIT` clause to be pushed down
+    /// - `options` - the options defined when `CREATE FOREIGN TABLE`
+    ///
+    /// All the quals are still passed to [`begin_scan`](Self::begin_scan) and
+    /// they are always rechecked locally, the reported [`Pushdown`] is shown as
+    /// `Remote Filter`, `Remote Sort` and `Remote Limit` in `EXPLAIN` output. If
+    /// the sorts are accepted, Postgres will not sort the scan result again,
+    /// otherwise they are not passed to [`begin_scan`](Self::begin_scan). Once
+    /// reported, the limit is only passed to [`begin_scan`](Self::begin_scan) when
+    /// the sorts are accepted as well and all the restrictions are applied
+    /// remotely. Return `None` if not reported, which is the default, then the
+    /// sorts and limit are passed as they are.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-planning.html).
+    fn get_scan_pushdown(
+        &mut self,
+        _quals: &[Qual],
+        _sorts: &[Sort],
+        _limit: &Option<Limit>,
+        _options: &HashMap<String, String>,
+    ) -> Result<Option<Pushdown>, E> {
+        Ok(None)
+    }
+
+    /// Obtain the estimated costs of a foreign scan
+    ///
+    /// - `quals` - `WHERE` clause pushed down
+    /// - `sorts` - `ORDER BY` clause pushed down
+    /// - `limit` - `LIMIT` clause pushed down
+    /// - `rows` - estimated number of rows returned by the scan
+    /// - `options` - the options defined when `CREATE SERVER`, overridden by
+    ///   those defined when `CREATE FOREIGN TABLE`
+    ///
+    /// Return a tuple of startup cost and total cost. By default, the startup
+    /// cost is the `startup_cost` or `fdw_startup_cost` option, and each row costs
+    /// the `fdw_tuple_cost` option. See [`DEFAULT_FDW_STARTUP_COST`] and
+    /// [`DEFAULT_FDW_TUPLE_COST`] for their default values.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-planning.html).
+    fn get_path_costs(
+        &mut self,
+        _quals: &[Qual],
+        _sorts: &[Sort],
+        _limit: &Option<Limit>,
+        rows: f64,
+        options: &HashMap<String, String>,
+    ) -> Result<(f64, f64), E> {
+        let startup_cost =
+            require_option_f64_or("fdw_startup_cost", options, DEFAULT_FDW_STARTUP_COST)
+                .and_then(|cost| require_option_f64_or("startup_cost", options, cost))
+                .report_unwrap();
+        let tuple_cost = require_option_f64_or("fdw_tuple_cost", options, DEFAULT_FDW_TUPLE_COST)
+            .report_unwrap();
+        Ok((startup_cost, startup_cost + rows * tuple_cost))
+    }
+
     /// Called when begin executing a foreign scan
     ///
     /// - `quals` - `WHERE` clause pushed down
@@ -704,6 +1869,10 @@ pub trait ForeignDataWrapper<E: Into<ErrorReport>> {
     ///
     /// FDW must save fetched foreign data into the [`Row`], or return `None` if no more rows to read.
     ///
+    /// Errors of converting the source data can be saved into the row by
+    /// [`Row::push_or_reject`] or [`Row::reject`], they are handled according
+    /// to the `on_error` foreign table option instead of aborting the query.
+    ///
     /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-SCAN).
     fn iter_scan(&mut self, row: &mut Row) -> Result<Option<()>, E>;
 
@@ -719,6 +1888,289 @@ pub trait ForeignDataWrapper<E: Into<ErrorReport>> {
     /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-SCAN).
     fn end_scan(&mut self) -> Result<(), E>;
 
+    /// Obtain the properties of a foreign scan to be shown in `EXPLAIN` output
+    ///
+    /// - `quals` - `WHERE` clause pushed down
+    /// - `columns` - target columns to be queried
+    /// - `sorts` - `ORDER BY` clause pushed down
+    /// - `limit` - `LIMIT` clause pushed down
+    /// - `options` - the options defined when `CREATE FOREIGN TABLE`
+    /// - `explain` - the `EXPLAIN` options
+    ///
+    /// It is called after [`begin_scan`](Self::begin_scan) is called, except
+    /// for plain `EXPLAIN` without `ANALYZE`, in which case the scan is not
+    /// begun and the FDW should not fetch any data from remote. The returned
+    /// properties, such as the remote query or the number of fetched pages,
+    /// are shown in the order they are returned. For aggregate or join
+    /// pushdown, it is only called with `ANALYZE` and the properties should
+    /// be built from the state saved in the FDW instance. Return empty list
+    /// if nothing to show, which is the default.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-EXPLAIN).
+    fn explain_scan(
+        &mut self,
+        _quals: &[Qual],
+        _columns: &[Column],
+        _sorts: &[Sort],
+        _limit: &Option<Limit>,
+        _options: &HashMap<String, String>,
+        _explain: &ExplainOptions,
+    ) -> Result<Vec<ExplainProperty>, E> {
+        Ok(Vec::new())
+    }
+
+    /// Returns the functions and JSON operators which can be used on the
+    /// column side of quals
+    ///
+    /// Quals like `lower(email) = 'x'` or `attrs->>'plan' = 'pro'` are only
+    /// extracted if all the function names or JSON path operators (`->`, `->>`,
+    /// `#>` and `#>>`) in the column side expression are in the list, the
+    /// expression is then given in [`Qual::field_expr`]. Other quals are
+    /// applied locally as usual. Return empty list by default, so no such
+    /// quals are extracted.
+    ///
+    /// For example,
+    ///
+    /// ```rust,no_run
+    /// fn field_expr_allowlist() -> &'static [&'static str] {
+    ///     &["lower", "upper", "->>"]
+    /// }
+    /// ```
+    fn field_expr_allowlist() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Called when planning a foreign scan, to decide whether the filters can
+    /// be applied remotely
+    ///
+    /// - `filters` - boolean expressions in `WHERE` clause, like `a = 1 or b = 2`,
+    ///   which cannot be represented by the flat quals. They are ANDed with the
+    ///   quals.
+    /// - `options` - the options defined when `CREATE FOREIGN TABLE`
+    ///
+    /// It is called before [`get_scan_pushdown`](Self::get_scan_pushdown) and
+    /// only when there are filters. Filters with query parameters are not
+    /// extracted. Return true if all the filters will be applied remotely, in
+    /// which case the FDW should save the filters and apply them in
+    /// [`begin_scan`](Self::begin_scan) and
+    /// [`begin_aggregate_scan`](Self::begin_aggregate_scan). The filters are
+    /// always rechecked locally, the result only decides whether the limit and
+    /// aggregates can be pushed down. Return false by default.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-planning.html).
+    fn get_filter_pushdown(
+        &mut self,
+        _filters: &[Filter],
+        _options: &HashMap<String, String>,
+    ) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    /// Called when planning an aggregate query on the foreign table, to decide
+    /// whether the aggregation can be pushed down to the foreign source
+    ///
+    /// - `quals` - `WHERE` clause, all of them must be applied remotely
+    /// - `group_by` - `GROUP BY` columns
+    /// - `aggregates` - aggregates in `SELECT` list
+    /// - `options` - the options defined when `CREATE FOREIGN TABLE`
+    ///
+    /// Return `true` to accept the pushdown, [`begin_aggregate_scan`](Self::begin_aggregate_scan)
+    /// will then be called instead of [`begin_scan`](Self::begin_scan). Aggregation is
+    /// not pushed down by default.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-UPPER-PLANNING).
+    fn get_aggregate_pushdown(
+        &mut self,
+        _quals: &[Qual],
+        _group_by: &[Column],
+        _aggregates: &[Aggregate],
+        _options: &HashMap<String, String>,
+    ) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    /// Called when begin executing a foreign scan with aggregate pushed down
+    ///
+    /// - `quals` - `WHERE` clause pushed down
+    /// - `group_by` - `GROUP BY` columns pushed down
+    /// - `aggregates` - aggregates pushed down
+    /// - `options` - the options defined when `CREATE FOREIGN TABLE`
+    ///
+    /// Each row returned by [`iter_scan`](Self::iter_scan) is one aggregated group,
+    /// which must contain the `group_by` cells followed by the `aggregates` cells,
+    /// in the same order as they are given.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-UPPER-PLANNING).
+    fn begin_aggregate_scan(
+        &mut self,
+        _quals: &[Qual],
+        _group_by: &[Column],
+        _aggregates: &[Aggregate],
+        _options: &HashMap<String, String>,
+    ) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Called when planning a join between two foreign tables on the same foreign
+    /// server, to decide whether the join can be pushed down to the foreign source
+    ///
+    /// - `join` - the join to be pushed down, see [`Join`]
+    ///
+    /// Return `true` to accept the pushdown, [`begin_join_scan`](Self::begin_join_scan)
+    /// will then be called instead of [`begin_scan`](Self::begin_scan). Join is not
+    /// pushed down by default.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-JOIN-SCAN).
+    fn get_join_pushdown(&mut self, _join: &Join) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    /// Called when begin executing a foreign scan with join pushed down
+    ///
+    /// - `join` - the join pushed down, see [`Join`]
+    ///
+    /// Each row returned by [`iter_scan`](Self::iter_scan) is one joined row, which
+    /// must contain the cells of `join.columns` in the same order as they are given.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-JOIN-SCAN).
+    fn begin_join_scan(&mut self, _join: &Join) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Called when planning a foreign scan, to decide whether it can be executed
+    /// asynchronously
+    ///
+    /// - `options` - the options defined when `CREATE FOREIGN TABLE`
+    ///
+    /// Return `true` to accept, when the foreign table is scanned under an `Append`
+    /// node, like a partition of partitioned table or a branch of `UNION ALL`,
+    /// [`begin_async_scan`](Self::begin_async_scan) will be called instead of
+    /// [`begin_scan`](Self::begin_scan). Asynchronous execution is not used by
+    /// default, and it is only available on Postgres 14 and later.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-ASYNC).
+    fn is_async_capable(&mut self, _options: &HashMap<String, String>) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    /// Called when begin executing an asynchronous foreign scan
+    ///
+    /// - `quals` - `WHERE` clause pushed down
+    /// - `columns` - target columns to be queried
+    /// - `sorts` - `ORDER BY` clause pushed down
+    /// - `limit` - `LIMIT` clause pushed down
+    /// - `options` - the options defined when `CREATE FOREIGN TABLE`
+    /// - `notifier` - notifier to tell Postgres the scan result is ready
+    ///
+    /// The FDW should start the remote requests in background and return
+    /// immediately, then call [`AsyncNotifier::notify`] when rows can be read by
+    /// [`iter_scan`](Self::iter_scan). Postgres will scan other foreign tables
+    /// while waiting. The background work must not call any Postgres functions.
+    ///
+    /// By default, it calls [`begin_scan`](Self::begin_scan) and notifies immediately.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-ASYNC).
+    fn begin_async_scan(
+        &mut self,
+        quals: &[Qual],
+        columns: &[Column],
+        sorts: &[Sort],
+        limit: &Option<Limit>,
+        options: &HashMap<String, String>,
+        notifier: &AsyncNotifier,
+    ) -> Result<(), E> {
+        self.begin_scan(quals, columns, sorts, limit, options)?;
+        notifier.notify();
+        Ok(())
+    }
+
+    /// Called when planning a foreign scan, to decide whether it can be executed
+    /// in parallel workers
+    ///
+    /// - `options` - the options defined when `CREATE FOREIGN TABLE`
+    ///
+    /// Return `true` to accept, then [`get_parallel_workers`](Self::get_parallel_workers)
+    /// will be called to plan a parallel scan. The FDW instance must be able to
+    /// be created in parallel workers. It is not accepted by default.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-PARALLEL).
+    fn is_parallel_safe(_options: &HashMap<String, String>) -> bool
+    where
+        Self: Sized,
+    {
+        false
+    }
+
+    /// Called when planning a parallel-safe foreign scan, to get the number of
+    /// parallel workers to scan the foreign table
+    ///
+    /// - `quals` - `WHERE` clause pushed down
+    /// - `options` - the options defined when `CREATE FOREIGN TABLE`
+    ///
+    /// The number is capped by `max_parallel_workers_per_gather`, no parallel
+    /// scan is planned if it is 0, which is the default.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-PARALLEL).
+    fn get_parallel_workers(
+        &mut self,
+        _quals: &[Qual],
+        _options: &HashMap<String, String>,
+    ) -> Result<usize, E> {
+        Ok(0)
+    }
+
+    /// Called in the leader before parallel workers are launched, to make the
+    /// data shared by the leader and all the workers
+    ///
+    /// - `quals` - `WHERE` clause pushed down
+    /// - `columns` - target columns to be queried
+    /// - `options` - the options defined when `CREATE FOREIGN TABLE`
+    ///
+    /// The data is copied to dynamic shared memory and can be read by
+    /// [`ParallelScan::data`], it usually describes the work units to be
+    /// claimed, like a list of files. By default, no data is shared.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-PARALLEL).
+    fn init_parallel_scan(
+        &mut self,
+        _quals: &[Qual],
+        _columns: &[Column],
+        _options: &HashMap<String, String>,
+    ) -> Result<Vec<u8>, E> {
+        Ok(Vec::new())
+    }
+
+    /// Called when begin executing a parallel foreign scan, in the leader and
+    /// each parallel worker
+    ///
+    /// - `quals` - `WHERE` clause pushed down
+    /// - `columns` - target columns to be queried
+    /// - `options` - the options defined when `CREATE FOREIGN TABLE`
+    /// - `scan` - shared state of the parallel scan
+    ///
+    /// The FDW should call [`ParallelScan::claim_unit`] to claim work units and
+    /// only return rows of the claimed units in [`iter_scan`](Self::iter_scan),
+    /// so each row is returned by exactly one participant. Return `false` if
+    /// there is nothing to scan, then `iter_scan` and `end_scan` will not be called.
+    ///
+    /// By default, the whole foreign table is scanned by [`begin_scan`](Self::begin_scan)
+    /// in the first participant.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-PARALLEL).
+    fn begin_parallel_scan(
+        &mut self,
+        quals: &[Qual],
+        columns: &[Column],
+        options: &HashMap<String, String>,
+        scan: &ParallelScan,
+    ) -> Result<bool, E> {
+        if scan.claim_unit(1).is_none() {
+            return Ok(false);
+        }
+        self.begin_scan(quals, columns, &[], &None, options)?;
+        Ok(true)
+    }
+
     /// Called when begin executing a foreign table modification operation.
     ///
     /// - `options` - the options defined when `CREATE FOREIGN TABLE`
@@ -754,6 +2206,22 @@ pub trait ForeignDataWrapper<E: Into<ErrorReport>> {
         Ok(())
     }
 
+    /// Called when insert a batch of rows into the foreign table
+    ///
+    /// - rows - the new rows to be inserted
+    ///
+    /// The batch size is set by the `batch_size` foreign table or server option,
+    /// defaults to 1. The default implementation calls [`insert`](Self::insert)
+    /// for each row.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-UPDATE).
+    fn insert_batch(&mut self, rows: &[Row]) -> Result<(), E> {
+        for row in rows {
+            self.insert(row)?;
+        }
+        Ok(())
+    }
+
     /// Called when update one row into the foreign table
     ///
     /// - rowid - the `rowid_column` cell
@@ -773,6 +2241,68 @@ pub trait ForeignDataWrapper<E: Into<ErrorReport>> {
         Ok(())
     }
 
+    /// Called when insert one row into the foreign table with `RETURNING` clause
+    ///
+    /// - row - the new row to be inserted
+    /// - columns - the columns referenced by `RETURNING` clause
+    /// - returning - the output row, fill it with the cells of `columns` returned
+    ///   from the foreign source, e.g. server generated id
+    ///
+    /// The columns not filled in `returning` keep the values of inserted row. The
+    /// default implementation calls [`insert`](Self::insert) and fills nothing.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-UPDATE).
+    fn insert_returning(
+        &mut self,
+        row: &Row,
+        _columns: &[Column],
+        _returning: &mut Row,
+    ) -> Result<(), E> {
+        self.insert(row)
+    }
+
+    /// Called when update one row into the foreign table with `RETURNING` clause
+    ///
+    /// - rowid - the `rowid_column` cell
+    /// - new_row - the new row with updated cells
+    /// - columns - the columns referenced by `RETURNING` clause
+    /// - returning - the output row, fill it with the cells of `columns` returned
+    ///   from the foreign source
+    ///
+    /// The columns not filled in `returning` keep the values of updated row. The
+    /// default implementation calls [`update`](Self::update) and fills nothing.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-UPDATE).
+    fn update_returning(
+        &mut self,
+        rowid: &Cell,
+        new_row: &Row,
+        _columns: &[Column],
+        _returning: &mut Row,
+    ) -> Result<(), E> {
+        self.update(rowid, new_row)
+    }
+
+    /// Called when delete one row into the foreign table with `RETURNING` clause
+    ///
+    /// - rowid - the `rowid_column` cell
+    /// - columns - the columns referenced by `RETURNING` clause
+    /// - returning - the output row, fill it with the cells of `columns` of the
+    ///   deleted row
+    ///
+    /// The columns not filled in `returning` are null. The default implementation
+    /// calls [`delete`](Self::delete) and fills nothing.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-UPDATE).
+    fn delete_returning(
+        &mut self,
+        rowid: &Cell,
+        _columns: &[Column],
+        _returning: &mut Row,
+    ) -> Result<(), E> {
+        self.delete(rowid)
+    }
+
     /// Called when end the table update
     ///
     /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-UPDATE).
@@ -780,6 +2310,127 @@ pub trait ForeignDataWrapper<E: Into<ErrorReport>> {
         Ok(())
     }
 
+    /// Called when truncate foreign tables
+    ///
+    /// - `tables` - the options of each foreign table to be truncated, all of
+    ///   them belong to the same foreign server
+    /// - `restart_seqs` - if `RESTART IDENTITY` is specified
+    /// - `cascade` - if `CASCADE` is specified
+    ///
+    /// An error is raised by default, because truncate is not supported. It is
+    /// only available on Postgres 14 and later.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-TRUNCATE).
+    fn truncate(
+        &mut self,
+        _tables: &[HashMap<String, String>],
+        _restart_seqs: bool,
+        _cascade: bool,
+    ) -> Result<(), E> {
+        report_error(
+            PgSqlErrorCode::ERRCODE_FEATURE_NOT_SUPPORTED,
+            "truncate is not supported by the foreign data wrapper",
+        );
+        Ok(())
+    }
+
+    /// Check if the transaction hooks should be called on this instance
+    ///
+    /// This is checked after [`begin_modify`](Self::begin_modify), return `true`
+    /// to have the transaction hooks, such as [`on_xact_commit`](Self::on_xact_commit),
+    /// called on this instance. `PREPARE TRANSACTION` is not allowed after such
+    /// instances have modified foreign tables. Return `false` by default.
+    fn has_xact_hooks(&self) -> bool {
+        false
+    }
+
+    /// Called before the local transaction commits
+    ///
+    /// This is only called on the FDW instances which have modified foreign
+    /// tables in the transaction and [`has_xact_hooks`](Self::has_xact_hooks)
+    /// returns `true`, those instances are kept alive until the transaction
+    /// ends. A remote transaction can be committed, or the buffered
+    /// writes can be flushed here.
+    ///
+    /// Returning an error will abort the local transaction, and then
+    /// [`on_xact_abort`](Self::on_xact_abort) will be called.
+    ///
+    /// The remote commit is atomic with the local transaction only if there is
+    /// at most one such instance in the transaction. Note that each statement
+    /// has its own instance. Multiple instances are committed one by one in
+    /// the order they first modified foreign tables, there is no two-phase
+    /// commit. If one of them returns an error, [`on_xact_abort`](Self::on_xact_abort)
+    /// is called on it and the ones not committed yet, but the instances
+    /// already committed are not rolled back.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/postgres-fdw.html#POSTGRES-FDW-TRANSACTION-MANAGEMENT).
+    fn on_xact_commit(&mut self) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Called when the local transaction aborts
+    ///
+    /// A remote transaction can be rolled back, or the buffered writes can be
+    /// discarded here. Error returned from this function is reported as a
+    /// warning, because the transaction is already aborting.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/postgres-fdw.html#POSTGRES-FDW-TRANSACTION-MANAGEMENT).
+    fn on_xact_abort(&mut self) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Called before a local subtransaction commits, for example, when a
+    /// savepoint is released
+    ///
+    /// - `subxact_id` - the id of the subtransaction
+    ///
+    /// Returning an error will abort the local subtransaction.
+    fn on_subxact_commit(&mut self, _subxact_id: u32) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Called when a local subtransaction aborts, for example, when rolling
+    /// back to a savepoint
+    ///
+    /// - `subxact_id` - the id of the subtransaction
+    ///
+    /// Error returned from this function is reported as a warning.
+    fn on_subxact_abort(&mut self, _subxact_id: u32) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Returns if the foreign data wrapper supports `ANALYZE`
+    ///
+    /// Return `true` if [`analyze`](Self::analyze) is implemented. Otherwise,
+    /// `ANALYZE` skips the foreign table with a warning, no FDW instance is
+    /// created. Return `false` by default.
+    fn is_analyze_supported() -> bool {
+        false
+    }
+
+    /// Called when `ANALYZE` the foreign table, to collect its statistics
+    ///
+    /// This is only called if [`is_analyze_supported`](Self::is_analyze_supported)
+    /// returns `true`.
+    ///
+    /// - `columns` - all columns of the foreign table
+    /// - `sample_size` - the maximum number of sample rows
+    /// - `options` - the options defined when `CREATE FOREIGN TABLE`
+    ///
+    /// Return the total number of rows in the foreign table and a random sample
+    /// of rows, from which the column statistics are computed. Return `None` if
+    /// the foreign table cannot be analyzed, its statistics will be kept intact.
+    ///
+    /// [See more details](https://www.postgresql.org/docs/current/fdw-callbacks.html#FDW-CALLBACKS-ANALYZE).
+    fn analyze(
+        &mut self,
+        _columns: &[Column],
+        _sample_size: usize,
+        _options: &HashMap<String, String>,
+    ) -> Result<Option<(i64, Vec<Row>)>, E> {
+        Ok(None)
+    }
+
     /// Obtain a list of foreign table creation commands
     ///
     /// Return a list of string, each of which must contain a CREATE FOREIGN TABLE
@@ -801,7 +2452,7 @@ pub trait ForeignDataWrapper<E: Into<ErrorReport>> {
         Self: Sized,
     {
         unsafe {
-            use crate::{import_foreign_schema, modify, scan};
+            use crate::{analyze, import_foreign_schema, modify, scan};
             let mut fdw_routine =
                 FdwRoutine::<AllocatedByRust>::alloc_node(pg_sys::NodeTag::T_FdwRoutine);
 
@@ -813,6 +2464,8 @@ pub trait ForeignDataWrapper<E: Into<ErrorReport>> {
             fdw_routine.GetForeignRelSize = Some(scan::get_foreign_rel_size::<E, Self>);
             fdw_routine.GetForeignPaths = Some(scan::get_foreign_paths::<E, Self>);
             fdw_routine.GetForeignPlan = Some(scan::get_foreign_plan::<E, Self>);
+            fdw_routine.GetForeignJoinPaths = Some(scan::get_foreign_join_paths::<E, Self>);
+            fdw_routine.GetForeignUpperPaths = Some(scan::get_foreign_upper_paths::<E, Self>);
             fdw_routine.ExplainForeignScan = Some(scan::explain_foreign_scan::<E, Self>);
 
             // scan phase
@@ -820,16 +2473,46 @@ pub trait ForeignDataWrapper<E: Into<ErrorReport>> {
             fdw_routine.IterateForeignScan = Some(scan::iterate_foreign_scan::<E, Self>);
             fdw_routine.ReScanForeignScan = Some(scan::re_scan_foreign_scan::<E, Self>);
             fdw_routine.EndForeignScan = Some(scan::end_foreign_scan::<E, Self>);
+            #[cfg(not(feature = "pg13"))]
+            {
+                fdw_routine.IsForeignPathAsyncCapable =
+                    Some(scan::is_foreign_path_async_capable::<E, Self>);
+                fdw_routine.ForeignAsyncRequest = Some(scan::foreign_async_request::<E, Self>);
+                fdw_routine.ForeignAsyncConfigureWait =
+                    Some(scan::foreign_async_configure_wait::<E, Self>);
+                fdw_routine.ForeignAsyncNotify = Some(scan::foreign_async_notify::<E, Self>);
+            }
+            fdw_routine.IsForeignScanParallelSafe =
+                Some(scan::is_foreign_scan_parallel_safe::<E, Self>);
+            fdw_routine.EstimateDSMForeignScan = Some(scan::estimate_dsm_foreign_scan::<E, Self>);
+            fdw_routine.InitializeDSMForeignScan =
+                Some(scan::initialize_dsm_foreign_scan::<E, Self>);
+            fdw_routine.ReInitializeDSMForeignScan =
+                Some(scan::reinitialize_dsm_foreign_scan::<E, Self>);
+            fdw_routine.InitializeWorkerForeignScan =
+                Some(scan::initialize_worker_foreign_scan::<E, Self>);
+            fdw_routine.ShutdownForeignScan = Some(scan::shutdown_foreign_scan::<E, Self>);
 
             // modify phase
             fdw_routine.AddForeignUpdateTargets = Some(modify::add_foreign_update_targets);
             fdw_routine.PlanForeignModify = Some(modify::plan_foreign_modify::<E, Self>);
             fdw_routine.BeginForeignModify = Some(modify::begin_foreign_modify::<E, Self>);
             fdw_routine.ExecForeignInsert = Some(modify::exec_foreign_insert::<E, Self>);
+            #[cfg(not(feature = "pg13"))]
+            {
+                fdw_routine.ExecForeignBatchInsert =
+                    Some(modify::exec_foreign_batch_insert::<E, Self>);
+                fdw_routine.GetForeignModifyBatchSize =
+                    Some(modify::get_foreign_modify_batch_size::<E, Self>);
+                fdw_routine.ExecForeignTruncate = Some(modify::exec_foreign_truncate::<E, Self>);
+            }
             fdw_routine.ExecForeignDelete = Some(modify::exec_foreign_delete::<E, Self>);
             fdw_routine.ExecForeignUpdate = Some(modify::exec_foreign_update::<E, Self>);
             fdw_routine.EndForeignModify = Some(modify::end_foreign_modify::<E, Self>);
 
+            // analyze
+            fdw_routine.AnalyzeForeignTable = Some(analyze::analyze_foreign_table::<E, Self>);
+
             Self::fdw_routine_hook(&mut fdw_routine);
             fdw_routine.into_pg_boxed()
         }
@@ -896,4 +2579,50 @@ pub trait ForeignDataWrapper<E: Into<ErrorReport>> {
     fn validator(_options: Vec<Option<String>>, _catalog: Option<Oid>) -> Result<(), E> {
         Ok(())
     }
+
+    /// Returns the definitions of options supported by the foreign data wrapper
+    ///
+    /// The options are checked against the definitions by the validator
+    /// function generated by [`wrappers_fdw`](crate::wrappers_fdw) before
+    /// [`validator`](Self::validator) is called, so unknown options, invalid
+    /// values and missing required options are rejected when `CREATE` or
+    /// `ALTER`. The options of `IMPORT FOREIGN SCHEMA` are also checked
+    /// before [`import_foreign_schema`](Self::import_foreign_schema) is
+    /// called. The definitions are listed by the `<fdw_name>_options()` SQL
+    /// function.
+    ///
+    /// The default accepts any option, only the values of the
+    /// [common options](crate::options::common_option_defs) are checked. Use
+    /// [`OptionDef::any`] to accept the options which are passed through to
+    /// the data source as they are, and return an empty list if no options
+    /// other than the common ones are supported.
+    ///
+    /// For example,
+    ///
+    /// ```rust,no_run
+    /// # use supabase_wrappers::prelude::*;
+    /// fn option_defs() -> Vec<OptionDef> {
+    ///     vec![
+    ///         OptionDef::new("api_key", &[OptionContext::Server, OptionContext::UserMapping])
+    ///             .secret()
+    ///             .description("API key"),
+    ///         OptionDef::new("object", &[OptionContext::Table])
+    ///             .required()
+    ///             .description("Remote object name"),
+    ///         OptionDef::new("limit", &[OptionContext::Table])
+    ///             .opt_type(OptionType::Integer)
+    ///             .default("100")
+    ///             .description("Maximum number of rows to fetch"),
+    ///     ]
+    /// }
+    /// ```
+    fn option_defs() -> Vec<OptionDef> {
+        vec![OptionDef::any(&[
+            OptionContext::Wrapper,
+            OptionContext::Server,
+            OptionContext::UserMapping,
+            OptionContext::Table,
+            OptionContext::Import,
+        ])]
+    }
 }
@@ -0,0 +1,181 @@
+use crate::interface::{Column, JoinColumn, JoinQual, JoinType};
+use crate::qual::{get_operator, unnest_clause};
+use pgrx::{is_a, pg_sys, PgList};
+use std::ffi::CStr;
+use std::os::raw::c_int;
+use std::ptr;
+
+// convert a Postgres join type to join type, only inner and outer joins are supported
+pub(crate) fn get_join_type(jointype: pg_sys::JoinType::Type) -> Option<JoinType> {
+    match jointype {
+        pg_sys::JoinType::JOIN_INNER => Some(JoinType::Inner),
+        pg_sys::JoinType::JOIN_LEFT => Some(JoinType::Left),
+        pg_sys::JoinType::JOIN_RIGHT => Some(JoinType::Right),
+        pg_sys::JoinType::JOIN_FULL => Some(JoinType::Full),
+        _ => None,
+    }
+}
+
+// get a plain column from a Var which belongs to one of the base relations, return
+// the column and whether it belongs to the outer relation
+unsafe fn get_var_column(
+    root: *mut pg_sys::PlannerInfo,
+    node: *mut pg_sys::Node,
+    outerrel: *mut pg_sys::RelOptInfo,
+    innerrel: *mut pg_sys::RelOptInfo,
+) -> Option<(Column, bool)> {
+    let node = unnest_clause(node);
+    if !is_a(node, pg_sys::NodeTag::T_Var) {
+        return None;
+    }
+
+    let var = node as *mut pg_sys::Var;
+    if (*var).varattno < 1 {
+        return None;
+    }
+
+    let varno = (*var).varno as pg_sys::Index;
+    let is_outer = if varno == (*outerrel).relid {
+        true
+    } else if varno == (*innerrel).relid {
+        false
+    } else {
+        return None;
+    };
+
+    let rte = pg_sys::planner_rt_fetch(varno, root);
+    let attname = pg_sys::get_attname((*rte).relid, (*var).varattno, true);
+    if attname.is_null() {
+        return None;
+    }
+
+    let col = Column {
+        name: CStr::from_ptr(attname).to_str().unwrap().to_owned(),
+        num: (*var).varattno as usize,
+        type_oid: (*var).vartype,
+    };
+    Some((col, is_outer))
+}
+
+// extract join conditions from the join restriction list, return None if any of
+// them cannot be pushed down
+//
+// Only binary operator between a column of the outer relation and a column of the
+// inner relation is supported, like `outer.col = inner.col`.
+pub(crate) unsafe fn extract_join_quals(
+    root: *mut pg_sys::PlannerInfo,
+    joinrel: *mut pg_sys::RelOptInfo,
+    outerrel: *mut pg_sys::RelOptInfo,
+    innerrel: *mut pg_sys::RelOptInfo,
+    join_type: JoinType,
+    restrictlist: *mut pg_sys::List,
+) -> Option<Vec<JoinQual>> {
+    let mut quals = Vec::new();
+
+    let rinfos = PgList::<pg_sys::RestrictInfo>::from_pg(restrictlist);
+    for rinfo in rinfos.iter_ptr() {
+        // for outer join, the clauses pushed down from upper level must be
+        // applied after the join, so they cannot be used as join conditions
+        if join_type != JoinType::Inner
+            && ((*rinfo).is_pushed_down
+                || !pg_sys::bms_is_subset((*rinfo).required_relids, (*joinrel).relids))
+        {
+            return None;
+        }
+
+        let clause = (*rinfo).clause as *mut pg_sys::Node;
+        if !is_a(clause, pg_sys::NodeTag::T_OpExpr) {
+            return None;
+        }
+
+        let expr = clause as *mut pg_sys::OpExpr;
+        let args = PgList::<pg_sys::Node>::from_pg((*expr).args);
+        if args.len() != 2 {
+            return None;
+        }
+
+        let opr = get_operator((*expr).opno);
+        if opr.is_null() {
+            return None;
+        }
+
+        let (left, left_is_outer) = get_var_column(root, args.get_ptr(0)?, outerrel, innerrel)?;
+        let (right, right_is_outer) = get_var_column(root, args.get_ptr(1)?, outerrel, innerrel)?;
+        if left_is_outer == right_is_outer {
+            return None;
+        }
+
+        let qual = if left_is_outer {
+            JoinQual {
+                outer_field: left.name,
+                operator: pgrx::name_data_to_str(&(*opr).oprname).to_string(),
+                inner_field: right.name,
+            }
+        } else {
+            // swap operands by using the commutator operator, like `a > b` to `b < a`
+            if (*opr).oprcom == pg_sys::Oid::INVALID {
+                return None;
+            }
+            let com = get_operator((*opr).oprcom);
+            if com.is_null() {
+                return None;
+            }
+            JoinQual {
+                outer_field: right.name,
+                operator: pgrx::name_data_to_str(&(*com).oprname).to_string(),
+                inner_field: left.name,
+            }
+        };
+        quals.push(qual);
+    }
+
+    Some(quals)
+}
+
+// extract target columns from the join relation target, return None if any of
+// them is not a plain column
+//
+// The returned target list is used as `fdw_scan_tlist` of the foreign scan, it
+// is in the same order as the cells returned by the FDW.
+pub(crate) unsafe fn extract_join_columns(
+    root: *mut pg_sys::PlannerInfo,
+    joinrel: *mut pg_sys::RelOptInfo,
+    outerrel: *mut pg_sys::RelOptInfo,
+    innerrel: *mut pg_sys::RelOptInfo,
+) -> Option<(Vec<JoinColumn>, *mut pg_sys::List)> {
+    let mut columns = Vec::new();
+    let mut tlist: *mut pg_sys::List = ptr::null_mut();
+
+    let exprs = PgList::<pg_sys::Node>::from_pg((*(*joinrel).reltarget).exprs);
+    for expr in exprs.iter_ptr() {
+        let (column, is_outer) = get_var_column(root, expr, outerrel, innerrel)?;
+        columns.push(JoinColumn {
+            column,
+            is_outer,
+            alias: format!("_wrappers_col_{}", columns.len() + 1),
+        });
+
+        let tle = pg_sys::makeTargetEntry(
+            expr as _,
+            columns.len() as pg_sys::AttrNumber,
+            ptr::null_mut(),
+            false,
+        );
+        tlist = pg_sys::lappend(tlist, tle as _);
+    }
+
+    Some((columns, tlist))
+}
+
+// check if the relation is a foreign table scanned by the same FDW
+pub(crate) unsafe fn is_foreign_base_rel(rel: *mut pg_sys::RelOptInfo) -> bool {
+    (*rel).reloptkind == pg_sys::RelOptKind::RELOPT_BASEREL && !(*rel).fdw_private.is_null()
+}
+
+// check if the relation is the result relation of UPDATE or DELETE
+pub(crate) unsafe fn is_result_rel(
+    root: *mut pg_sys::PlannerInfo,
+    rel: *mut pg_sys::RelOptInfo,
+) -> bool {
+    (*(*root).parse).resultRelation == (*rel).relid as c_int
+}
@@ -43,17 +43,54 @@
 //!
 //! - Query planning phase
 //!   - [get_rel_size()](`interface::ForeignDataWrapper#method.get_rel_size`)
+//!   - [get_lookup_columns()](`interface::ForeignDataWrapper#method.get_lookup_columns`)
+//!   - [field_expr_allowlist()](`interface::ForeignDataWrapper#method.field_expr_allowlist`)
+//!   - [get_filter_pushdown()](`interface::ForeignDataWrapper#method.get_filter_pushdown`)
+//!   - [get_scan_pushdown()](`interface::ForeignDataWrapper#method.get_scan_pushdown`)
+//!   - [get_path_costs()](`interface::ForeignDataWrapper#method.get_path_costs`)
 //! - Scan phase
 //!   - [begin_scan()](`interface::ForeignDataWrapper#tymethod.begin_scan`) *required*
 //!   - [iter_scan()](`interface::ForeignDataWrapper#tymethod.iter_scan`) *required*
 //!   - [re_scan()](`interface::ForeignDataWrapper#method.re_scan`)
 //!   - [end_scan()](`interface::ForeignDataWrapper#tymethod.end_scan`) *required*
+//!   - [explain_scan()](`interface::ForeignDataWrapper#method.explain_scan`)
+//! - Aggregate pushdown
+//!   - [get_aggregate_pushdown()](`interface::ForeignDataWrapper#method.get_aggregate_pushdown`)
+//!   - [begin_aggregate_scan()](`interface::ForeignDataWrapper#method.begin_aggregate_scan`)
+//! - Join pushdown
+//!   - [get_join_pushdown()](`interface::ForeignDataWrapper#method.get_join_pushdown`)
+//!   - [begin_join_scan()](`interface::ForeignDataWrapper#method.begin_join_scan`)
+//! - Asynchronous scan
+//!   - [is_async_capable()](`interface::ForeignDataWrapper#method.is_async_capable`)
+//!   - [begin_async_scan()](`interface::ForeignDataWrapper#method.begin_async_scan`)
+//! - Parallel scan
+//!   - [is_parallel_safe()](`interface::ForeignDataWrapper#method.is_parallel_safe`)
+//!   - [get_parallel_workers()](`interface::ForeignDataWrapper#method.get_parallel_workers`)
+//!   - [init_parallel_scan()](`interface::ForeignDataWrapper#method.init_parallel_scan`)
+//!   - [begin_parallel_scan()](`interface::ForeignDataWrapper#method.begin_parallel_scan`)
 //! - Modify phase
 //!   - [begin_modify()](`interface::ForeignDataWrapper#method.begin_modify`)
 //!   - [insert()](`interface::ForeignDataWrapper#method.insert`)
+//!   - [insert_batch()](`interface::ForeignDataWrapper#method.insert_batch`)
 //!   - [update()](`interface::ForeignDataWrapper#method.update`)
 //!   - [delete()](`interface::ForeignDataWrapper#method.delete`)
+//!   - [insert_returning()](`interface::ForeignDataWrapper#method.insert_returning`)
+//!   - [update_returning()](`interface::ForeignDataWrapper#method.update_returning`)
+//!   - [delete_returning()](`interface::ForeignDataWrapper#method.delete_returning`)
 //!   - [end_modify()](`interface::ForeignDataWrapper#method.end_modify`)
+//!   - [truncate()](`interface::ForeignDataWrapper#method.truncate`)
+//! - Transaction
+//!   - [has_xact_hooks()](`interface::ForeignDataWrapper#method.has_xact_hooks`)
+//!   - [on_xact_commit()](`interface::ForeignDataWrapper#method.on_xact_commit`)
+//!   - [on_xact_abort()](`interface::ForeignDataWrapper#method.on_xact_abort`)
+//!   - [on_subxact_commit()](`interface::ForeignDataWrapper#method.on_subxact_commit`)
+//!   - [on_subxact_abort()](`interface::ForeignDataWrapper#method.on_subxact_abort`)
+//! - Analyze
+//!   - [is_analyze_supported()](`interface::ForeignDataWrapper#method.is_analyze_supported`)
+//!   - [analyze()](`interface::ForeignDataWrapper#method.analyze`)
+//! - Options
+//!   - [option_defs()](`interface::ForeignDataWrapper#method.option_defs`)
+//!   - [validator()](`interface::ForeignDataWrapper#method.validator`)
 //!
 //! To give different functionalities to your FDW, you can choose different callback functions to implement. The required ones are `begin_scan`, `iter_scan` and `end_scan`, all the others are optional. See [Postgres FDW document](https://www.postgresql.org/docs/current/fdw-callbacks.html) for more details about FDW development.
 //!
@@ -270,13 +307,15 @@
 //!          Sort Key: col
 //!          ->  Foreign Scan on hello  (cost=0.00..1.00 rows=1 width=0)
 //!                Filter: (id = 1)
-//!                Wrappers: quals = [Qual { field: "id", operator: "=", value: Cell(I32(1)), use_or: false, param: None }]
+//!                Wrappers: quals = [Qual { field: "id", operator: "=", value: Cell(I32(1)), use_or: false, param: None, field_expr: None }]
 //!                Wrappers: tgts = [Column { name: "id", num: 1, type_oid: 20 }, Column { name: "col", num: 2, type_oid: 25 }]
-//!                Wrappers: sorts = [Sort { field: "col", field_no: 2, reversed: false, nulls_first: false, collate: None }]
+//!                Wrappers: sorts = [Sort { field: "col", field_no: 2, reversed: false, nulls_first: false, collate: None, collation: Some("default") }]
 //!                Wrappers: limit = Some(Limit { count: 1, offset: 0 })
 //! (9 rows)
 //! ```
 //!
+//! The FDW can also show its own properties, such as the remote query it sends, by implementing [explain_scan()](`interface::ForeignDataWrapper#method.explain_scan`).
+//!
 //! ### More FDW Examples
 //!
 //! See more FDW examples which interact with RDBMS or RESTful API.
@@ -292,12 +331,14 @@
 //! - [SQL Server](https://github.com/supabase/wrappers/tree/main/wrappers/src/fdw/mssql_fdw): A FDW for [Microsoft SQL Server](https://www.microsoft.com/en-au/sql-server/) which supports data read only.
 //! - [Redis](https://github.com/supabase/wrappers/tree/main/wrappers/src/fdw/redis_fdw): A FDW for [Redis](https://redis.io/) which supports data read only.
 
+pub mod dialect;
 pub mod interface;
 pub mod options;
 pub mod utils;
 
 /// The prelude includes all necessary imports to make Wrappers work
 pub mod prelude {
+    pub use crate::dialect::*;
     pub use crate::import_foreign_schema::*;
     pub use crate::instance::ForeignServer;
     pub use crate::interface::*;
@@ -310,15 +351,22 @@ pub mod prelude {
 use pgrx::prelude::*;
 use pgrx::AllocatedByPostgres;
 
+mod aggregate;
+mod analyze;
+mod cache;
 mod import_foreign_schema;
 mod instance;
+mod join;
 mod limit;
 mod memctx;
 mod modify;
+mod on_error;
+mod param_path;
 mod polyfill;
 mod qual;
 mod scan;
 mod sort;
+mod xact;
 
 /// PgBox'ed `FdwRoutine`, used in [`fdw_routine`](interface::ForeignDataWrapper::fdw_routine)
 pub type FdwRoutine<A = AllocatedByPostgres> = PgBox<pg_sys::FdwRoutine, A>;
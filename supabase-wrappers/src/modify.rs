@@ -1,12 +1,14 @@
 use pgrx::pg_sys::panic::ErrorReport;
 use pgrx::{
     debug2, memcxt::PgMemoryContexts, pg_sys::Oid, prelude::*, rel::PgRelation,
-    tupdesc::PgTupleDesc, FromDatum, PgSqlErrorCode,
+    tupdesc::PgTupleDesc, FromDatum, IntoDatum, PgList, PgSqlErrorCode,
 };
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::os::raw::c_int;
 use std::ptr;
+use std::rc::Rc;
 
 use crate::prelude::*;
 
@@ -14,11 +16,13 @@ use super::instance;
 use super::memctx;
 use super::polyfill;
 use super::utils;
+use super::xact;
 
 // Fdw private state for modify
 struct FdwModifyState<E: Into<ErrorReport>, W: ForeignDataWrapper<E>> {
-    // foreign data wrapper instance
-    instance: W,
+    // foreign data wrapper instance, it is shared with transaction participant
+    // list and kept alive until the transaction ends
+    instance: Rc<RefCell<W>>,
 
     // row id attribute number and type id
     rowid_name: String,
@@ -28,6 +32,9 @@ struct FdwModifyState<E: Into<ErrorReport>, W: ForeignDataWrapper<E>> {
     // foreign table options
     opts: HashMap<String, String>,
 
+    // columns referenced by RETURNING clause, empty if no RETURNING
+    returning_cols: Vec<Column>,
+
     // temporary memory context per foreign table, created under Wrappers root
     // memory context
     tmp_ctx: PgMemoryContexts,
@@ -38,13 +45,17 @@ struct FdwModifyState<E: Into<ErrorReport>, W: ForeignDataWrapper<E>> {
 }
 
 impl<E: Into<ErrorReport>, W: ForeignDataWrapper<E>> FdwModifyState<E, W> {
-    unsafe fn new(foreigntableid: Oid, tmp_ctx: PgMemoryContexts) -> Self {
+    unsafe fn new(foreigntableid: Oid, user_id: Oid, tmp_ctx: PgMemoryContexts) -> Self {
         Self {
-            instance: instance::create_fdw_instance_from_table_id(foreigntableid),
+            instance: Rc::new(RefCell::new(instance::create_fdw_instance_from_table_id(
+                foreigntableid,
+                user_id,
+            ))),
             rowid_name: String::default(),
             rowid_attno: 0,
             rowid_typid: Oid::INVALID,
             opts: HashMap::new(),
+            returning_cols: Vec::new(),
             tmp_ctx,
             _phantom: PhantomData,
             #[cfg(feature = "pg13")]
@@ -53,23 +64,51 @@ impl<E: Into<ErrorReport>, W: ForeignDataWrapper<E>> FdwModifyState<E, W> {
     }
 
     fn begin_modify(&mut self) -> Result<(), E> {
-        self.instance.begin_modify(&self.opts)
+        self.instance.borrow_mut().begin_modify(&self.opts)
     }
 
     fn insert(&mut self, row: &Row) -> Result<(), E> {
-        self.instance.insert(row)
+        self.instance.borrow_mut().insert(row)
+    }
+
+    fn insert_returning(&mut self, row: &Row, returning: &mut Row) -> Result<(), E> {
+        self.instance
+            .borrow_mut()
+            .insert_returning(row, &self.returning_cols, returning)
+    }
+
+    #[cfg(not(feature = "pg13"))]
+    fn insert_batch(&mut self, rows: &[Row]) -> Result<(), E> {
+        self.instance.borrow_mut().insert_batch(rows)
     }
 
     fn update(&mut self, rowid: &Cell, new_row: &Row) -> Result<(), E> {
-        self.instance.update(rowid, new_row)
+        self.instance.borrow_mut().update(rowid, new_row)
+    }
+
+    fn update_returning(
+        &mut self,
+        rowid: &Cell,
+        new_row: &Row,
+        returning: &mut Row,
+    ) -> Result<(), E> {
+        self.instance
+            .borrow_mut()
+            .update_returning(rowid, new_row, &self.returning_cols, returning)
     }
 
     fn delete(&mut self, rowid: &Cell) -> Result<(), E> {
-        self.instance.delete(rowid)
+        self.instance.borrow_mut().delete(rowid)
+    }
+
+    fn delete_returning(&mut self, rowid: &Cell, returning: &mut Row) -> Result<(), E> {
+        self.instance
+            .borrow_mut()
+            .delete_returning(rowid, &self.returning_cols, returning)
     }
 
     fn end_modify(&mut self) -> Result<(), E> {
-        self.instance.end_modify()
+        self.instance.borrow_mut().end_modify()
     }
 }
 
@@ -100,6 +139,76 @@ unsafe fn find_rowid_column(
     None
 }
 
+// get columns referenced by the RETURNING list of the result relation
+unsafe fn get_returning_columns(
+    returning_list: *mut pg_sys::List,
+    result_relation: pg_sys::Index,
+    rel: &PgRelation,
+) -> Vec<Column> {
+    let mut attrs: *mut pg_sys::Bitmapset = ptr::null_mut();
+    pg_sys::pull_varattnos(returning_list as _, result_relation, &mut attrs);
+
+    // whole-row reference, like `RETURNING t` for table alias `t`, needs all columns
+    let offset = pg_sys::FirstLowInvalidHeapAttributeNumber;
+    let whole_row = pg_sys::bms_is_member(-offset, attrs);
+
+    let tup_desc = PgTupleDesc::from_relation(rel);
+    tup_desc
+        .iter()
+        .filter(|a| !a.attisdropped)
+        .filter(|a| whole_row || pg_sys::bms_is_member(a.attnum as c_int - offset, attrs))
+        .map(|a| Column {
+            name: pgrx::name_data_to_str(&a.attname).to_string(),
+            num: a.attnum as usize,
+            type_oid: a.atttypid,
+        })
+        .collect()
+}
+
+// store the RETURNING row into the slot, the attributes not in the row keep
+// their existing values in the slot
+unsafe fn store_returning_row(slot: *mut pg_sys::TupleTableSlot, row: &Row) {
+    let tup_desc = PgTupleDesc::from_pg_copy((*slot).tts_tupleDescriptor);
+    let natts = tup_desc.len();
+    let mut values = vec![0.into_datum().unwrap(); natts];
+    let mut nulls = vec![true; natts];
+
+    // copy existing attributes before the slot is cleared
+    if (*slot).tts_flags & pg_sys::TTS_FLAG_EMPTY as u16 == 0 {
+        for (i, attr) in tup_desc.iter().enumerate() {
+            if attr.attisdropped {
+                continue;
+            }
+            let mut is_null = true;
+            let datum = polyfill::slot_getattr(slot, (i + 1) as c_int, &mut is_null);
+            if !is_null {
+                values[i] = pg_sys::datumCopy(datum, attr.attbyval, attr.attlen as c_int);
+                nulls[i] = false;
+            }
+        }
+    }
+
+    for (col, cell) in row.iter() {
+        let att_idx = tup_desc
+            .iter()
+            .position(|a| !a.attisdropped && pgrx::name_data_to_str(&a.attname) == col);
+        if let Some(att_idx) = att_idx {
+            match cell {
+                Some(cell) => {
+                    values[att_idx] = cell.clone().into_datum().unwrap();
+                    nulls[att_idx] = false;
+                }
+                None => nulls[att_idx] = true,
+            }
+        }
+    }
+
+    polyfill::exec_clear_tuple(slot);
+    ptr::copy_nonoverlapping(values.as_ptr(), (*slot).tts_values, natts);
+    ptr::copy_nonoverlapping(nulls.as_ptr(), (*slot).tts_isnull, natts);
+    pg_sys::ExecStoreVirtualTuple(slot);
+}
+
 #[cfg(feature = "pg13")]
 #[pg_guard]
 pub(super) extern "C" fn add_foreign_update_targets(
@@ -169,19 +278,20 @@ pub(super) extern "C-unwind" fn plan_foreign_modify<
     root: *mut pg_sys::PlannerInfo,
     plan: *mut pg_sys::ModifyTable,
     result_relation: pg_sys::Index,
-    _subplan_index: c_int,
+    subplan_index: c_int,
 ) -> *mut pg_sys::List {
     debug2!("---> plan_foreign_modify");
     unsafe {
-        if !(*plan).returningLists.is_null() {
-            report_error(
-                PgSqlErrorCode::ERRCODE_FDW_ERROR,
-                "RETURNING is not supported",
-            )
-        }
-
         let rte = pg_sys::planner_rt_fetch(result_relation, root);
 
+        // the user to check access as, it is invalid if it is current user
+        #[cfg(any(feature = "pg13", feature = "pg14", feature = "pg15"))]
+        let user_id = (*rte).checkAsUser;
+        #[cfg(not(any(feature = "pg13", feature = "pg14", feature = "pg15")))]
+        let user_id = PgList::<pg_sys::RTEPermissionInfo>::from_pg((*(*root).parse).rteperminfos)
+            .get_ptr(((*rte).perminfoindex as usize).wrapping_sub(1))
+            .map_or(pg_sys::InvalidOid, |perminfo| (*perminfo).checkAsUser);
+
         // core code already has some lock on each rel being planned, so we can
         // use NoLock here.
         let rel = PgRelation::with_lock((*rte).relid, pg_sys::NoLock as _);
@@ -211,12 +321,21 @@ pub(super) extern "C-unwind" fn plan_foreign_modify<
                 let ctx = memctx::refresh_wrappers_memctx(&ctx_name);
 
                 // create modify state
-                let mut state = FdwModifyState::<E, W>::new(ftable_id, ctx);
+                let mut state = FdwModifyState::<E, W>::new(ftable_id, user_id, ctx);
 
                 state.rowid_name = rowid_name.to_string();
                 state.rowid_typid = attr.atttypid;
                 state.opts = opts;
 
+                // get columns referenced by RETURNING clause
+                if !(*plan).returningLists.is_null() {
+                    let returning_lists = PgList::<pg_sys::List>::from_pg((*plan).returningLists);
+                    if let Some(returning_list) = returning_lists.get_ptr(subplan_index as _) {
+                        state.returning_cols =
+                            get_returning_columns(returning_list, result_relation, &rel);
+                    }
+                }
+
                 #[cfg(feature = "pg13")]
                 {
                     // get update column list
@@ -281,6 +400,11 @@ pub(super) extern "C-unwind" fn begin_foreign_modify<
 
         state.begin_modify().report_unwrap();
 
+        // take part in current transaction, so transaction hooks can be called
+        if state.instance.borrow().has_xact_hooks() {
+            xact::register_participant::<E, W>(&state.instance);
+        }
+
         (*rinfo).ri_FdwState = state.into_pg() as _;
     }
 }
@@ -302,12 +426,91 @@ pub(super) extern "C-unwind" fn exec_foreign_insert<
         );
 
         let row = utils::tuple_table_slot_to_row(slot);
-        state.insert(&row).report_unwrap();
+        if state.returning_cols.is_empty() {
+            state.insert(&row).report_unwrap();
+        } else {
+            let mut returning = Row::new();
+            state.insert_returning(&row, &mut returning).report_unwrap();
+            store_returning_row(slot, &returning);
+        }
     }
 
     slot
 }
 
+// get batch size from foreign table options first, then server options
+#[cfg(not(feature = "pg13"))]
+unsafe fn get_batch_size(ftable_id: Oid) -> c_int {
+    let ftable = pg_sys::GetForeignTable(ftable_id);
+    let mut opts = options_to_hashmap((*ftable).options).report_unwrap();
+    if !opts.contains_key("batch_size") {
+        let fserver = pg_sys::GetForeignServer((*ftable).serverid);
+        opts = options_to_hashmap((*fserver).options).report_unwrap();
+    }
+
+    opts.get("batch_size")
+        .map(|s| match s.parse::<c_int>() {
+            Ok(v) if v > 0 => v,
+            _ => {
+                pgrx::error!("invalid option batch_size: {}", s);
+            }
+        })
+        .unwrap_or(1)
+}
+
+#[cfg(not(feature = "pg13"))]
+#[pg_guard]
+pub(super) extern "C-unwind" fn get_foreign_modify_batch_size<
+    E: Into<ErrorReport>,
+    W: ForeignDataWrapper<E>,
+>(
+    rinfo: *mut pg_sys::ResultRelInfo,
+) -> c_int {
+    debug2!("---> get_foreign_modify_batch_size");
+    unsafe {
+        // batch insert cannot be used when RETURNING or row triggers exist,
+        // because they need the inserted row immediately
+        let trig_desc = (*rinfo).ri_TrigDesc;
+        if !(*rinfo).ri_projectReturning.is_null()
+            || (!trig_desc.is_null()
+                && ((*trig_desc).trig_insert_before_row || (*trig_desc).trig_insert_after_row))
+        {
+            return 1;
+        }
+
+        get_batch_size((*(*rinfo).ri_RelationDesc).rd_id)
+    }
+}
+
+#[cfg(not(feature = "pg13"))]
+#[pg_guard]
+pub(super) extern "C-unwind" fn exec_foreign_batch_insert<
+    E: Into<ErrorReport>,
+    W: ForeignDataWrapper<E>,
+>(
+    _estate: *mut pg_sys::EState,
+    rinfo: *mut pg_sys::ResultRelInfo,
+    slots: *mut *mut pg_sys::TupleTableSlot,
+    _plan_slots: *mut *mut pg_sys::TupleTableSlot,
+    num_slots: *mut c_int,
+) -> *mut *mut pg_sys::TupleTableSlot {
+    debug2!("---> exec_foreign_batch_insert");
+    unsafe {
+        let mut state = PgBox::<FdwModifyState<E, W>>::from_pg(
+            (*rinfo).ri_FdwState as *mut FdwModifyState<E, W>,
+        );
+
+        let slots_cnt = *num_slots as usize;
+        let rows = std::slice::from_raw_parts(slots, slots_cnt)
+            .iter()
+            .map(|slot| utils::tuple_table_slot_to_row(*slot))
+            .collect::<Vec<Row>>();
+        state.insert_batch(&rows).report_unwrap();
+    }
+
+    slots
+}
+
 unsafe fn get_rowid_cell<E: Into<ErrorReport>, W: ForeignDataWrapper<E>>(
     state: &FdwModifyState<E, W>,
     plan_slot: *mut pg_sys::TupleTableSlot,
@@ -335,7 +538,15 @@ pub(super) extern "C-unwind" fn exec_foreign_delete<
 
         let cell = get_rowid_cell(&state, plan_slot);
         if let Some(rowid) = cell {
-            state.delete(&rowid).report_unwrap();
+            if state.returning_cols.is_empty() {
+                state.delete(&rowid).report_unwrap();
+            } else {
+                let mut returning = Row::new();
+                state
+                    .delete_returning(&rowid, &mut returning)
+                    .report_unwrap();
+                store_returning_row(slot, &returning);
+            }
         }
     }
 
@@ -382,7 +593,15 @@ pub(super) extern "C-unwind" fn exec_foreign_update<
                 }
             });
 
-            state.update(&rowid, &new_row).report_unwrap();
+            if state.returning_cols.is_empty() {
+                state.update(&rowid, &new_row).report_unwrap();
+            } else {
+                let mut returning = Row::new();
+                state
+                    .update_returning(&rowid, &new_row, &mut returning)
+                    .report_unwrap();
+                store_returning_row(slot, &returning);
+            }
         }
     }
 
@@ -406,3 +625,39 @@ pub(super) extern "C-unwind" fn end_foreign_modify<
         }
     }
 }
+
+#[cfg(not(feature = "pg13"))]
+#[pg_guard]
+pub(super) extern "C-unwind" fn exec_foreign_truncate<
+    E: Into<ErrorReport>,
+    W: ForeignDataWrapper<E>,
+>(
+    rels: *mut pg_sys::List,
+    behavior: pg_sys::DropBehavior::Type,
+    restart_seqs: bool,
+) {
+    debug2!("---> exec_foreign_truncate");
+    unsafe {
+        // all the foreign tables belong to the same foreign server, so one
+        // FDW instance is enough
+        let rels = PgList::<pg_sys::RelationData>::from_pg(rels);
+        let Some(first) = rels.head() else {
+            return;
+        };
+        let mut instance =
+            instance::create_fdw_instance_from_table_id::<E, W>((*first).rd_id, pg_sys::InvalidOid);
+
+        let tables = rels
+            .iter_ptr()
+            .map(|rel| {
+                let ftable = pg_sys::GetForeignTable((*rel).rd_id);
+                options_to_hashmap((*ftable).options).report_unwrap()
+            })
+            .collect::<Vec<_>>();
+        let cascade = behavior == pg_sys::DropBehavior::DROP_CASCADE;
+
+        instance
+            .truncate(&tables, restart_seqs, cascade)
+            .report_unwrap();
+    }
+}
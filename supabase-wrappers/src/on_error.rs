@@ -0,0 +1,181 @@
+use pgrx::{prelude::*, PgSqlErrorCode};
+use std::collections::HashMap;
+
+use crate::interface::{Row, RowError};
+use crate::options::OptionsError;
+use crate::utils::{report_error, report_notice};
+
+// how to handle the rows which cannot be converted from source data, set by
+// the `on_error` foreign table option
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum OnError {
+    // raise the error and abort the query
+    #[default]
+    Stop,
+
+    // set the invalid cells to null
+    Null,
+
+    // skip the row
+    Skip,
+}
+
+impl OnError {
+    fn from_options(opts: &HashMap<String, String>) -> Result<Self, OptionsError> {
+        match opts.get("on_error").map(|v| v.as_str()) {
+            None | Some("stop") => Ok(Self::Stop),
+            Some("null") => Ok(Self::Null),
+            Some("skip") => Ok(Self::Skip),
+            Some(v) => Err(OptionsError::OptionValueIsInvalid(
+                "on_error".to_string(),
+                v.to_string(),
+                "one of `stop`, `null`, `skip`".to_string(),
+            )),
+        }
+    }
+}
+
+// rejected rows of a foreign scan, they are reported when the scan is ended
+#[derive(Debug, Default)]
+pub(crate) struct Rejects {
+    on_error: OnError,
+
+    // foreign table name, none if join or aggregate is pushed down
+    relname: Option<String>,
+
+    // table to log the rejected source data
+    reject_table: Option<String>,
+
+    // number of rows whose invalid cells are set to null, and number of
+    // skipped rows
+    nulled_rows: i64,
+    skipped_rows: i64,
+
+    // errors to be logged to the reject table
+    errors: Vec<RowError>,
+}
+
+impl Rejects {
+    pub(crate) fn new(
+        opts: &HashMap<String, String>,
+        relname: Option<String>,
+    ) -> Result<Self, OptionsError> {
+        Ok(Self {
+            on_error: OnError::from_options(opts)?,
+            relname,
+            reject_table: opts.get("reject_table").cloned(),
+            ..Default::default()
+        })
+    }
+
+    // handle the conversion errors saved in the row, return false if the row
+    // should be skipped
+    pub(crate) fn check_row(&mut self, row: &mut Row) -> bool {
+        let Some(err) = row.errors.first() else {
+            return true;
+        };
+
+        let is_kept = match self.on_error {
+            OnError::Stop => {
+                report_error(PgSqlErrorCode::ERRCODE_FDW_INVALID_DATA_TYPE, &err.message);
+                return false;
+            }
+            OnError::Null => !row.is_rejected(),
+            OnError::Skip => false,
+        };
+        if is_kept {
+            self.nulled_rows += 1;
+        } else {
+            self.skipped_rows += 1;
+        }
+
+        if self.reject_table.is_some() {
+            self.errors.append(&mut row.errors);
+        } else {
+            row.errors.clear();
+        }
+
+        is_kept
+    }
+
+    // log the rejected source data to the reject table and report the number
+    // of affected rows
+    pub(crate) fn report(&mut self) {
+        self.log_errors();
+
+        let relname = self
+            .relname
+            .as_ref()
+            .map(|name| format!(" of foreign table \"{}\"", name))
+            .unwrap_or_default();
+        if self.nulled_rows > 0 {
+            report_notice(&format!(
+                "{} row(s){} had invalid data set to null",
+                self.nulled_rows, relname
+            ));
+        }
+        if self.skipped_rows > 0 {
+            report_notice(&format!(
+                "{} row(s){} were skipped due to invalid data",
+                self.skipped_rows, relname
+            ));
+        }
+        self.nulled_rows = 0;
+        self.skipped_rows = 0;
+    }
+
+    fn log_errors(&mut self) {
+        let Some(reject_table) = &self.reject_table else {
+            return;
+        };
+        if self.errors.is_empty() {
+            return;
+        }
+
+        let errors = std::mem::take(&mut self.errors);
+        let (columns, raws, messages) = errors.into_iter().fold(
+            (Vec::new(), Vec::new(), Vec::new()),
+            |(mut columns, mut raws, mut messages), err| {
+                columns.push(err.column);
+                raws.push(err.raw);
+                messages.push(err.message);
+                (columns, raws, messages)
+            },
+        );
+
+        // resolve the table name so it is properly quoted in the statement
+        let table = match Spi::get_one_with_args::<String>(
+            "select $1::regclass::text",
+            &[reject_table.as_str().into()],
+        ) {
+            Ok(Some(table)) => table,
+            Ok(None) => return,
+            Err(err) => {
+                report_error(
+                    PgSqlErrorCode::ERRCODE_FDW_ERROR,
+                    &format!("invalid reject table \"{}\": {}", reject_table, err),
+                );
+                return;
+            }
+        };
+        let sql = format!(
+            "insert into {} (relname, column_name, raw, error)
+             select $1, * from unnest($2::text[], $3::text[], $4::text[])",
+            table
+        );
+        if let Err(err) = Spi::run_with_args(
+            &sql,
+            &[
+                self.relname.clone().into(),
+                columns.into(),
+                raws.into(),
+                messages.into(),
+            ],
+        ) {
+            report_error(
+                PgSqlErrorCode::ERRCODE_FDW_ERROR,
+                &format!("log rejected rows to \"{}\" failed: {}", reject_table, err),
+            );
+        }
+    }
+}
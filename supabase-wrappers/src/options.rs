@@ -1,11 +1,17 @@
 use pgrx::list::List;
 use pgrx::pg_sys::panic::ErrorReport;
+use pgrx::pg_sys::Oid;
 use pgrx::{pg_sys, PgSqlErrorCode};
 use std::collections::HashMap;
 use std::ffi::c_void;
 use std::ffi::CStr;
 use thiserror::Error;
 
+use crate::interface::{
+    FOREIGN_DATA_WRAPPER_RELATION_ID, FOREIGN_SERVER_RELATION_ID, FOREIGN_TABLE_RELATION_ID,
+    USER_MAPPING_RELATION_ID,
+};
+
 #[derive(Error, Debug)]
 pub enum OptionsError {
     #[error("required option `{0}` is not specified")]
@@ -14,6 +20,12 @@ pub enum OptionsError {
     OptionNameIsInvalidUtf8(String),
     #[error("option value `{0}` is not a valid UTF-8 string")]
     OptionValueIsInvalidUtf8(String),
+    #[error("option `{0}` value `{1}` is not a valid number")]
+    OptionValueIsInvalidNumber(String, String),
+    #[error("invalid option `{0}` for {1}, valid options are: {2}")]
+    OptionNameIsInvalid(String, String, String),
+    #[error("option `{0}` value `{1}` is invalid, expected {2}")]
+    OptionValueIsInvalid(String, String, String),
 }
 
 impl From<OptionsError> for ErrorReport {
@@ -31,6 +43,17 @@ impl From<OptionsError> for ErrorReport {
                 error_message,
                 "",
             ),
+            OptionsError::OptionNameIsInvalid(_, _, _) => ErrorReport::new(
+                PgSqlErrorCode::ERRCODE_FDW_INVALID_OPTION_NAME,
+                error_message,
+                "",
+            ),
+            OptionsError::OptionValueIsInvalidNumber(_, _)
+            | OptionsError::OptionValueIsInvalid(_, _, _) => ErrorReport::new(
+                PgSqlErrorCode::ERRCODE_FDW_INVALID_ATTRIBUTE_VALUE,
+                error_message,
+                "",
+            ),
         }
     }
 }
@@ -82,6 +105,38 @@ pub fn require_option_or<'a>(
     options.get(opt_name).map(|t| t.as_ref()).unwrap_or(default)
 }
 
+/// Get floating point option value from the `options` map or a provided default
+///
+/// Get the option's value from `options` map and parse it as `f64`, return
+/// default if it does not exist, or error if it is not a valid number.
+///
+/// For example,
+///
+/// ```rust,no_run
+/// # use supabase_wrappers::prelude::require_option_f64_or;
+/// # use std::collections::HashMap;
+/// # use supabase_wrappers::options::OptionsError;
+/// # fn main() -> Result<(), OptionsError> {
+/// # let options = &HashMap::new();
+/// require_option_f64_or("my_option", options, 1.0)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn require_option_f64_or(
+    opt_name: &str,
+    options: &HashMap<String, String>,
+    default: f64,
+) -> Result<f64, OptionsError> {
+    options
+        .get(opt_name)
+        .map(|v| {
+            v.parse::<f64>().map_err(|_| {
+                OptionsError::OptionValueIsInvalidNumber(opt_name.to_string(), v.to_string())
+            })
+        })
+        .unwrap_or(Ok(default))
+}
+
 /// Check if the option list contains a specific option, used in [validator](crate::interface::ForeignDataWrapper::validator)
 pub fn check_options_contain(opt_list: &[Option<String>], tgt: &str) -> Result<(), OptionsError> {
     let search_key = format!("{}=", tgt);
@@ -96,6 +151,293 @@ pub fn check_options_contain(opt_list: &[Option<String>], tgt: &str) -> Result<(
     }
 }
 
+/// Where an option can be specified, used in [`OptionDef`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionContext {
+    /// `CREATE FOREIGN DATA WRAPPER`
+    Wrapper,
+    /// `CREATE SERVER`
+    Server,
+    /// `CREATE USER MAPPING`
+    UserMapping,
+    /// `CREATE FOREIGN TABLE`
+    Table,
+    /// `IMPORT FOREIGN SCHEMA`
+    Import,
+}
+
+impl OptionContext {
+    /// Get the context from the catalog oid passed to [validator](crate::interface::ForeignDataWrapper::validator)
+    pub fn from_catalog(catalog: Oid) -> Option<Self> {
+        match catalog {
+            FOREIGN_DATA_WRAPPER_RELATION_ID => Some(Self::Wrapper),
+            FOREIGN_SERVER_RELATION_ID => Some(Self::Server),
+            USER_MAPPING_RELATION_ID => Some(Self::UserMapping),
+            FOREIGN_TABLE_RELATION_ID => Some(Self::Table),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Wrapper => "foreign data wrapper",
+            Self::Server => "server",
+            Self::UserMapping => "user mapping",
+            Self::Table => "foreign table",
+            Self::Import => "import foreign schema",
+        }
+    }
+}
+
+/// Value type of an option, used in [`OptionDef`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionType {
+    /// any string
+    String,
+    /// `true` or `false`
+    Bool,
+    /// 64-bit integer
+    Integer,
+    /// 64-bit floating point number
+    Float,
+}
+
+impl OptionType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Bool => "boolean",
+            Self::Integer => "integer",
+            Self::Float => "number",
+        }
+    }
+
+    fn is_valid(&self, value: &str) -> bool {
+        match self {
+            Self::String => true,
+            Self::Bool => value == "true" || value == "false",
+            Self::Integer => value.parse::<i64>().is_ok(),
+            Self::Float => value.parse::<f64>().is_ok(),
+        }
+    }
+}
+
+/// Definition of an option supported by a foreign data wrapper, returned by
+/// [`option_defs`](crate::interface::ForeignDataWrapper::option_defs)
+///
+/// For example,
+///
+/// ```rust,no_run
+/// # use supabase_wrappers::prelude::*;
+/// OptionDef::new("format", &[OptionContext::Table])
+///     .required()
+///     .allowed(&["csv", "jsonl", "parquet"])
+///     .description("File format");
+/// OptionDef::new("api_key", &[OptionContext::Server, OptionContext::UserMapping])
+///     .secret()
+///     .description("API key");
+/// ```
+#[derive(Debug, Clone)]
+pub struct OptionDef {
+    pub name: &'static str,
+    pub contexts: &'static [OptionContext],
+    pub opt_type: OptionType,
+    pub default: Option<&'static str>,
+    pub allowed: &'static [&'static str],
+    pub required: bool,
+    pub secret: bool,
+    pub description: &'static str,
+}
+
+impl OptionDef {
+    /// Create an optional string option which can be specified in `contexts`
+    pub fn new(name: &'static str, contexts: &'static [OptionContext]) -> Self {
+        Self {
+            name,
+            contexts,
+            opt_type: OptionType::String,
+            default: None,
+            allowed: &[],
+            required: false,
+            secret: false,
+            description: "",
+        }
+    }
+
+    /// Create a definition which accepts any other option in `contexts`, for
+    /// the options passed through to the data source as they are
+    pub fn any(contexts: &'static [OptionContext]) -> Self {
+        Self::new("*", contexts).description("Any other option")
+    }
+
+    fn is_any(&self) -> bool {
+        self.name == "*"
+    }
+
+    pub fn opt_type(mut self, opt_type: OptionType) -> Self {
+        self.opt_type = opt_type;
+        self
+    }
+
+    /// Set the default value, it is for documentation only
+    pub fn default(mut self, default: &'static str) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// Set the allowed values, any other value is rejected
+    pub fn allowed(mut self, allowed: &'static [&'static str]) -> Self {
+        self.allowed = allowed;
+        self
+    }
+
+    /// Make the option required in all of its contexts
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Mark the option as a secret, like a password or API key
+    pub fn secret(mut self) -> Self {
+        self.secret = true;
+        self
+    }
+
+    pub fn description(mut self, description: &'static str) -> Self {
+        self.description = description;
+        self
+    }
+
+    fn validate(&self, value: &str) -> Result<(), OptionsError> {
+        let expected = if !self.allowed.is_empty() {
+            if self.allowed.contains(&value) {
+                return Ok(());
+            }
+            let allowed = self
+                .allowed
+                .iter()
+                .map(|v| format!("`{}`", v))
+                .collect::<Vec<_>>();
+            format!("one of {}", allowed.join(", "))
+        } else {
+            if self.opt_type.is_valid(value) {
+                return Ok(());
+            }
+            self.opt_type.name().to_string()
+        };
+        Err(OptionsError::OptionValueIsInvalid(
+            self.name.to_string(),
+            value.to_string(),
+            expected,
+        ))
+    }
+}
+
+/// Get the definitions of options used by all foreign data wrappers, such as
+/// the cost options used in [get_path_costs](crate::interface::ForeignDataWrapper::get_path_costs),
+/// the `on_error` option used in [iter_scan](crate::interface::ForeignDataWrapper::iter_scan)
+/// and the `batch_size` option used in [insert](crate::interface::ForeignDataWrapper::insert)
+pub fn common_option_defs() -> Vec<OptionDef> {
+    vec![
+        OptionDef::new(
+            "fdw_startup_cost",
+            &[OptionContext::Server, OptionContext::Table],
+        )
+        .opt_type(OptionType::Float)
+        .default("0")
+        .description("Cost of starting up a foreign scan"),
+        OptionDef::new(
+            "fdw_tuple_cost",
+            &[OptionContext::Server, OptionContext::Table],
+        )
+        .opt_type(OptionType::Float)
+        .default("1")
+        .description("Cost of transferring one row from the data source"),
+        OptionDef::new("startup_cost", &[OptionContext::Table])
+            .opt_type(OptionType::Float)
+            .description("Overrides `fdw_startup_cost` for the table"),
+        OptionDef::new("on_error", &[OptionContext::Table])
+            .allowed(&["stop", "null", "skip"])
+            .default("stop")
+            .description("How to handle the rows which cannot be converted from source data"),
+        OptionDef::new("reject_table", &[OptionContext::Table])
+            .description("Table to log the rows rejected by `on_error`"),
+        OptionDef::new("batch_size", &[OptionContext::Server, OptionContext::Table])
+            .opt_type(OptionType::Integer)
+            .default("1")
+            .description("Number of rows inserted in one batch"),
+    ]
+}
+
+/// Validate the options against their definitions, used in the validator
+/// function generated by [`wrappers_fdw`](crate::wrappers_fdw)
+///
+/// Unknown options, invalid values and missing required options are rejected.
+/// The [common options](common_option_defs) are always accepted and their
+/// values are always validated, other options are accepted without check if
+/// there is an [`OptionDef::any`] definition for the context.
+pub fn validate_options(
+    defs: &[OptionDef],
+    opt_list: &[Option<String>],
+    catalog: Option<Oid>,
+) -> Result<(), OptionsError> {
+    let Some(ctx) = catalog.and_then(OptionContext::from_catalog) else {
+        return Ok(());
+    };
+    let options = opt_list
+        .iter()
+        .flatten()
+        .filter_map(|opt| opt.split_once('='));
+    validate_option_pairs(defs, ctx, options)
+}
+
+pub(super) fn validate_option_pairs<'a>(
+    defs: &[OptionDef],
+    ctx: OptionContext,
+    options: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Result<(), OptionsError> {
+    let defs = defs
+        .iter()
+        .cloned()
+        .chain(common_option_defs())
+        .filter(|def| def.contexts.contains(&ctx))
+        .collect::<Vec<_>>();
+    let accepts_any = defs.iter().any(|def| def.is_any());
+
+    let mut specified = Vec::new();
+    for (name, value) in options {
+        let Some(def) = defs.iter().find(|def| def.name == name) else {
+            if accepts_any {
+                continue;
+            }
+            let valid = if defs.is_empty() {
+                "none".to_string()
+            } else {
+                defs.iter()
+                    .map(|def| def.name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            return Err(OptionsError::OptionNameIsInvalid(
+                name.to_string(),
+                ctx.name().to_string(),
+                valid,
+            ));
+        };
+        def.validate(value)?;
+        specified.push(name);
+    }
+
+    if let Some(def) = defs
+        .iter()
+        .find(|def| def.required && !specified.contains(&def.name))
+    {
+        return Err(OptionsError::OptionNameNotFound(def.name.to_string()));
+    }
+
+    Ok(())
+}
+
 // convert options definition to hashmap
 pub(super) unsafe fn options_to_hashmap(
     options: *mut pg_sys::List,
@@ -0,0 +1,174 @@
+use crate::interface::{Cell, Param, ParamKind, Qual, Value};
+use crate::qual::{get_operator, unnest_clause};
+use pgrx::{is_a, pg_guard, pg_sys, PgList};
+use std::ffi::{c_void, CStr, CString};
+use std::os::raw::c_int;
+
+// check if the node is a plain column of the relation
+unsafe fn is_rel_column(node: *mut pg_sys::Node, relid: pg_sys::Index) -> bool {
+    let node = unnest_clause(node);
+    if !is_a(node, pg_sys::NodeTag::T_Var) {
+        return false;
+    }
+    let var = node as *mut pg_sys::Var;
+    (*var).varno as pg_sys::Index == relid && (*var).varattno >= 1
+}
+
+// callback for generate_implied_equalities_for_column(), check if the
+// equivalence member is the column whose attribute number is passed in arg
+#[pg_guard]
+extern "C-unwind" fn ec_member_matches_column(
+    _root: *mut pg_sys::PlannerInfo,
+    rel: *mut pg_sys::RelOptInfo,
+    _ec: *mut pg_sys::EquivalenceClass,
+    em: *mut pg_sys::EquivalenceMember,
+    arg: *mut c_void,
+) -> bool {
+    unsafe {
+        let attno = *(arg as *mut pg_sys::AttrNumber);
+        let expr = unnest_clause((*em).em_expr as _);
+        if !is_a(expr, pg_sys::NodeTag::T_Var) {
+            return false;
+        }
+        let var = expr as *mut pg_sys::Var;
+        (*var).varno as pg_sys::Index == (*rel).relid && (*var).varattno == attno
+    }
+}
+
+// get join clauses on the lookup columns, whose other side is from other
+// relations and can be supplied by the outer relation of a nested loop join
+pub(crate) unsafe fn get_lookup_clauses(
+    root: *mut pg_sys::PlannerInfo,
+    baserel: *mut pg_sys::RelOptInfo,
+    baserel_id: pg_sys::Oid,
+    lookup_cols: &[String],
+) -> Vec<*mut pg_sys::RestrictInfo> {
+    let mut clauses = Vec::new();
+    let relid = (*baserel).relid;
+
+    for col in lookup_cols {
+        let col_name = CString::new(col.as_str()).unwrap();
+        let mut attno = pg_sys::get_attnum(baserel_id, col_name.as_ptr());
+        if attno == pg_sys::InvalidAttrNumber as pg_sys::AttrNumber {
+            continue;
+        }
+
+        // equality join clauses are in the equivalence classes
+        let ec_clauses = pg_sys::generate_implied_equalities_for_column(
+            root,
+            baserel,
+            Some(ec_member_matches_column),
+            &mut attno as *mut _ as _,
+            (*baserel).lateral_referencers,
+        );
+        clauses.extend(PgList::<pg_sys::RestrictInfo>::from_pg(ec_clauses).iter_ptr());
+
+        // other join clauses, like outer join conditions, are in join info list
+        let joininfo = PgList::<pg_sys::RestrictInfo>::from_pg((*baserel).joininfo);
+        for rinfo in joininfo.iter_ptr() {
+            if !pg_sys::join_clause_is_movable_to(rinfo, baserel) {
+                continue;
+            }
+
+            let clause = (*rinfo).clause as *mut pg_sys::Node;
+            if !is_a(clause, pg_sys::NodeTag::T_OpExpr) {
+                continue;
+            }
+            let args = PgList::<pg_sys::Node>::from_pg((*(clause as *mut pg_sys::OpExpr)).args);
+            if args.len() != 2 {
+                continue;
+            }
+
+            let is_lookup_col = |node: *mut pg_sys::Node| {
+                let node = unnest_clause(node);
+                is_rel_column(node, relid) && (*(node as *mut pg_sys::Var)).varattno == attno
+            };
+            let (left, right) = (args.get_ptr(0).unwrap(), args.get_ptr(1).unwrap());
+            if (is_lookup_col(left)
+                && !pg_sys::bms_is_member(relid as c_int, (*rinfo).right_relids))
+                || (is_lookup_col(right)
+                    && !pg_sys::bms_is_member(relid as c_int, (*rinfo).left_relids))
+            {
+                clauses.push(rinfo);
+            }
+        }
+    }
+
+    clauses
+}
+
+// extract quals from the parameterized join clauses, the values of them are
+// evaluated from the returned expressions at execution time
+//
+// The expressions are appended to `exprs` and the returned expression list is
+// used as `fdw_exprs` of the foreign scan, the outer relation columns in it are
+// replaced with executor parameters by planner.
+pub(crate) unsafe fn extract_param_quals(
+    baserel: *mut pg_sys::RelOptInfo,
+    baserel_id: pg_sys::Oid,
+    clauses: *mut pg_sys::List,
+    mut exprs: *mut pg_sys::List,
+) -> (Vec<Qual>, *mut pg_sys::List) {
+    let mut quals = Vec::new();
+    let relid = (*baserel).relid;
+
+    let rinfos = PgList::<pg_sys::RestrictInfo>::from_pg(clauses);
+    for rinfo in rinfos.iter_ptr() {
+        let clause = (*rinfo).clause as *mut pg_sys::Node;
+        if !is_a(clause, pg_sys::NodeTag::T_OpExpr) {
+            continue;
+        }
+
+        let expr = clause as *mut pg_sys::OpExpr;
+        let args = PgList::<pg_sys::Node>::from_pg((*expr).args);
+        if args.len() != 2 {
+            continue;
+        }
+
+        let opr = get_operator((*expr).opno);
+        if opr.is_null() {
+            continue;
+        }
+
+        let (left, right) = (args.get_ptr(0).unwrap(), args.get_ptr(1).unwrap());
+        let (var, outer, opr) = if is_rel_column(left, relid)
+            && !pg_sys::bms_is_member(relid as c_int, (*rinfo).right_relids)
+        {
+            (left, right, opr)
+        } else if is_rel_column(right, relid)
+            && !pg_sys::bms_is_member(relid as c_int, (*rinfo).left_relids)
+            && (*opr).oprcom != pg_sys::Oid::INVALID
+        {
+            // swap operands by using the commutator operator
+            let com = get_operator((*opr).oprcom);
+            if com.is_null() {
+                continue;
+            }
+            (right, left, com)
+        } else {
+            continue;
+        };
+
+        let var = unnest_clause(var) as *mut pg_sys::Var;
+        let field = pg_sys::get_attname(baserel_id, (*var).varattno, false);
+
+        exprs = pg_sys::lappend(exprs, outer as _);
+
+        // add a dummy value, the actual value will be evaluated from the
+        // expression in execution state
+        quals.push(Qual {
+            field: CStr::from_ptr(field).to_str().unwrap().to_string(),
+            operator: pgrx::name_data_to_str(&(*opr).oprname).to_string(),
+            value: Value::Cell(Cell::I64(0)),
+            use_or: false,
+            param: Some(Param {
+                id: (*exprs).length as usize,
+                type_oid: pg_sys::exprType(outer),
+                kind: ParamKind::Exec,
+            }),
+            field_expr: None,
+        });
+    }
+
+    (quals, exprs)
+}
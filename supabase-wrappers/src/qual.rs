@@ -4,15 +4,15 @@ use pgrx::{
     datum::{Array, Date, JsonB, Timestamp},
     is_a,
     list::List,
-    pg_sys,
+    pg_guard, pg_sys,
     pg_sys::Datum,
-    FromDatum, PgBuiltInOids, PgOid,
+    FromDatum, PgBuiltInOids, PgList, PgOid,
 };
 use std::ffi::c_void;
 use std::ffi::CStr;
-use std::os::raw::c_int;
+use std::os::raw::{c_char, c_int};
 
-use crate::interface::Param;
+use crate::interface::{Param, ParamKind};
 
 // create array of Cell from constant datum array
 pub(crate) unsafe fn form_array_from_datum(
@@ -140,11 +140,207 @@ pub(crate) unsafe fn unnest_clause(node: *mut pg_sys::Node) -> *mut pg_sys::Node
     }
 }
 
+// callback for expression_tree_walker(), check if the expression has any
+// parameters other than external ones, whose values are unknown when the scan
+// is begun
+#[pg_guard]
+extern "C-unwind" fn contain_non_extern_param_walker(
+    node: *mut pg_sys::Node,
+    context: *mut c_void,
+) -> bool {
+    unsafe {
+        if node.is_null() {
+            return false;
+        }
+        if is_a(node, pg_sys::NodeTag::T_Param) {
+            return (*(node as *mut pg_sys::Param)).paramkind != pg_sys::ParamKind::PARAM_EXTERN;
+        }
+        pg_sys::expression_tree_walker(node, Some(contain_non_extern_param_walker), context)
+    }
+}
+
+// check if the expression is stable, that is, it doesn't reference any columns
+// and its value doesn't change during the scan, like `now()` or
+// `current_date - 7`, so it can be evaluated once at the beginning of scan
+unsafe fn is_stable_expr(node: *mut pg_sys::Node) -> bool {
+    !pg_sys::contain_var_clause(node)
+        && !pg_sys::contain_volatile_functions(node)
+        && !pg_sys::contain_subplans(node)
+        && !contain_non_extern_param_walker(node, std::ptr::null_mut())
+}
+
+// get the stable expression operand of an operator expression, which is
+// extracted as a qual with stable parameter
+unsafe fn get_stable_operand(expr: *mut pg_sys::OpExpr) -> *mut pg_sys::Node {
+    let args = PgList::<pg_sys::Node>::from_pg((*expr).args);
+    let left = unnest_clause(args.get_ptr(0).unwrap());
+    let right = unnest_clause(args.get_ptr(1).unwrap());
+    if is_a(right, pg_sys::NodeTag::T_Var) {
+        left
+    } else {
+        right
+    }
+}
+
+// extract the value side of an operator expression, it can be a constant, a
+// query parameter or a stable expression
+unsafe fn extract_qual_value(right: *mut pg_sys::Node) -> Option<(Cell, Option<Param>)> {
+    if is_a(right, pg_sys::NodeTag::T_Const) {
+        let right = right as *mut pg_sys::Const;
+        Cell::from_polymorphic_datum(
+            (*right).constvalue,
+            (*right).constisnull,
+            (*right).consttype,
+        )
+        .map(|cell| (cell, None))
+    } else if is_a(right, pg_sys::NodeTag::T_Param) {
+        // add a dummy value if this is query parameter, the actual value
+        // will be extracted from execution state
+        let right = right as *mut pg_sys::Param;
+        let param = Param::new((*right).paramid as _, (*right).paramtype);
+        Some((Cell::I64(0), Some(param)))
+    } else if is_stable_expr(right) {
+        // add a dummy value if this is stable expression, the actual value
+        // will be evaluated when the scan is begun, the parameter id is
+        // assigned after all quals are extracted
+        let param = Param {
+            id: 0,
+            type_oid: pg_sys::exprType(right),
+            kind: ParamKind::Stable,
+        };
+        Some((Cell::I64(0), Some(param)))
+    } else {
+        None
+    }
+}
+
+// extract an argument of the column side expression, the functions and JSON
+// path operators in it must be in the allowlist
+unsafe fn extract_field_arg(
+    baserel_id: pg_sys::Oid,
+    baserel_ids: pg_sys::Relids,
+    node: *mut pg_sys::Node,
+    allowlist: &[&str],
+) -> Option<FieldArg> {
+    let node = unnest_clause(node);
+    if is_a(node, pg_sys::NodeTag::T_Var) {
+        let var = node as *mut pg_sys::Var;
+        if !pg_sys::bms_is_member((*var).varno as c_int, baserel_ids) || (*var).varattno < 1 {
+            return None;
+        }
+        let field = pg_sys::get_attname(baserel_id, (*var).varattno, false);
+        return Some(FieldArg::Column(
+            CStr::from_ptr(field).to_str().unwrap().to_string(),
+        ));
+    }
+
+    if is_a(node, pg_sys::NodeTag::T_Const) {
+        let cnst = node as *mut pg_sys::Const;
+        return Cell::from_polymorphic_datum(
+            (*cnst).constvalue,
+            (*cnst).constisnull,
+            (*cnst).consttype,
+        )
+        .map(FieldArg::Cell);
+    }
+
+    if is_a(node, pg_sys::NodeTag::T_FuncExpr) {
+        // implicit casts are not function calls written in query
+        let expr = node as *mut pg_sys::FuncExpr;
+        if (*expr).funcformat != pg_sys::CoercionForm::COERCE_EXPLICIT_CALL
+            || pg_sys::func_volatile((*expr).funcid) == pg_sys::PROVOLATILE_VOLATILE as c_char
+        {
+            return None;
+        }
+        let name = pg_sys::get_func_name((*expr).funcid);
+        if name.is_null() {
+            return None;
+        }
+        let name = CStr::from_ptr(name).to_str().unwrap().to_string();
+        if !allowlist.contains(&name.as_str()) {
+            return None;
+        }
+        let args = PgList::<pg_sys::Node>::from_pg((*expr).args)
+            .iter_ptr()
+            .map(|arg| extract_field_arg(baserel_id, baserel_ids, arg, allowlist))
+            .collect::<Option<Vec<_>>>()?;
+        return Some(FieldArg::Expr(FieldExpr::Func { name, args }));
+    }
+
+    if is_a(node, pg_sys::NodeTag::T_OpExpr) {
+        let expr = node as *mut pg_sys::OpExpr;
+        let opr = get_operator((*expr).opno);
+        if opr.is_null() {
+            return None;
+        }
+        let opname = pgrx::name_data_to_str(&(*opr).oprname);
+        let as_text = match opname {
+            "->" | "#>" => false,
+            "->>" | "#>>" => true,
+            _ => return None,
+        };
+        if !allowlist.contains(&opname) {
+            return None;
+        }
+
+        let args = PgList::<pg_sys::Node>::from_pg((*expr).args);
+        if args.len() != 2 {
+            return None;
+        }
+
+        // the path keys must be constants, either a text key, an array index
+        // or a text array path
+        let key = unnest_clause(args.get_ptr(1).unwrap());
+        if !is_a(key, pg_sys::NodeTag::T_Const) {
+            return None;
+        }
+        let key = key as *mut pg_sys::Const;
+        let keys = if (*key).consttype == pg_sys::TEXTARRAYOID {
+            form_array_from_datum((*key).constvalue, (*key).constisnull, (*key).consttype)?
+        } else {
+            vec![Cell::from_polymorphic_datum(
+                (*key).constvalue,
+                (*key).constisnull,
+                (*key).consttype,
+            )?]
+        };
+        let keys = keys
+            .iter()
+            .map(|key| match key {
+                Cell::String(s) => Some(s.clone()),
+                Cell::I32(i) => Some(i.to_string()),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        // chained JSON path operators are merged into one path
+        let json = extract_field_arg(baserel_id, baserel_ids, args.get_ptr(0).unwrap(), allowlist)?;
+        let (column, mut path) = match json {
+            FieldArg::Column(column) => (column, Vec::new()),
+            FieldArg::Expr(FieldExpr::JsonPath {
+                column,
+                path,
+                as_text: false,
+            }) => (column, path),
+            _ => return None,
+        };
+        path.extend(keys);
+        return Some(FieldArg::Expr(FieldExpr::JsonPath {
+            column,
+            path,
+            as_text,
+        }));
+    }
+
+    None
+}
+
 pub(crate) unsafe fn extract_from_op_expr(
     _root: *mut pg_sys::PlannerInfo,
     baserel_id: pg_sys::Oid,
     baserel_ids: pg_sys::Relids,
     expr: *mut pg_sys::OpExpr,
+    allowlist: &[&str],
 ) -> Option<Qual> {
     pgrx::memcx::current_context(|mcx| {
         if let Some(args) = List::<*mut c_void>::downcast_ptr_in_memcx((*expr).args, mcx) {
@@ -156,7 +352,7 @@ pub(crate) unsafe fn extract_from_op_expr(
 
             // get operator
             let opno = (*expr).opno;
-            let opr = get_operator(opno);
+            let mut opr = get_operator(opno);
             if opr.is_null() {
                 report_warning("operator is empty");
                 return None;
@@ -165,55 +361,55 @@ pub(crate) unsafe fn extract_from_op_expr(
             let mut left = unnest_clause(*args.get(0).unwrap() as _);
             let mut right = unnest_clause(*args.get(1).unwrap() as _);
 
-            // swap operands if needed
+            // swap operands if needed, by using the commutator operator, for
+            // example, `5 < col` becomes `col > 5`
             if is_a(right, pg_sys::NodeTag::T_Var)
                 && !is_a(left, pg_sys::NodeTag::T_Var)
                 && (*opr).oprcom != Oid::INVALID
             {
+                let com = get_operator((*opr).oprcom);
+                if com.is_null() {
+                    report_warning("commutator operator is empty");
+                    return None;
+                }
+                opr = com;
                 std::mem::swap(&mut left, &mut right);
             }
 
-            if is_a(left, pg_sys::NodeTag::T_Var) {
+            // the column side is either a column or an expression on columns
+            let field = if is_a(left, pg_sys::NodeTag::T_Var) {
                 let left = left as *mut pg_sys::Var;
-
                 if pg_sys::bms_is_member((*left).varno as c_int, baserel_ids)
                     && (*left).varattno >= 1
                 {
                     let field = pg_sys::get_attname(baserel_id, (*left).varattno, false);
+                    Some((CStr::from_ptr(field).to_str().unwrap().to_string(), None))
+                } else {
+                    None
+                }
+            } else if !allowlist.is_empty() {
+                match extract_field_arg(baserel_id, baserel_ids, left, allowlist) {
+                    Some(FieldArg::Expr(field_expr)) => field_expr
+                        .column()
+                        .map(|col| col.to_string())
+                        .map(|col| (col, Some(field_expr))),
+                    _ => None,
+                }
+            } else {
+                None
+            };
 
-                    let (value, param) = if is_a(right, pg_sys::NodeTag::T_Const) {
-                        let right = right as *mut pg_sys::Const;
-                        (
-                            Cell::from_polymorphic_datum(
-                                (*right).constvalue,
-                                (*right).constisnull,
-                                (*right).consttype,
-                            ),
-                            None,
-                        )
-                    } else if is_a(right, pg_sys::NodeTag::T_Param) {
-                        // add a dummy value if this is query parameter, the actual value
-                        // will be extracted from execution state
-                        let right = right as *mut pg_sys::Param;
-                        let param = Param {
-                            id: (*right).paramid as _,
-                            type_oid: (*right).paramtype,
-                        };
-                        (Some(Cell::I64(0)), Some(param))
-                    } else {
-                        (None, None)
+            if let Some((field, field_expr)) = field {
+                if let Some((value, param)) = extract_qual_value(right) {
+                    let qual = Qual {
+                        field,
+                        operator: pgrx::name_data_to_str(&(*opr).oprname).to_string(),
+                        value: Value::Cell(value),
+                        use_or: false,
+                        param,
+                        field_expr,
                     };
-
-                    if let Some(value) = value {
-                        let qual = Qual {
-                            field: CStr::from_ptr(field).to_str().unwrap().to_string(),
-                            operator: pgrx::name_data_to_str(&(*opr).oprname).to_string(),
-                            value: Value::Cell(value),
-                            use_or: false,
-                            param,
-                        };
-                        return Some(qual);
-                    }
+                    return Some(qual);
                 }
             }
 
@@ -249,6 +445,7 @@ pub(crate) unsafe fn extract_from_null_test(
         value: Value::Cell(Cell::String("null".to_string())),
         use_or: false,
         param: None,
+        field_expr: None,
     };
 
     Some(qual)
@@ -298,6 +495,7 @@ pub(crate) unsafe fn extract_from_scalar_array_op_expr(
                             value: Value::Array(value),
                             use_or: (*expr).useOr,
                             param: None,
+                            field_expr: None,
                         };
                         return Some(qual);
                     }
@@ -334,6 +532,7 @@ pub(crate) unsafe fn extract_from_var(
         value: Value::Cell(Cell::Bool(true)),
         use_or: false,
         param: None,
+        field_expr: None,
     };
 
     Some(qual)
@@ -352,7 +551,8 @@ pub(crate) unsafe fn extract_from_bool_expr(
             }
 
             let var = *args.get(0).unwrap() as *mut pg_sys::Var;
-            if (*var).varattno < 1
+            if !is_a(var as _, pg_sys::NodeTag::T_Var)
+                || (*var).varattno < 1
                 || (*var).vartype != pg_sys::BOOLOID
                 || !pg_sys::bms_is_member((*var).varno as c_int, baserel_ids)
             {
@@ -367,6 +567,7 @@ pub(crate) unsafe fn extract_from_bool_expr(
                 value: Value::Cell(Cell::Bool(false)),
                 use_or: false,
                 param: None,
+                field_expr: None,
             };
 
             return Some(qual);
@@ -401,54 +602,156 @@ pub(crate) unsafe fn extract_from_boolean_test(
         value: Value::Cell(Cell::Bool(value)),
         use_or: false,
         param: None,
+        field_expr: None,
     };
 
     Some(qual)
 }
 
+// extract a single qual from an expression
+unsafe fn extract_qual(
+    root: *mut pg_sys::PlannerInfo,
+    baserel_id: pg_sys::Oid,
+    baserel_ids: pg_sys::Relids,
+    expr: *mut pg_sys::Node,
+    allowlist: &[&str],
+) -> Option<Qual> {
+    if is_a(expr, pg_sys::NodeTag::T_OpExpr) {
+        extract_from_op_expr(root, baserel_id, baserel_ids, expr as _, allowlist)
+    } else if is_a(expr, pg_sys::NodeTag::T_NullTest) {
+        extract_from_null_test(baserel_id, expr as _)
+    } else if is_a(expr, pg_sys::NodeTag::T_ScalarArrayOpExpr) {
+        extract_from_scalar_array_op_expr(root, baserel_id, baserel_ids, expr as _)
+    } else if is_a(expr, pg_sys::NodeTag::T_Var) {
+        extract_from_var(root, baserel_id, baserel_ids, expr as _)
+    } else if is_a(expr, pg_sys::NodeTag::T_BoolExpr) {
+        extract_from_bool_expr(root, baserel_id, baserel_ids, expr as _)
+    } else if is_a(expr, pg_sys::NodeTag::T_BooleanTest) {
+        extract_from_boolean_test(baserel_id, expr as _)
+    } else {
+        if let Some(stm) = pgrx::nodes::node_to_string(expr) {
+            report_warning(&format!("unsupported qual: {}", stm));
+        }
+        None
+    }
+}
+
+// extract a boolean expression tree, all of its leaves must be extracted as
+// quals without parameters, otherwise the whole tree is not extracted
+unsafe fn extract_filter(
+    root: *mut pg_sys::PlannerInfo,
+    baserel_id: pg_sys::Oid,
+    baserel_ids: pg_sys::Relids,
+    expr: *mut pg_sys::Node,
+    allowlist: &[&str],
+) -> Option<Filter> {
+    if !is_a(expr, pg_sys::NodeTag::T_BoolExpr) {
+        return extract_qual(root, baserel_id, baserel_ids, expr, allowlist)
+            .filter(|qual| qual.param.is_none())
+            .map(Filter::Qual);
+    }
+
+    let expr = expr as *mut pg_sys::BoolExpr;
+    let mut args = pgrx::memcx::current_context(|mcx| {
+        List::<*mut c_void>::downcast_ptr_in_memcx((*expr).args, mcx)
+            .map(|args| {
+                args.iter()
+                    .map(|arg| *arg as *mut pg_sys::Node)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    })
+    .into_iter()
+    .map(|arg| extract_filter(root, baserel_id, baserel_ids, arg, allowlist))
+    .collect::<Option<Vec<_>>>()?;
+
+    match (*expr).boolop {
+        pg_sys::BoolExprType::AND_EXPR => Some(Filter::And(args)),
+        pg_sys::BoolExprType::OR_EXPR => Some(Filter::Or(args)),
+        pg_sys::BoolExprType::NOT_EXPR if args.len() == 1 => {
+            Some(Filter::Not(Box::new(args.remove(0))))
+        }
+        _ => None,
+    }
+}
+
+// get restriction clauses of a base relation
+unsafe fn get_restriction_clauses(baserel: *mut pg_sys::RelOptInfo) -> Vec<*mut pg_sys::Node> {
+    pgrx::memcx::current_context(|mcx| {
+        List::<*mut c_void>::downcast_ptr_in_memcx((*baserel).baserestrictinfo, mcx)
+            .map(|conds| {
+                conds
+                    .iter()
+                    .map(|cond| (*(*cond as *mut pg_sys::RestrictInfo)).clause as *mut pg_sys::Node)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    })
+}
+
 pub(crate) unsafe fn extract_quals(
     root: *mut pg_sys::PlannerInfo,
     baserel: *mut pg_sys::RelOptInfo,
     baserel_id: pg_sys::Oid,
-) -> Vec<Qual> {
-    pgrx::memcx::current_context(|mcx| {
-        let mut quals = Vec::new();
+    allowlist: &[&str],
+) -> (Vec<Qual>, Vec<*mut pg_sys::Node>) {
+    let clauses = get_restriction_clauses(baserel);
+    extract_quals_from_clauses(root, baserel_id, (*baserel).relids, &clauses, allowlist)
+}
 
-        if let Some(conds) =
-            List::<*mut c_void>::downcast_ptr_in_memcx((*baserel).baserestrictinfo, mcx)
-        {
-            for cond in conds.iter() {
-                let expr = (*(*cond as *mut pg_sys::RestrictInfo)).clause as *mut pg_sys::Node;
-                let extracted = if is_a(expr, pg_sys::NodeTag::T_OpExpr) {
-                    extract_from_op_expr(root, baserel_id, (*baserel).relids, expr as _)
-                } else if is_a(expr, pg_sys::NodeTag::T_NullTest) {
-                    extract_from_null_test(baserel_id, expr as _)
-                } else if is_a(expr, pg_sys::NodeTag::T_ScalarArrayOpExpr) {
-                    extract_from_scalar_array_op_expr(
-                        root,
-                        baserel_id,
-                        (*baserel).relids,
-                        expr as _,
-                    )
-                } else if is_a(expr, pg_sys::NodeTag::T_Var) {
-                    extract_from_var(root, baserel_id, (*baserel).relids, expr as _)
-                } else if is_a(expr, pg_sys::NodeTag::T_BoolExpr) {
-                    extract_from_bool_expr(root, baserel_id, (*baserel).relids, expr as _)
-                } else if is_a(expr, pg_sys::NodeTag::T_BooleanTest) {
-                    extract_from_boolean_test(baserel_id, expr as _)
-                } else {
-                    if let Some(stm) = pgrx::nodes::node_to_string(expr) {
-                        report_warning(&format!("unsupported qual: {}", stm));
-                    }
-                    None
-                };
+pub(crate) unsafe fn extract_filters(
+    root: *mut pg_sys::PlannerInfo,
+    baserel: *mut pg_sys::RelOptInfo,
+    baserel_id: pg_sys::Oid,
+    allowlist: &[&str],
+) -> Vec<Filter> {
+    let clauses = get_restriction_clauses(baserel);
+    extract_filters_from_clauses(root, baserel_id, (*baserel).relids, &clauses, allowlist)
+}
 
-                if let Some(qual) = extracted {
-                    quals.push(qual);
-                }
-            }
+// extract quals from restriction clauses, the clauses can be from either
+// relation restrictions or an executor plan's qual list
+//
+// The stable expressions of quals are also returned, each stable parameter id
+// is the 1-based position of its expression in the list.
+pub(crate) unsafe fn extract_quals_from_clauses(
+    root: *mut pg_sys::PlannerInfo,
+    baserel_id: pg_sys::Oid,
+    baserel_ids: pg_sys::Relids,
+    clauses: &[*mut pg_sys::Node],
+    allowlist: &[&str],
+) -> (Vec<Qual>, Vec<*mut pg_sys::Node>) {
+    let mut quals = Vec::new();
+    let mut exprs = Vec::new();
+    for expr in clauses {
+        let Some(mut qual) = extract_qual(root, baserel_id, baserel_ids, *expr, allowlist) else {
+            continue;
+        };
+        if let Some(param) = qual.param.as_mut().filter(|p| p.kind == ParamKind::Stable) {
+            exprs.push(get_stable_operand(*expr as _));
+            param.id = exprs.len();
         }
+        quals.push(qual);
+    }
+    (quals, exprs)
+}
 
-        quals
-    })
+// extract filters from restriction clauses, only the boolean expressions which
+// are not extracted as quals are extracted as filters
+pub(crate) unsafe fn extract_filters_from_clauses(
+    root: *mut pg_sys::PlannerInfo,
+    baserel_id: pg_sys::Oid,
+    baserel_ids: pg_sys::Relids,
+    clauses: &[*mut pg_sys::Node],
+    allowlist: &[&str],
+) -> Vec<Filter> {
+    clauses
+        .iter()
+        .copied()
+        .filter(|expr| {
+            is_a(*expr, pg_sys::NodeTag::T_BoolExpr)
+                && extract_from_bool_expr(root, baserel_id, baserel_ids, *expr as _).is_none()
+        })
+        .filter_map(|expr| extract_filter(root, baserel_id, baserel_ids, expr, allowlist))
+        .collect()
 }
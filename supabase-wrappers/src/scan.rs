@@ -1,20 +1,34 @@
 use pgrx::FromDatum;
 use pgrx::{
-    debug2, memcxt::PgMemoryContexts, pg_sys::Datum, pg_sys::Oid, prelude::*, IntoDatum,
+    debug2, memcxt::PgMemoryContexts, pg_sys::Datum, pg_sys::Oid, prelude::*, IntoDatum, PgList,
     PgSqlErrorCode,
 };
 use std::collections::HashMap;
+use std::ffi::CStr;
+#[cfg(not(feature = "pg13"))]
+use std::io::Read;
 use std::marker::PhantomData;
+#[cfg(not(feature = "pg13"))]
+use std::os::fd::AsRawFd;
+#[cfg(not(feature = "pg13"))]
+use std::os::unix::net::UnixStream;
 
 use pgrx::pg_sys::panic::ErrorReport;
 use std::os::raw::c_int;
 use std::ptr;
 
+use crate::aggregate::*;
 use crate::instance;
-use crate::interface::{Cell, Column, Limit, Qual, Row, Sort, Value};
+use crate::interface::{
+    Aggregate, Cell, Column, ExplainOptions, ExplainProperty, Filter, Join, JoinRelation, JoinType,
+    Limit, ParallelScan, ParamKind, Pushdown, Qual, Row, Sort, Value,
+};
+use crate::join::*;
 use crate::limit::*;
 use crate::memctx;
+use crate::on_error::Rejects;
 use crate::options::options_to_hashmap;
+use crate::param_path::*;
 use crate::polyfill;
 use crate::prelude::ForeignDataWrapper;
 use crate::qual::*;
@@ -29,6 +43,10 @@ struct FdwState<E: Into<ErrorReport>, W: ForeignDataWrapper<E>> {
     // query conditions
     quals: Vec<Qual>,
 
+    // boolean expression conditions, and if they are pushed down by the FDW
+    filters: Vec<Filter>,
+    is_filter_pushdown: bool,
+
     // query target column list
     tgts: Vec<Column>,
 
@@ -38,13 +56,60 @@ struct FdwState<E: Into<ErrorReport>, W: ForeignDataWrapper<E>> {
     // limit
     limit: Option<Limit>,
 
+    // quals, sorts and limit pushdown reported by the FDW
+    pushdown: Option<Pushdown>,
+
+    // aggregate pushdown group by column and aggregate list
+    group_by: Vec<Column>,
+    aggregates: Vec<Aggregate>,
+
+    // if aggregate is pushed down in this scan
+    is_agg_pushdown: bool,
+
+    // join pushdown join description
+    join: Option<Join>,
+
+    // if join is pushed down in this scan
+    is_join_pushdown: bool,
+
     // foreign table options
     opts: HashMap<String, String>,
 
+    // foreign server options
+    server_opts: HashMap<String, String>,
+
+    // stable expressions of quals, evaluated when the scan is begun
+    stable_exprs: Vec<*mut pg_sys::Node>,
+
+    // expressions of the stable quals and the parameters supplied by outer
+    // relation, in the same order as the plan's fdw_exprs
+    param_exprs: Vec<*mut pg_sys::ExprState>,
+
+    // receiver of the asynchronous scan notifier, and if the scan result is ready
+    #[cfg(not(feature = "pg13"))]
+    async_receiver: Option<UnixStream>,
+    #[cfg(not(feature = "pg13"))]
+    is_async_ready: bool,
+
+    // shared state of parallel scan, and the data made by the leader to be
+    // copied into it
+    parallel_scan: Option<ParallelScan>,
+    parallel_data: Vec<u8>,
+
+    // if the scan is begun on first iteration because it is parallel aware
+    // or parameterized, if there is nothing to scan in this parallel
+    // participant, and if the scan is begun and not ended yet
+    is_scan_deferred: bool,
+    is_scan_idle: bool,
+    is_scan_begun: bool,
+
     // temporary memory context per foreign table, created under Wrappers root
     // memory context
     tmp_ctx: PgMemoryContexts,
 
+    // rows rejected by source data conversion errors
+    rejects: Rejects,
+
     // query result list
     values: Vec<Datum>,
     nulls: Vec<bool>,
@@ -53,15 +118,36 @@ struct FdwState<E: Into<ErrorReport>, W: ForeignDataWrapper<E>> {
 }
 
 impl<E: Into<ErrorReport>, W: ForeignDataWrapper<E>> FdwState<E, W> {
-    unsafe fn new(foreigntableid: Oid, tmp_ctx: PgMemoryContexts) -> Self {
+    unsafe fn new(foreigntableid: Oid, user_id: Oid, tmp_ctx: PgMemoryContexts) -> Self {
         Self {
-            instance: instance::create_fdw_instance_from_table_id(foreigntableid),
+            instance: instance::create_fdw_instance_from_table_id(foreigntableid, user_id),
             quals: Vec::new(),
+            filters: Vec::new(),
+            is_filter_pushdown: false,
             tgts: Vec::new(),
             sorts: Vec::new(),
             limit: None,
+            pushdown: None,
+            group_by: Vec::new(),
+            aggregates: Vec::new(),
+            is_agg_pushdown: false,
+            join: None,
+            is_join_pushdown: false,
             opts: HashMap::new(),
+            server_opts: HashMap::new(),
+            stable_exprs: Vec::new(),
+            param_exprs: Vec::new(),
+            #[cfg(not(feature = "pg13"))]
+            async_receiver: None,
+            #[cfg(not(feature = "pg13"))]
+            is_async_ready: false,
+            parallel_scan: None,
+            parallel_data: Vec::new(),
+            is_scan_deferred: false,
+            is_scan_idle: false,
+            is_scan_begun: false,
             tmp_ctx,
+            rejects: Rejects::default(),
             values: Vec::new(),
             nulls: Vec::new(),
             row: Row::new(),
@@ -80,8 +166,92 @@ impl<E: Into<ErrorReport>, W: ForeignDataWrapper<E>> FdwState<E, W> {
         )
     }
 
+    #[inline]
+    fn get_filter_pushdown(&mut self) -> Result<bool, E> {
+        if self.filters.is_empty() {
+            return Ok(false);
+        }
+        self.instance.get_filter_pushdown(&self.filters, &self.opts)
+    }
+
+    // number of the extracted conditions which can be applied remotely
+    fn num_remote_conds(&self) -> usize {
+        let filters = if self.is_filter_pushdown {
+            self.filters.len()
+        } else {
+            0
+        };
+        self.quals.len() + filters
+    }
+
+    // if the scan is parameterized by the outer relation of a nested loop join
+    fn is_parameterized(&self) -> bool {
+        self.quals
+            .iter()
+            .any(|q| q.param.as_ref().is_some_and(|p| p.kind == ParamKind::Exec))
+    }
+
+    #[inline]
+    fn get_scan_pushdown(&mut self) -> Result<Option<Pushdown>, E> {
+        self.instance
+            .get_scan_pushdown(&self.quals, &self.sorts, &self.limit, &self.opts)
+    }
+
+    // check if the FDW can return rows in the order of the sorts
+    fn get_sort_pushdown(&mut self, sorts: &[Sort]) -> Result<bool, E> {
+        self.instance
+            .get_scan_pushdown(&self.quals, sorts, &None, &self.opts)
+            .map(|pushdown| pushdown.is_some_and(|p| p.sorts))
+    }
+
+    #[inline]
+    fn get_path_costs(&mut self, rows: f64) -> Result<(f64, f64), E> {
+        // table options take precedence over server options
+        let mut opts = self.server_opts.clone();
+        opts.extend(self.opts.clone());
+        self.instance
+            .get_path_costs(&self.quals, &self.sorts, &self.limit, rows, &opts)
+    }
+
+    #[inline]
+    fn get_lookup_columns(&mut self) -> Result<Vec<String>, E> {
+        self.instance.get_lookup_columns(&self.opts)
+    }
+
+    #[inline]
+    fn get_aggregate_pushdown(&mut self) -> Result<bool, E> {
+        self.instance.get_aggregate_pushdown(
+            &self.quals,
+            &self.group_by,
+            &self.aggregates,
+            &self.opts,
+        )
+    }
+
+    #[inline]
+    fn get_join_pushdown(&mut self, join: &Join) -> Result<bool, E> {
+        self.instance.get_join_pushdown(join)
+    }
+
     #[inline]
     fn begin_scan(&mut self) -> Result<(), E> {
+        self.is_scan_begun = true;
+
+        if self.is_join_pushdown {
+            if let Some(join) = &self.join {
+                return self.instance.begin_join_scan(join);
+            }
+        }
+
+        if self.is_agg_pushdown {
+            return self.instance.begin_aggregate_scan(
+                &self.quals,
+                &self.group_by,
+                &self.aggregates,
+                &self.opts,
+            );
+        }
+
         self.instance.begin_scan(
             &self.quals,
             &self.tgts,
@@ -91,24 +261,126 @@ impl<E: Into<ErrorReport>, W: ForeignDataWrapper<E>> FdwState<E, W> {
         )
     }
 
+    #[cfg(not(feature = "pg13"))]
+    #[inline]
+    fn is_async_capable(&mut self) -> Result<bool, E> {
+        self.instance.is_async_capable(&self.opts)
+    }
+
+    // begin scan asynchronously, the scan result is ready when the receiver
+    // becomes readable
+    #[cfg(not(feature = "pg13"))]
+    fn begin_async_scan(&mut self) -> Result<(), E> {
+        let (notifier, receiver) = crate::interface::AsyncNotifier::new()
+            .unwrap_or_else(|err| pgrx::error!("create async notifier failed: {}", err));
+        self.async_receiver = Some(receiver);
+        self.is_async_ready = false;
+        self.is_scan_begun = true;
+        self.instance.begin_async_scan(
+            &self.quals,
+            &self.tgts,
+            &self.sorts,
+            &self.limit,
+            &self.opts,
+            &notifier,
+        )
+    }
+
+    // check if the asynchronous scan result is ready, without blocking
+    #[cfg(not(feature = "pg13"))]
+    fn poll_async_ready(&mut self) -> bool {
+        if self.is_async_ready {
+            return true;
+        }
+        if let Some(receiver) = &self.async_receiver {
+            let mut buf = [0u8; 16];
+            // drain all the notifications, end of stream means all notifiers
+            // are dropped and no more notification will come
+            while let Ok(n) = (&*receiver).read(&mut buf) {
+                self.is_async_ready = true;
+                if n == 0 {
+                    break;
+                }
+            }
+        }
+        self.is_async_ready
+    }
+
+    #[inline]
+    fn get_parallel_workers(&mut self) -> Result<usize, E> {
+        self.instance.get_parallel_workers(&self.quals, &self.opts)
+    }
+
+    #[inline]
+    fn init_parallel_scan(&mut self) -> Result<Vec<u8>, E> {
+        self.instance
+            .init_parallel_scan(&self.quals, &self.tgts, &self.opts)
+    }
+
+    // begin the scan deferred to first iteration, it is a parallel scan if
+    // the shared state is set up, otherwise the whole table is scanned with
+    // the parameters supplied by outer relation if any
+    fn begin_deferred_scan(&mut self) -> Result<(), E> {
+        self.is_scan_deferred = false;
+        match &self.parallel_scan {
+            Some(scan) => {
+                let is_begun =
+                    self.instance
+                        .begin_parallel_scan(&self.quals, &self.tgts, &self.opts, scan)?;
+                self.is_scan_idle = !is_begun;
+                self.is_scan_begun = is_begun;
+                Ok(())
+            }
+            None => self.begin_scan(),
+        }
+    }
+
     #[inline]
     fn iter_scan(&mut self) -> Result<Option<()>, E> {
         self.instance.iter_scan(&mut self.row)
     }
 
+    // handle the source data conversion errors in the fetched row, return
+    // false if the row should be skipped
+    #[inline]
+    fn check_row(&mut self) -> bool {
+        self.rejects.check_row(&mut self.row)
+    }
+
     #[inline]
     fn re_scan(&mut self) -> Result<(), E> {
         self.instance.re_scan()
     }
 
-    #[inline]
+    // end the scan only if it is begun, a deferred scan may be never begun
+    // when there is no iteration, for example, the outer relation is empty
     fn end_scan(&mut self) -> Result<(), E> {
+        if !self.is_scan_begun {
+            return Ok(());
+        }
+        self.is_scan_begun = false;
         self.instance.end_scan()
     }
 }
 
 impl<E: Into<ErrorReport>, W: ForeignDataWrapper<E>> utils::SerdeList for FdwState<E, W> {}
 
+// extra cost of sorting remotely, same as the default in postgres_fdw
+const SORT_COST_MULTIPLIER: f64 = 1.2;
+
+// the number of participants sharing the rows of a parallel scan, same as
+// get_parallel_divisor() in Postgres
+fn get_parallel_divisor(workers: usize) -> f64 {
+    let mut divisor = workers as f64;
+    if unsafe { pg_sys::parallel_leader_participation } {
+        let leader_contribution = 1.0 - (0.3 * workers as f64);
+        if leader_contribution > 0.0 {
+            divisor += leader_contribution;
+        }
+    }
+    divisor
+}
+
 #[pg_guard]
 pub(super) extern "C-unwind" fn get_foreign_rel_size<
     E: Into<ErrorReport>,
@@ -125,10 +397,11 @@ pub(super) extern "C-unwind" fn get_foreign_rel_size<
         let ctx = memctx::refresh_wrappers_memctx(&ctx_name);
 
         // create scan state
-        let mut state = FdwState::<E, W>::new(foreigntableid, ctx);
+        let mut state = FdwState::<E, W>::new(foreigntableid, (*baserel).userid, ctx);
 
         // extract qual list
-        state.quals = extract_quals(root, baserel, foreigntableid);
+        (state.quals, state.stable_exprs) =
+            extract_quals(root, baserel, foreigntableid, W::field_expr_allowlist());
 
         // extract target column list from target and restriction expression
         state.tgts = utils::extract_target_columns(root, baserel);
@@ -142,11 +415,48 @@ pub(super) extern "C-unwind" fn get_foreign_rel_size<
         // get foreign table options
         let ftable = pg_sys::GetForeignTable(foreigntableid);
         state.opts = options_to_hashmap((*ftable).options).report_unwrap();
+        let fserver = pg_sys::GetForeignServer((*ftable).serverid);
+        state.server_opts = options_to_hashmap((*fserver).options).report_unwrap();
+
+        // extract boolean expression filters and check if they can be pushed down
+        state.filters = extract_filters(root, baserel, foreigntableid, W::field_expr_allowlist());
+        state.is_filter_pushdown = state.get_filter_pushdown().report_unwrap();
+
+        // get quals, sorts and limit can be pushed down
+        state.pushdown = state.get_scan_pushdown().report_unwrap();
+        if let Some(pushdown) = &state.pushdown {
+            // limit can be applied remotely only when this is the only relation
+            // in query and all the restrictions are applied remotely as well,
+            // otherwise rows could be filtered out locally after limiting
+            let conds = PgList::<pg_sys::RestrictInfo>::from_pg((*baserel).baserestrictinfo);
+            let all_pushed = conds.len() == state.num_remote_conds()
+                && (0..state.quals.len()).all(|i| pushdown.quals.contains(&i));
+            if !all_pushed || !pg_sys::bms_equal((*root).all_baserels, (*baserel).relids) {
+                state.limit = None;
+            }
+
+            // the sorts and limit not applied remotely are not passed to the
+            // FDW, and the limit cannot be applied before the rows are sorted
+            if !pushdown.limit || (!pushdown.sorts && !state.sorts.is_empty()) {
+                state.limit = None;
+            }
+            if !pushdown.sorts {
+                state.sorts.clear();
+            }
+        }
 
         // get estimate row count and mean row width
         let (rows, width) = state.get_rel_size().report_unwrap();
-        (*baserel).rows = rows as f64;
-        (*(*baserel).reltarget).width = width;
+        if rows == 0 && (*baserel).tuples > 0.0 {
+            // no estimate from the FDW, use local statistics collected by ANALYZE
+            pg_sys::set_baserel_size_estimates(root, baserel);
+            if width > 0 {
+                (*(*baserel).reltarget).width = width;
+            }
+        } else {
+            (*baserel).rows = rows as f64;
+            (*(*baserel).reltarget).width = width;
+        }
 
         // install callback to drop the state when memory context is reset
         let ctx = state.tmp_ctx.value();
@@ -161,24 +471,14 @@ pub(super) extern "C-unwind" fn get_foreign_paths<
 >(
     root: *mut pg_sys::PlannerInfo,
     baserel: *mut pg_sys::RelOptInfo,
-    _foreigntableid: pg_sys::Oid,
+    foreigntableid: pg_sys::Oid,
 ) {
     debug2!("---> get_foreign_paths");
     unsafe {
-        let state = PgBox::<FdwState<E, W>>::from_pg((*baserel).fdw_private as _);
-
-        // get startup cost from foreign table options
-        let startup_cost = state
-            .opts
-            .get("startup_cost")
-            .map(|c| match c.parse::<f64>() {
-                Ok(v) => v,
-                Err(_) => {
-                    pgrx::error!("invalid option startup_cost: {}", c);
-                }
-            })
-            .unwrap_or(0.0);
-        let total_cost = startup_cost + (*baserel).rows;
+        let mut state = PgBox::<FdwState<E, W>>::from_pg((*baserel).fdw_private as _);
+
+        // get path costs estimated by the FDW
+        let (startup_cost, total_cost) = state.get_path_costs((*baserel).rows).report_unwrap();
 
         // create a ForeignPath node and add it as the only possible path
         let path = pg_sys::create_foreignscan_path(
@@ -196,6 +496,371 @@ pub(super) extern "C-unwind" fn get_foreign_paths<
             ptr::null_mut(), // no fdw_private data
         );
         pg_sys::add_path(baserel, &mut ((*path).path));
+
+        // add a partial path if the foreign table can be scanned by parallel
+        // workers, the rows are split among the workers by the FDW
+        if (*baserel).consider_parallel && (*baserel).lateral_relids.is_null() {
+            let workers = state
+                .get_parallel_workers()
+                .report_unwrap()
+                .min(pg_sys::max_parallel_workers_per_gather.max(0) as usize);
+            if workers > 0 {
+                let rows = (*baserel).rows / get_parallel_divisor(workers);
+                let (startup_cost, total_cost) = state.get_path_costs(rows).report_unwrap();
+                let path = pg_sys::create_foreignscan_path(
+                    root,
+                    baserel,
+                    ptr::null_mut(), // default pathtarget
+                    rows,
+                    startup_cost,
+                    total_cost,
+                    ptr::null_mut(), // no pathkeys
+                    ptr::null_mut(), // no outer rel either
+                    ptr::null_mut(), // no extra plan
+                    #[cfg(feature = "pg17")]
+                    ptr::null_mut(), // no restrict info
+                    ptr::null_mut(), // no fdw_private data
+                );
+                (*path).path.parallel_aware = true;
+                (*path).path.parallel_workers = workers as c_int;
+                pg_sys::add_partial_path(baserel, &mut ((*path).path));
+            }
+        }
+
+        // add sorted paths if the FDW can return rows in the order of useful
+        // pathkeys, so no local sort is needed for the query's ORDER BY and the
+        // scan can be used as pre-sorted input of merge join
+        let mut useful_pathkeys = Vec::new();
+        let query_pathkeys = Some((*root).query_pathkeys).filter(|p| !p.is_null());
+        for pathkeys in query_pathkeys
+            .into_iter()
+            .chain(get_merge_join_pathkeys(root, baserel))
+        {
+            // all the pathkeys must be converted, otherwise the rows are not
+            // fully in the order of them
+            let Some(sorts) = pathkeys_to_sorts(pathkeys, baserel, foreigntableid) else {
+                continue;
+            };
+            if !sorts.is_empty() && state.get_sort_pushdown(&sorts).report_unwrap() {
+                useful_pathkeys.push(pathkeys);
+            }
+        }
+        for pathkeys in useful_pathkeys {
+            let path = pg_sys::create_foreignscan_path(
+                root,
+                baserel,
+                ptr::null_mut(), // default pathtarget
+                (*baserel).rows,
+                startup_cost * SORT_COST_MULTIPLIER,
+                total_cost * SORT_COST_MULTIPLIER,
+                pathkeys,
+                ptr::null_mut(), // no outer rel either
+                ptr::null_mut(), // no extra plan
+                #[cfg(feature = "pg17")]
+                ptr::null_mut(), // no restrict info
+                ptr::null_mut(), // no fdw_private data
+            );
+            // sorts cannot be rebuilt in parallel workers
+            (*path).path.parallel_safe = false;
+            pg_sys::add_path(baserel, &mut ((*path).path));
+        }
+
+        // add parameterized paths for the join clauses on lookup columns, so
+        // the foreign table can be the inner side of a nested loop join
+        let lookup_cols = state.get_lookup_columns().report_unwrap();
+        if lookup_cols.is_empty() {
+            return;
+        }
+        for rinfo in get_lookup_clauses(root, baserel, foreigntableid, &lookup_cols) {
+            // the outer relations needed by the join clause
+            let required_outer = pg_sys::bms_del_member(
+                pg_sys::bms_union((*rinfo).clause_relids, (*baserel).lateral_relids),
+                (*baserel).relid as c_int,
+            );
+            if pg_sys::bms_num_members(required_outer) == 0 {
+                continue;
+            }
+
+            let param_info = pg_sys::get_baserel_parampathinfo(root, baserel, required_outer);
+            let rows = (*param_info).ppi_rows;
+            let (startup_cost, total_cost) = state.get_path_costs(rows).report_unwrap();
+            let path = pg_sys::create_foreignscan_path(
+                root,
+                baserel,
+                ptr::null_mut(), // default pathtarget
+                rows,
+                startup_cost,
+                total_cost,
+                ptr::null_mut(), // no pathkeys
+                required_outer,
+                ptr::null_mut(), // no extra plan
+                #[cfg(feature = "pg17")]
+                ptr::null_mut(), // no restrict info
+                ptr::null_mut(), // no fdw_private data
+            );
+            // parameters supplied by outer relation cannot be rebuilt in
+            // parallel workers
+            (*path).path.parallel_safe = false;
+            pg_sys::add_path(baserel, &mut ((*path).path));
+        }
+    }
+}
+
+// make a join relation from a base relation scan state, the relation
+// restrictions are qualified by the relation alias
+fn make_join_relation<E: Into<ErrorReport>, W: ForeignDataWrapper<E>>(
+    state: &FdwState<E, W>,
+    alias: &str,
+) -> JoinRelation {
+    JoinRelation {
+        alias: alias.to_string(),
+        quals: state
+            .quals
+            .iter()
+            .map(|q| {
+                let mut field_expr = q.field_expr.clone();
+                if let Some(expr) = field_expr.as_mut() {
+                    expr.qualify_columns(alias);
+                }
+                Qual {
+                    field: format!("{}.{}", alias, q.field),
+                    field_expr,
+                    ..q.clone()
+                }
+            })
+            .collect(),
+        options: state.opts.clone(),
+    }
+}
+
+#[pg_guard]
+pub(super) extern "C-unwind" fn get_foreign_join_paths<
+    E: Into<ErrorReport>,
+    W: ForeignDataWrapper<E>,
+>(
+    root: *mut pg_sys::PlannerInfo,
+    joinrel: *mut pg_sys::RelOptInfo,
+    outerrel: *mut pg_sys::RelOptInfo,
+    innerrel: *mut pg_sys::RelOptInfo,
+    jointype: pg_sys::JoinType::Type,
+    extra: *mut pg_sys::JoinPathExtraData,
+) {
+    debug2!("---> get_foreign_join_paths");
+    unsafe {
+        // skip if this join relation has been considered already
+        if !(*joinrel).fdw_private.is_null() {
+            return;
+        }
+
+        // only join between two foreign tables can be pushed down
+        if !is_foreign_base_rel(outerrel) || !is_foreign_base_rel(innerrel) {
+            return;
+        }
+
+        // row locking and modifying a joined foreign table are not supported
+        if !(*root).rowMarks.is_null()
+            || is_result_rel(root, outerrel)
+            || is_result_rel(root, innerrel)
+        {
+            return;
+        }
+
+        let Some(join_type) = get_join_type(jointype) else {
+            return;
+        };
+
+        let mut outer_state = PgBox::<FdwState<E, W>>::from_pg((*outerrel).fdw_private as _);
+        let inner_state = PgBox::<FdwState<E, W>>::from_pg((*innerrel).fdw_private as _);
+
+        // all restrictions on both relations must be pushed down, because they
+        // cannot be applied locally after join
+        let outer_conds = PgList::<pg_sys::RestrictInfo>::from_pg((*outerrel).baserestrictinfo);
+        let inner_conds = PgList::<pg_sys::RestrictInfo>::from_pg((*innerrel).baserestrictinfo);
+        if outer_conds.len() != outer_state.quals.len()
+            || inner_conds.len() != inner_state.quals.len()
+        {
+            return;
+        }
+
+        // restrictions on both sides of a full join cannot be deparsed as
+        // either join conditions or filters
+        if join_type == JoinType::Full
+            && (!outer_state.quals.is_empty() || !inner_state.quals.is_empty())
+        {
+            return;
+        }
+
+        // extract join conditions, cross join is not pushed down
+        let Some(quals) = extract_join_quals(
+            root,
+            joinrel,
+            outerrel,
+            innerrel,
+            join_type,
+            (*extra).restrictlist,
+        ) else {
+            return;
+        };
+        if quals.is_empty() {
+            return;
+        }
+
+        // extract join target columns
+        let Some((columns, scan_tlist)) = extract_join_columns(root, joinrel, outerrel, innerrel)
+        else {
+            return;
+        };
+
+        // stable parameter ids of the inner relation follow the outer relation's
+        let mut inner = make_join_relation(&inner_state, "_wrappers_inner");
+        for param in inner.quals.iter_mut().filter_map(|q| q.param.as_mut()) {
+            if param.kind == ParamKind::Stable {
+                param.id += outer_state.stable_exprs.len();
+            }
+        }
+
+        let join = Join {
+            join_type,
+            outer: make_join_relation(&outer_state, "_wrappers_outer"),
+            inner,
+            quals,
+            columns,
+        };
+
+        // ask the FDW if it can push down the join
+        if !outer_state.get_join_pushdown(&join).report_unwrap() {
+            return;
+        }
+
+        // create a separate scan state for the join relation, because the outer
+        // relation can be joined with different relations, the state lives in
+        // the same memory context as the outer relation scan state
+        let outer_rte = pg_sys::planner_rt_fetch((*outerrel).relid, root);
+        let ctx = PgMemoryContexts::For(outer_state.tmp_ctx.value());
+        let mut state = FdwState::<E, W>::new((*outer_rte).relid, (*outerrel).userid, ctx);
+        state.opts = outer_state.opts.clone();
+        state.server_opts = outer_state.server_opts.clone();
+        state.join = Some(join);
+
+        // stable expressions of both relations are evaluated in the join scan
+        state.stable_exprs = outer_state
+            .stable_exprs
+            .iter()
+            .chain(inner_state.stable_exprs.iter())
+            .copied()
+            .collect();
+
+        let rows = (*joinrel).rows;
+        let (startup_cost, total_cost) = state.get_path_costs(rows).report_unwrap();
+
+        // create a ForeignPath node for the join relation, the foreign scan
+        // target list is carried in its private data
+        let path = pg_sys::create_foreign_join_path(
+            root,
+            joinrel,
+            ptr::null_mut(), // default pathtarget
+            rows,
+            startup_cost,
+            total_cost,
+            ptr::null_mut(), // no pathkeys
+            ptr::null_mut(), // no outer rel either
+            ptr::null_mut(), // no extra plan
+            #[cfg(feature = "pg17")]
+            ptr::null_mut(), // no restrict info
+            scan_tlist,
+        );
+        // join cannot be rebuilt in parallel workers
+        (*path).path.parallel_safe = false;
+        pg_sys::add_path(joinrel, &mut ((*path).path));
+
+        // install callback to drop the state when memory context is reset
+        let ctx = state.tmp_ctx.value();
+        (*joinrel).fdw_private = PgMemoryContexts::For(ctx).leak_and_drop_on_delete(state) as _;
+    }
+}
+
+#[pg_guard]
+pub(super) extern "C-unwind" fn get_foreign_upper_paths<
+    E: Into<ErrorReport>,
+    W: ForeignDataWrapper<E>,
+>(
+    root: *mut pg_sys::PlannerInfo,
+    stage: pg_sys::UpperRelationKind::Type,
+    input_rel: *mut pg_sys::RelOptInfo,
+    output_rel: *mut pg_sys::RelOptInfo,
+    extra: *mut std::ffi::c_void,
+) {
+    debug2!("---> get_foreign_upper_paths");
+    unsafe {
+        // only aggregate on a foreign table can be pushed down
+        if stage != pg_sys::UpperRelationKind::UPPERREL_GROUP_AGG
+            || (*input_rel).reloptkind != pg_sys::RelOptKind::RELOPT_BASEREL
+            || (*input_rel).fdw_private.is_null()
+        {
+            return;
+        }
+
+        // partial aggregate is not supported
+        if !extra.is_null()
+            && (*(extra as *mut pg_sys::GroupPathExtraData)).patype
+                == pg_sys::PartitionwiseAggregateType::PARTITIONWISE_AGGREGATE_PARTIAL
+        {
+            return;
+        }
+
+        let mut state = PgBox::<FdwState<E, W>>::from_pg((*input_rel).fdw_private as _);
+
+        // all restrictions must be pushed down, because they cannot be applied
+        // locally after aggregation
+        let conds = PgList::<pg_sys::RestrictInfo>::from_pg((*input_rel).baserestrictinfo);
+        if conds.len() != state.num_remote_conds() {
+            return;
+        }
+
+        // extract group by columns and aggregates
+        let rte = pg_sys::planner_rt_fetch((*input_rel).relid, root);
+        let Some((group_by, aggregates, scan_tlist)) =
+            extract_aggregates(root, input_rel, (*rte).relid, output_rel)
+        else {
+            return;
+        };
+        state.group_by = group_by;
+        state.aggregates = aggregates;
+
+        // ask the FDW if it can push down the aggregation
+        if !state.get_aggregate_pushdown().report_unwrap() {
+            return;
+        }
+
+        // estimate aggregated row count
+        let rows = if state.group_by.is_empty() {
+            1.0
+        } else {
+            ((*input_rel).rows * 0.1).max(1.0)
+        };
+        let (startup_cost, total_cost) = state.get_path_costs(rows).report_unwrap();
+
+        // create a ForeignPath node for the upper relation, the foreign scan
+        // target list is carried in its private data
+        let path = pg_sys::create_foreign_upper_path(
+            root,
+            output_rel,
+            (*output_rel).reltarget,
+            rows,
+            startup_cost,
+            total_cost,
+            ptr::null_mut(), // no pathkeys
+            ptr::null_mut(), // no extra plan
+            #[cfg(feature = "pg17")]
+            ptr::null_mut(), // no restrict info
+            scan_tlist,
+        );
+        // aggregates cannot be rebuilt in parallel workers
+        (*path).path.parallel_safe = false;
+        pg_sys::add_path(output_rel, &mut ((*path).path));
+
+        // share the scan state with upper relation
+        (*output_rel).fdw_private = (*input_rel).fdw_private;
     }
 }
 
@@ -203,15 +868,107 @@ pub(super) extern "C-unwind" fn get_foreign_paths<
 pub(super) extern "C-unwind" fn get_foreign_plan<E: Into<ErrorReport>, W: ForeignDataWrapper<E>>(
     _root: *mut pg_sys::PlannerInfo,
     baserel: *mut pg_sys::RelOptInfo,
-    _foreigntableid: pg_sys::Oid,
-    _best_path: *mut pg_sys::ForeignPath,
+    foreigntableid: pg_sys::Oid,
+    best_path: *mut pg_sys::ForeignPath,
     tlist: *mut pg_sys::List,
     scan_clauses: *mut pg_sys::List,
     outer_plan: *mut pg_sys::Plan,
 ) -> *mut pg_sys::ForeignScan {
     debug2!("---> get_foreign_plan");
     unsafe {
-        let state = PgBox::<FdwState<E, W>>::from_pg((*baserel).fdw_private as _);
+        let mut state = PgBox::<FdwState<E, W>>::from_pg((*baserel).fdw_private as _);
+
+        // the stable expressions of quals are evaluated when the scan is begun,
+        // they are put before the parameters supplied by outer relation
+        let mut fdw_exprs: *mut pg_sys::List = ptr::null_mut();
+        for expr in state.stable_exprs.iter() {
+            fdw_exprs = pg_sys::lappend(fdw_exprs, *expr as _);
+        }
+
+        // if this is an upper or join relation, aggregate or join is pushed down
+        // and the foreign scan doesn't scan any base relation, its output columns
+        // are described by the target list saved in path's private data
+        let (scan_relid, fdw_scan_tlist) =
+            if (*baserel).reloptkind == pg_sys::RelOptKind::RELOPT_UPPER_REL {
+                state.is_agg_pushdown = true;
+                state.tgts = state
+                    .group_by
+                    .iter()
+                    .map(|c| (c.name.clone(), c.type_oid))
+                    .chain(
+                        state
+                            .aggregates
+                            .iter()
+                            .map(|a| (a.alias.clone(), a.type_oid)),
+                    )
+                    .enumerate()
+                    .map(|(i, (name, type_oid))| Column {
+                        name,
+                        num: i + 1,
+                        type_oid,
+                    })
+                    .collect();
+                state.sorts.clear();
+                state.limit = None;
+                (0, (*best_path).fdw_private)
+            } else if (*baserel).reloptkind == pg_sys::RelOptKind::RELOPT_JOINREL {
+                // join is pushed down, the output columns are the join target
+                // columns named by their aliases
+                state.is_join_pushdown = true;
+                state.tgts = state
+                    .join
+                    .as_ref()
+                    .map(|j| j.output_columns())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, col)| Column { num: i + 1, ..col })
+                    .collect();
+                state.sorts.clear();
+                state.limit = None;
+                (0, (*best_path).fdw_private)
+            } else {
+                // parameterized path, the join clauses are pushed down as quals
+                // whose values are supplied by the outer relation
+                state
+                    .quals
+                    .retain(|q| !q.param.as_ref().is_some_and(|p| p.kind == ParamKind::Exec));
+                // sorted path, the sorts are taken from the path's pathkeys
+                let pathkeys = (*best_path).path.pathkeys;
+                if !pathkeys.is_null() {
+                    let sorts =
+                        pathkeys_to_sorts(pathkeys, baserel, foreigntableid).unwrap_or_default();
+                    let is_sorted = state.get_sort_pushdown(&sorts).report_unwrap();
+                    state.sorts = sorts;
+                    if let Some(pushdown) = state.pushdown.as_mut() {
+                        pushdown.sorts = is_sorted;
+                    }
+                } else if state.pushdown.is_some() && !state.sorts.is_empty() {
+                    // unsorted path, the rows are sorted locally so they cannot
+                    // be sorted or limited remotely
+                    state.sorts.clear();
+                    state.limit = None;
+                }
+
+                let param_info = (*best_path).path.param_info;
+                if !param_info.is_null() {
+                    let (quals, exprs) = extract_param_quals(
+                        baserel,
+                        foreigntableid,
+                        (*param_info).ppi_clauses,
+                        fdw_exprs,
+                    );
+                    state.quals.extend(quals);
+                    fdw_exprs = exprs;
+                }
+                // parallel scan, rows are split among the participants so
+                // they cannot be sorted or limited remotely
+                if (*best_path).path.parallel_aware {
+                    state.sorts.clear();
+                    state.limit = None;
+                }
+                ((*baserel).relid, ptr::null_mut())
+            };
 
         // make foreign scan plan
         let scan_clauses = pg_sys::extract_actual_clauses(scan_clauses, false);
@@ -229,10 +986,10 @@ pub(super) extern "C-unwind" fn get_foreign_plan<E: Into<ErrorReport>, W: Foreig
         pg_sys::make_foreignscan(
             tlist,
             scan_clauses,
-            (*baserel).relid,
-            ptr::null_mut(),
+            scan_relid,
+            fdw_exprs,
             fdw_private as _,
-            ptr::null_mut(),
+            fdw_scan_tlist,
             ptr::null_mut(),
             outer_plan,
         )
@@ -254,7 +1011,7 @@ pub(super) extern "C-unwind" fn explain_foreign_scan<
             return;
         }
 
-        let state = PgBox::<FdwState<E, W>>::from_pg(fdw_state);
+        let mut state = PgBox::<FdwState<E, W>>::from_pg(fdw_state);
 
         let ctx = PgMemoryContexts::CurrentMemoryContext;
 
@@ -271,35 +1028,221 @@ pub(super) extern "C-unwind" fn explain_foreign_scan<
 
         let value = ctx.pstrdup(&format!("limit = {:?}", state.limit));
         pg_sys::ExplainPropertyText(label, value, es);
+
+        if state.is_agg_pushdown {
+            let value = ctx.pstrdup(&format!("group_by = {:?}", state.group_by));
+            pg_sys::ExplainPropertyText(label, value, es);
+
+            let value = ctx.pstrdup(&format!("aggregates = {:?}", state.aggregates));
+            pg_sys::ExplainPropertyText(label, value, es);
+        }
+
+        if state.is_join_pushdown {
+            if let Some(join) = &state.join {
+                let value = ctx.pstrdup(&format!("join = {:?}", join));
+                pg_sys::ExplainPropertyText(label, value, es);
+            }
+        }
+
+        if !state.filters.is_empty() {
+            let value = ctx.pstrdup(&format!("filters = {:?}", state.filters));
+            pg_sys::ExplainPropertyText(label, value, es);
+        }
+
+        // properties provided by the FDW, aggregate and join scans are only
+        // begun when the query is executed
+        let explain = ExplainOptions {
+            verbose: (*es).verbose,
+            analyze: (*es).analyze,
+        };
+        let st = &mut *state;
+        let props = if (st.is_agg_pushdown || st.is_join_pushdown) && !explain.analyze {
+            Vec::new()
+        } else {
+            st.instance
+                .explain_scan(
+                    &st.quals, &st.tgts, &st.sorts, &st.limit, &st.opts, &explain,
+                )
+                .report_unwrap()
+        };
+        for prop in props {
+            match prop {
+                ExplainProperty::Text(label, value) => {
+                    let label = ctx.pstrdup(&label);
+                    let value = ctx.pstrdup(&value);
+                    pg_sys::ExplainPropertyText(label, value, es);
+                }
+                ExplainProperty::Integer(label, value) => {
+                    let label = ctx.pstrdup(&label);
+                    pg_sys::ExplainPropertyInteger(label, ptr::null(), value, es);
+                }
+            }
+        }
+
+        // show what is actually applied remotely
+        if state.is_agg_pushdown || state.is_join_pushdown {
+            return;
+        }
+        if let Some(pushdown) = &state.pushdown {
+            let mut filter = pushdown
+                .quals
+                .iter()
+                .filter_map(|i| state.quals.get(*i))
+                .map(|q| q.deparse())
+                .collect::<Vec<_>>();
+            if state.is_filter_pushdown {
+                filter.extend(state.filters.iter().map(|f| format!("({})", f.deparse())));
+            }
+            if !filter.is_empty() {
+                let label = ctx.pstrdup("Remote Filter");
+                let value = ctx.pstrdup(&filter.join(" and "));
+                pg_sys::ExplainPropertyText(label, value, es);
+            }
+
+            if pushdown.sorts && !state.sorts.is_empty() {
+                let sorts = state.sorts.iter().map(|s| s.deparse()).collect::<Vec<_>>();
+                let label = ctx.pstrdup("Remote Sort");
+                let value = ctx.pstrdup(&sorts.join(", "));
+                pg_sys::ExplainPropertyText(label, value, es);
+            }
+
+            if let Some(limit) = state.limit.as_ref().filter(|_| pushdown.limit) {
+                let label = ctx.pstrdup("Remote Limit");
+                let value = ctx.pstrdup(&limit.deparse());
+                pg_sys::ExplainPropertyText(label, value, es);
+            }
+        }
     }
 }
 
-// extract paramter value and assign it to qual in scan state
+// extract paramter value and assign it to qual in scan state, the parameters
+// supplied by outer relation are only assigned if `with_exec` is true, because
+// they are not set until the outer relation produces a row
 unsafe fn assign_paramenter_value<E: Into<ErrorReport>, W: ForeignDataWrapper<E>>(
     node: *mut pg_sys::ForeignScanState,
     state: &mut FdwState<E, W>,
+    with_exec: bool,
 ) {
     // get parameter list in execution state
     let estate = (*node).ss.ps.state;
     let plist_info = (*estate).es_param_list_info;
-    if plist_info.is_null() {
-        return;
-    }
-    let params_cnt = (*plist_info).numParams as usize;
-    let plist = (*plist_info).params.as_slice(params_cnt);
-    assert!(state.quals.iter().filter(|q| q.param.is_some()).count() <= params_cnt);
-
-    // assign parameter value to qual
-    for qual in &mut state.quals.iter_mut() {
-        if let Some(param) = &qual.param {
-            let p: pg_sys::ParamExternData = plist[param.id - 1];
-            if let Some(value) = Cell::from_polymorphic_datum(p.value, p.isnull, p.ptype) {
+    let plist: &[pg_sys::ParamExternData] = if plist_info.is_null() {
+        &[]
+    } else {
+        let params_cnt = (*plist_info).numParams as usize;
+        (*plist_info).params.as_slice(params_cnt)
+    };
+    let econtext = (*node).ss.ps.ps_ExprContext;
+
+    // assign parameter value to qual, including the relation quals of join
+    let join_quals = state
+        .join
+        .iter_mut()
+        .flat_map(|j| j.outer.quals.iter_mut().chain(j.inner.quals.iter_mut()));
+    for qual in state.quals.iter_mut().chain(join_quals) {
+        if let Some(param) = qual
+            .param
+            .as_ref()
+            .filter(|p| with_exec || p.kind != ParamKind::Exec)
+        {
+            let value = match param.kind {
+                ParamKind::External => plist
+                    .get(param.id - 1)
+                    .and_then(|p| Cell::from_polymorphic_datum(p.value, p.isnull, p.ptype)),
+                ParamKind::Exec | ParamKind::Stable => {
+                    // evaluate the expression, with current outer row if it is
+                    // supplied by outer relation
+                    let expr_state = state.param_exprs[param.id - 1];
+                    let mut is_null = true;
+                    PgMemoryContexts::For((*econtext).ecxt_per_tuple_memory).switch_to(|_| {
+                        let eval = (*expr_state).evalfunc.unwrap();
+                        let datum = eval(expr_state, econtext, &mut is_null);
+                        Cell::from_polymorphic_datum(datum, is_null, param.type_oid)
+                    })
+                }
+            };
+            if let Some(value) = value {
                 qual.value = Value::Cell(value);
             }
         }
     }
 }
 
+// get the user to check access as for the scan, the same as the one used in
+// planning, it is invalid if the scan runs as current user
+unsafe fn get_check_as_user(node: *mut pg_sys::ForeignScanState) -> Oid {
+    let plan = (*node).ss.ps.plan as *mut pg_sys::ForeignScan;
+
+    #[cfg(any(feature = "pg13", feature = "pg14", feature = "pg15"))]
+    {
+        let estate = (*node).ss.ps.state;
+        let rti = (*plan).scan.scanrelid as usize;
+        if rti == 0 {
+            return pg_sys::InvalidOid;
+        }
+        let rte = *(*estate).es_range_table_array.add(rti - 1);
+        (*rte).checkAsUser
+    }
+
+    #[cfg(not(any(feature = "pg13", feature = "pg14", feature = "pg15")))]
+    {
+        (*plan).checkAsUser
+    }
+}
+
+// make scan state in parallel worker, the state pointer in plan is only valid
+// in the leader process, so the state is rebuilt from the plan instead
+unsafe fn make_worker_state<E: Into<ErrorReport>, W: ForeignDataWrapper<E>>(
+    node: *mut pg_sys::ForeignScanState,
+) -> PgBox<FdwState<E, W>> {
+    let plan = (*node).ss.ps.plan as *mut pg_sys::ForeignScan;
+    let relid = (*(*node).ss.ss_currentRelation).rd_id;
+
+    // one memory context per plan node, as a table can be scanned more than
+    // once in a plan
+    let ctx_name = format!(
+        "Wrappers_scan_{}_{}",
+        relid.to_u32(),
+        (*plan).scan.plan.plan_node_id
+    );
+    let ctx = memctx::refresh_wrappers_memctx(&ctx_name);
+    let mut state = FdwState::<E, W>::new(relid, get_check_as_user(node), ctx);
+
+    // extract qual list and target column list from the plan
+    let clauses = PgList::<pg_sys::Node>::from_pg((*plan).scan.plan.qual)
+        .iter_ptr()
+        .collect::<Vec<_>>();
+    let relids = pg_sys::bms_make_singleton((*plan).scan.scanrelid as c_int);
+    (state.quals, state.stable_exprs) = extract_quals_from_clauses(
+        ptr::null_mut(),
+        relid,
+        relids,
+        &clauses,
+        W::field_expr_allowlist(),
+    );
+    state.tgts = utils::extract_scan_target_columns(plan, relid);
+
+    // get foreign table options
+    let ftable = pg_sys::GetForeignTable(relid);
+    state.opts = options_to_hashmap((*ftable).options).report_unwrap();
+    let fserver = pg_sys::GetForeignServer((*ftable).serverid);
+    state.server_opts = options_to_hashmap((*fserver).options).report_unwrap();
+
+    // extract boolean expression filters and pass them to the FDW
+    state.filters = extract_filters_from_clauses(
+        ptr::null_mut(),
+        relid,
+        relids,
+        &clauses,
+        W::field_expr_allowlist(),
+    );
+    state.is_filter_pushdown = state.get_filter_pushdown().report_unwrap();
+
+    // install callback to drop the state when memory context is reset
+    let ctx = state.tmp_ctx.value();
+    PgBox::from_pg(PgMemoryContexts::For(ctx).leak_and_drop_on_delete(state))
+}
+
 #[pg_guard]
 pub(super) extern "C-unwind" fn begin_foreign_scan<
     E: Into<ErrorReport>,
@@ -312,18 +1255,54 @@ pub(super) extern "C-unwind" fn begin_foreign_scan<
     unsafe {
         let scan_state = (*node).ss;
         let plan = scan_state.ps.plan as *mut pg_sys::ForeignScan;
-        let mut state = FdwState::<E, W>::deserialize_from_list((*plan).fdw_private as _);
+        let mut state = if pg_sys::ParallelWorkerNumber >= 0 {
+            make_worker_state::<E, W>(node)
+        } else {
+            FdwState::<E, W>::deserialize_from_list((*plan).fdw_private as _)
+        };
         assert!(!state.is_null());
 
-        // assign parameter values to qual
-        assign_paramenter_value(node, &mut state);
+        // initialize expressions of the parameters supplied by outer relation
+        let param_exprs = pg_sys::ExecInitExprList((*plan).fdw_exprs, node as _);
+        state.param_exprs = PgList::<pg_sys::ExprState>::from_pg(param_exprs)
+            .iter_ptr()
+            .collect();
+
+        // assign parameter values to qual, except the ones supplied by outer
+        // relation, which are assigned when the scan is actually begun
+        assign_paramenter_value(node, &mut state, false);
+
+        // there is no current relation if aggregate or join is pushed down
+        let rel = scan_state.ss_currentRelation;
+        let relname = if rel.is_null() {
+            None
+        } else {
+            let name = pg_sys::get_rel_name((*rel).rd_id);
+            (!name.is_null()).then(|| CStr::from_ptr(name).to_string_lossy().into_owned())
+        };
+        state.rejects = Rejects::new(&state.opts, relname).report_unwrap();
 
         // begin scan if it is not EXPLAIN statement
         if eflags & pg_sys::EXEC_FLAG_EXPLAIN_ONLY as c_int <= 0 {
-            state.begin_scan().report_unwrap();
+            // parameterized scan is begun on first iteration, after the
+            // parameters are set by the outer relation, and parallel scan is
+            // begun on first iteration as well, after its shared state is set up
+            if (*plan).scan.plan.parallel_aware || state.is_parameterized() {
+                state.is_scan_deferred = true;
+            } else {
+                #[cfg(not(feature = "pg13"))]
+                if (*node).ss.ps.async_capable {
+                    state.begin_async_scan().report_unwrap();
+                } else {
+                    state.begin_scan().report_unwrap();
+                }
+                #[cfg(feature = "pg13")]
+                state.begin_scan().report_unwrap();
+            }
 
-            let rel = scan_state.ss_currentRelation;
-            let tup_desc = (*rel).rd_att;
+            // use scan slot's tuple descriptor because there is no current
+            // relation if aggregate or join is pushed down
+            let tup_desc = (*scan_state.ss_ScanTupleSlot).tts_tupleDescriptor;
             let natts = (*tup_desc).natts as usize;
 
             // initialize scan result lists
@@ -353,8 +1332,25 @@ pub(super) extern "C-unwind" fn iterate_foreign_scan<
         let slot = (*node).ss.ss_ScanTupleSlot;
         polyfill::exec_clear_tuple(slot);
 
+        if state.is_scan_deferred {
+            if state.is_parameterized() {
+                assign_paramenter_value(node, &mut state, true);
+            }
+            state.begin_deferred_scan().report_unwrap();
+        }
+        if state.is_scan_idle {
+            return slot;
+        }
+
+        // rows rejected by conversion errors are skipped according to the
+        // `on_error` option
         state.row.clear();
-        if state.iter_scan().report_unwrap().is_some() {
+        while state.iter_scan().report_unwrap().is_some() {
+            if !state.check_row() {
+                state.row.clear();
+                continue;
+            }
+
             if state.row.cols.len() != state.tgts.len() {
                 report_error(
                     PgSqlErrorCode::ERRCODE_FDW_INVALID_COLUMN_NUMBER,
@@ -378,6 +1374,7 @@ pub(super) extern "C-unwind" fn iterate_foreign_scan<
             (*slot).tts_values = state.values.as_mut_ptr();
             (*slot).tts_isnull = state.nulls.as_mut_ptr();
             pg_sys::ExecStoreVirtualTuple(slot);
+            break;
         }
 
         slot
@@ -396,8 +1393,128 @@ pub(super) extern "C-unwind" fn re_scan_foreign_scan<
         let fdw_state = (*node).fdw_state as *mut FdwState<E, W>;
         if !fdw_state.is_null() {
             let mut state = PgBox::<FdwState<E, W>>::from_pg(fdw_state);
-            state.re_scan().report_unwrap();
+
+            // parallel scan is restarted on next iteration, after the shared
+            // state is reinitialized
+            if (*(*node).ss.ps.plan).parallel_aware {
+                state.end_scan().report_unwrap();
+                state.is_scan_deferred = true;
+                state.is_scan_idle = false;
+                return;
+            }
+
+            // restart the scan on next iteration if parameters supplied by
+            // outer relation changed, the scan which is not begun yet doesn't
+            // need to be rescanned
+            if state.is_parameterized() && !(*node).ss.ps.chgParam.is_null() {
+                assign_paramenter_value(node, &mut state, true);
+                state.end_scan().report_unwrap();
+                state.is_scan_deferred = true;
+            } else if state.is_scan_begun {
+                state.re_scan().report_unwrap();
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "pg13"))]
+#[pg_guard]
+pub(super) extern "C-unwind" fn is_foreign_path_async_capable<
+    E: Into<ErrorReport>,
+    W: ForeignDataWrapper<E>,
+>(
+    path: *mut pg_sys::ForeignPath,
+) -> bool {
+    debug2!("---> is_foreign_path_async_capable");
+    unsafe {
+        // only plain scan on a base relation or a partition can be asynchronous,
+        // parameterized scan is begun by outer relation so it cannot be either
+        let rel = (*path).path.parent;
+        if ((*rel).reloptkind != pg_sys::RelOptKind::RELOPT_BASEREL
+            && (*rel).reloptkind != pg_sys::RelOptKind::RELOPT_OTHER_MEMBER_REL)
+            || !(*path).path.param_info.is_null()
+            || (*rel).fdw_private.is_null()
+        {
+            return false;
         }
+
+        let mut state = PgBox::<FdwState<E, W>>::from_pg((*rel).fdw_private as _);
+        state.is_async_capable().report_unwrap()
+    }
+}
+
+// produce a row for the asynchronous request if the scan result is ready,
+// otherwise mark the request as pending and wait for notification
+#[cfg(not(feature = "pg13"))]
+unsafe fn produce_async_row<E: Into<ErrorReport>, W: ForeignDataWrapper<E>>(
+    areq: *mut pg_sys::AsyncRequest,
+) {
+    let node = (*areq).requestee as *mut pg_sys::ForeignScanState;
+    let is_ready = {
+        let mut state = PgBox::<FdwState<E, W>>::from_pg((*node).fdw_state as _);
+        state.poll_async_ready()
+    };
+    if is_ready {
+        let slot = iterate_foreign_scan::<E, W>(node);
+        pg_sys::ExecAsyncRequestDone(areq, slot);
+    } else {
+        pg_sys::ExecAsyncRequestPending(areq);
+    }
+}
+
+#[cfg(not(feature = "pg13"))]
+#[pg_guard]
+pub(super) extern "C-unwind" fn foreign_async_request<
+    E: Into<ErrorReport>,
+    W: ForeignDataWrapper<E>,
+>(
+    areq: *mut pg_sys::AsyncRequest,
+) {
+    debug2!("---> foreign_async_request");
+    unsafe {
+        produce_async_row::<E, W>(areq);
+    }
+}
+
+#[cfg(not(feature = "pg13"))]
+#[pg_guard]
+pub(super) extern "C-unwind" fn foreign_async_configure_wait<
+    E: Into<ErrorReport>,
+    W: ForeignDataWrapper<E>,
+>(
+    areq: *mut pg_sys::AsyncRequest,
+) {
+    debug2!("---> foreign_async_configure_wait");
+    unsafe {
+        let node = (*areq).requestee as *mut pg_sys::ForeignScanState;
+        let state = PgBox::<FdwState<E, W>>::from_pg((*node).fdw_state as _);
+
+        // wait for the notifier receiver to be readable, only Append node can
+        // make asynchronous requests
+        if let Some(receiver) = &state.async_receiver {
+            let append = (*areq).requestor as *mut pg_sys::AppendState;
+            pg_sys::AddWaitEventToSet(
+                (*append).as_eventset,
+                pg_sys::WL_SOCKET_READABLE,
+                receiver.as_raw_fd(),
+                ptr::null_mut(),
+                areq as _,
+            );
+        }
+    }
+}
+
+#[cfg(not(feature = "pg13"))]
+#[pg_guard]
+pub(super) extern "C-unwind" fn foreign_async_notify<
+    E: Into<ErrorReport>,
+    W: ForeignDataWrapper<E>,
+>(
+    areq: *mut pg_sys::AsyncRequest,
+) {
+    debug2!("---> foreign_async_notify");
+    unsafe {
+        produce_async_row::<E, W>(areq);
     }
 }
 
@@ -413,6 +1530,115 @@ pub(super) extern "C-unwind" fn end_foreign_scan<E: Into<ErrorReport>, W: Foreig
         }
 
         let mut state = PgBox::<FdwState<E, W>>::from_pg(fdw_state);
+        state.rejects.report();
         state.end_scan().report_unwrap();
     }
 }
+
+#[pg_guard]
+pub(super) extern "C-unwind" fn is_foreign_scan_parallel_safe<
+    E: Into<ErrorReport>,
+    W: ForeignDataWrapper<E>,
+>(
+    _root: *mut pg_sys::PlannerInfo,
+    _rel: *mut pg_sys::RelOptInfo,
+    rte: *mut pg_sys::RangeTblEntry,
+) -> bool {
+    debug2!("---> is_foreign_scan_parallel_safe");
+    unsafe {
+        let ftable = pg_sys::GetForeignTable((*rte).relid);
+        let opts = options_to_hashmap((*ftable).options).report_unwrap();
+
+        // rejected rows cannot be logged to table in parallel mode
+        W::is_parallel_safe(&opts) && !opts.contains_key("reject_table")
+    }
+}
+
+#[pg_guard]
+pub(super) extern "C-unwind" fn estimate_dsm_foreign_scan<
+    E: Into<ErrorReport>,
+    W: ForeignDataWrapper<E>,
+>(
+    node: *mut pg_sys::ForeignScanState,
+    _pcxt: *mut pg_sys::ParallelContext,
+) -> pg_sys::Size {
+    debug2!("---> estimate_dsm_foreign_scan");
+    unsafe {
+        let mut state = PgBox::<FdwState<E, W>>::from_pg((*node).fdw_state as _);
+
+        // the shared data is made here, so its size is known
+        state.parallel_data = state.init_parallel_scan().report_unwrap();
+        ParallelScan::estimate_size(state.parallel_data.len())
+    }
+}
+
+#[pg_guard]
+pub(super) extern "C-unwind" fn initialize_dsm_foreign_scan<
+    E: Into<ErrorReport>,
+    W: ForeignDataWrapper<E>,
+>(
+    node: *mut pg_sys::ForeignScanState,
+    _pcxt: *mut pg_sys::ParallelContext,
+    coordinate: *mut std::ffi::c_void,
+) {
+    debug2!("---> initialize_dsm_foreign_scan");
+    unsafe {
+        let mut state = PgBox::<FdwState<E, W>>::from_pg((*node).fdw_state as _);
+        let data = std::mem::take(&mut state.parallel_data);
+        state.parallel_scan = Some(ParallelScan::initialize(coordinate, &data));
+    }
+}
+
+#[pg_guard]
+pub(super) extern "C-unwind" fn reinitialize_dsm_foreign_scan<
+    E: Into<ErrorReport>,
+    W: ForeignDataWrapper<E>,
+>(
+    node: *mut pg_sys::ForeignScanState,
+    _pcxt: *mut pg_sys::ParallelContext,
+    _coordinate: *mut std::ffi::c_void,
+) {
+    debug2!("---> reinitialize_dsm_foreign_scan");
+    unsafe {
+        let state = PgBox::<FdwState<E, W>>::from_pg((*node).fdw_state as _);
+        if let Some(scan) = &state.parallel_scan {
+            scan.reset();
+        }
+    }
+}
+
+#[pg_guard]
+pub(super) extern "C-unwind" fn initialize_worker_foreign_scan<
+    E: Into<ErrorReport>,
+    W: ForeignDataWrapper<E>,
+>(
+    node: *mut pg_sys::ForeignScanState,
+    _toc: *mut pg_sys::shm_toc,
+    coordinate: *mut std::ffi::c_void,
+) {
+    debug2!("---> initialize_worker_foreign_scan");
+    unsafe {
+        let mut state = PgBox::<FdwState<E, W>>::from_pg((*node).fdw_state as _);
+        state.parallel_scan = Some(ParallelScan::attach(coordinate));
+    }
+}
+
+#[pg_guard]
+pub(super) extern "C-unwind" fn shutdown_foreign_scan<
+    E: Into<ErrorReport>,
+    W: ForeignDataWrapper<E>,
+>(
+    node: *mut pg_sys::ForeignScanState,
+) {
+    debug2!("---> shutdown_foreign_scan");
+    unsafe {
+        let fdw_state = (*node).fdw_state as *mut FdwState<E, W>;
+        if fdw_state.is_null() {
+            return;
+        }
+
+        // the shared memory is going to be detached
+        let mut state = PgBox::<FdwState<E, W>>::from_pg(fdw_state);
+        state.parallel_scan = None;
+    }
+}
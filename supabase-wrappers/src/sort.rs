@@ -1,8 +1,7 @@
 use crate::interface::Sort;
-use pgrx::list::List;
-use pgrx::{is_a, pg_sys};
-use std::ffi::c_void;
+use pgrx::{is_a, pg_sys, PgList};
 use std::ffi::CStr;
+use std::ptr;
 
 pub(crate) unsafe fn create_sort(
     pathkey: *mut pg_sys::PathKey,
@@ -12,11 +11,19 @@ pub(crate) unsafe fn create_sort(
     let attno = (*var).varattno;
     let attname = pg_sys::get_attname(baserel_id, attno, true);
     if !attname.is_null() {
+        let coll_id = (*(*pathkey).pk_eclass).ec_collation;
+        let collation = if coll_id != pg_sys::InvalidOid {
+            let name = pg_sys::get_collation_name(coll_id);
+            (!name.is_null()).then(|| CStr::from_ptr(name).to_str().unwrap().to_owned())
+        } else {
+            None
+        };
         let sort = Sort {
             field: CStr::from_ptr(attname).to_str().unwrap().to_owned(),
             field_no: attno as usize,
             reversed: (*pathkey).pk_strategy as u32 == pg_sys::BTGreaterStrategyNumber,
             nulls_first: (*pathkey).pk_nulls_first,
+            collation,
             ..Default::default()
         };
         return Some(sort);
@@ -24,69 +31,100 @@ pub(crate) unsafe fn create_sort(
     None
 }
 
-// extract sorts
+// extract sorts, they are only extracted if all of the query pathkeys can be
+// converted, otherwise the rows sorted by part of them are not in the query order
 pub(crate) unsafe fn extract_sorts(
     root: *mut pg_sys::PlannerInfo,
     baserel: *mut pg_sys::RelOptInfo,
     baserel_id: pg_sys::Oid,
 ) -> Vec<Sort> {
-    pgrx::memcx::current_context(|mcx| {
-        let mut ret = Vec::new();
+    pathkeys_to_sorts((*root).query_pathkeys, baserel, baserel_id).unwrap_or_default()
+}
 
-        if let Some(pathkeys) =
-            List::<*mut c_void>::downcast_ptr_in_memcx((*root).query_pathkeys, mcx)
-        {
-            for pathkey in pathkeys.iter() {
-                let ec = (*(*pathkey as *mut pg_sys::PathKey)).pk_eclass;
+// convert a pathkey to sort, none if it is not on the relation's plain column
+unsafe fn pathkey_to_sort(
+    pathkey: *mut pg_sys::PathKey,
+    baserel: *mut pg_sys::RelOptInfo,
+    baserel_id: pg_sys::Oid,
+) -> Option<Sort> {
+    let ec = (*pathkey).pk_eclass;
+    if (*ec).ec_has_volatile {
+        return None;
+    }
 
-                if (*ec).ec_has_volatile {
-                    continue;
-                }
+    let em = PgList::<pg_sys::EquivalenceMember>::from_pg((*ec).ec_members)
+        .iter_ptr()
+        .find(|em| pg_sys::bms_equal((**em).em_relids, (*baserel).relids))?;
+    let expr = (*em).em_expr as *mut pg_sys::Node;
 
-                if let Some(ems) = List::<*mut c_void>::downcast_ptr_in_memcx((*ec).ec_members, mcx)
-                {
-                    ems.iter()
-                        .find(|em| {
-                            pg_sys::bms_equal(
-                                (*(**em as *mut pg_sys::EquivalenceMember)).em_relids,
-                                (*baserel).relids,
-                            )
-                        })
-                        .and_then(|em| {
-                            let expr = (*(*em as *mut pg_sys::EquivalenceMember)).em_expr
-                                as *mut pg_sys::Node;
+    if is_a(expr, pg_sys::NodeTag::T_Var) {
+        create_sort(pathkey, expr as *mut pg_sys::Var, baserel_id)
+    } else if is_a(expr, pg_sys::NodeTag::T_RelabelType) {
+        // ORDER BY clauses having a COLLATE option will be RelabelType
+        let expr = expr as *mut pg_sys::RelabelType;
+        let var = (*expr).arg as *mut pg_sys::Var;
+        if !is_a(var as *mut pg_sys::Node, pg_sys::NodeTag::T_Var) {
+            return None;
+        }
+        let mut sort = create_sort(pathkey, var, baserel_id)?;
+        let coll_id = (*expr).resultcollid;
+        sort.collate = Some(
+            CStr::from_ptr(pg_sys::get_collation_name(coll_id))
+                .to_str()
+                .unwrap()
+                .to_owned(),
+        );
+        Some(sort)
+    } else {
+        None
+    }
+}
 
-                            if is_a(expr, pg_sys::NodeTag::T_Var) {
-                                let var = expr as *mut pg_sys::Var;
-                                let sort = create_sort(*pathkey as _, var, baserel_id);
-                                if let Some(sort) = sort {
-                                    ret.push(sort);
-                                }
-                            } else if is_a(expr, pg_sys::NodeTag::T_RelabelType) {
-                                // ORDER BY clauses having a COLLATE option will be RelabelType
-                                let expr = expr as *mut pg_sys::RelabelType;
-                                let var = (*expr).arg as *mut pg_sys::Var;
-                                if is_a(var as *mut pg_sys::Node, pg_sys::NodeTag::T_Var) {
-                                    let sort = create_sort(*pathkey as _, var, baserel_id);
-                                    if let Some(mut sort) = sort {
-                                        let coll_id = (*expr).resultcollid;
-                                        sort.collate = Some(
-                                            CStr::from_ptr(pg_sys::get_collation_name(coll_id))
-                                                .to_str()
-                                                .unwrap()
-                                                .to_owned(),
-                                        );
-                                        ret.push(sort);
-                                    }
-                                }
-                            }
+// convert pathkeys to sorts, none if any of them is not on the relation's
+// plain columns
+pub(crate) unsafe fn pathkeys_to_sorts(
+    pathkeys: *mut pg_sys::List,
+    baserel: *mut pg_sys::RelOptInfo,
+    baserel_id: pg_sys::Oid,
+) -> Option<Vec<Sort>> {
+    PgList::<pg_sys::PathKey>::from_pg(pathkeys)
+        .iter_ptr()
+        .map(|pathkey| pathkey_to_sort(pathkey, baserel, baserel_id))
+        .collect()
+}
+
+// get pathkeys which are useful for merge joins, each of them is a single pathkey
+// on a column of the relation that is in a mergeable join clause
+pub(crate) unsafe fn get_merge_join_pathkeys(
+    root: *mut pg_sys::PlannerInfo,
+    baserel: *mut pg_sys::RelOptInfo,
+) -> Vec<*mut pg_sys::List> {
+    let mut ret = Vec::new();
 
-                            None::<()>
-                        });
-                }
-            }
+    if !(*baserel).has_eclass_joins {
+        return ret;
+    }
+
+    let ecs = PgList::<pg_sys::EquivalenceClass>::from_pg((*root).eq_classes);
+    for ec in ecs.iter_ptr() {
+        if (*ec).ec_has_volatile || !pg_sys::eclass_useful_for_merging(root, ec, baserel) {
+            continue;
         }
 
-        ret
-    })
+        // use the first operator family and ascending order, same as what
+        // postgres_fdw does
+        let Some(opfamily) = PgList::<pg_sys::Oid>::from_pg((*ec).ec_opfamilies).get_oid(0) else {
+            continue;
+        };
+        let pathkey = pg_sys::make_canonical_pathkey(
+            root,
+            ec,
+            opfamily,
+            pg_sys::BTLessStrategyNumber as _,
+            false,
+        );
+        ret.push(pg_sys::lappend(ptr::null_mut(), pathkey as _));
+    }
+
+    ret
 }
@@ -9,8 +9,13 @@ use pgrx::IntoDatum;
 use pgrx::*;
 use std::ffi::c_void;
 use std::ffi::CStr;
+use std::future::{poll_fn, Future};
 use std::num::NonZeroUsize;
+use std::os::raw::c_int;
+use std::pin::pin;
 use std::ptr;
+use std::task::Poll;
+use std::time::Duration;
 use thiserror::Error;
 use tokio::runtime::{Builder, Runtime};
 use uuid::Uuid;
@@ -155,6 +160,60 @@ pub fn create_async_runtime() -> Result<Runtime, CreateRuntimeError> {
     Ok(Builder::new_current_thread().enable_all().build()?)
 }
 
+// interval of checking for interrupts when blocking on a future
+const INTERRUPT_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Run a future to completion on the runtime, while checking for interrupts
+///
+/// This is same as `Runtime::block_on()`, except it periodically checks for
+/// query cancellation, such as `Ctrl-C` or `statement_timeout`. If the query
+/// is canceled, the future is dropped and an error is raised.
+///
+/// It must be called in the backend's main thread, because Postgres functions
+/// are called in it.
+///
+/// For example,
+///
+/// ```rust,no_run
+/// # use supabase_wrappers::prelude::*;
+/// # fn main() -> Result<(), CreateRuntimeError> {
+/// # struct Client {}
+/// # impl Client {
+/// #     async fn query(&self, _sql: &str) -> Result<(), ()> { Ok(()) }
+/// # }
+/// # let client = Client {};
+/// # let sql = "";
+/// let rt = create_async_runtime()?;
+///
+/// // the query can be canceled while waiting for the result
+/// match block_on_interruptible(&rt, client.query(&sql)) {
+///     Ok(result) => { }
+///     Err(err) => { }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn block_on_interruptible<F: Future>(rt: &Runtime, future: F) -> F::Output {
+    rt.block_on(async {
+        let mut future = pin!(future);
+        let mut interval = tokio::time::interval(INTERRUPT_CHECK_INTERVAL);
+        poll_fn(|cx| {
+            if let Poll::Ready(output) = future.as_mut().poll(cx) {
+                return Poll::Ready(output);
+            }
+
+            // error raised here will unwind out of the runtime and drop the
+            // future, so the remote call is canceled
+            while interval.poll_tick(cx).is_ready() {
+                check_for_interrupts!();
+            }
+
+            Poll::Pending
+        })
+        .await
+    })
+}
+
 /// Get decrypted secret from Vault by secret ID
 ///
 /// Get decrypted secret as string from Vault by secret ID. Vault is an extension for storing
@@ -293,6 +352,57 @@ pub(super) unsafe fn extract_target_columns(
     ret
 }
 
+// extract target column name and attribute no list from a foreign scan plan,
+// this is used in parallel workers where the planner info is not available
+pub(super) unsafe fn extract_scan_target_columns(
+    plan: *mut pg_sys::ForeignScan,
+    relid: pg_sys::Oid,
+) -> Vec<Column> {
+    let mut ret = Vec::new();
+    let scanrelid = (*plan).scan.scanrelid as c_int;
+
+    memcx::current_context(|mcx| {
+        // gather vars from target list and quals
+        let col_vars = [(*plan).scan.plan.targetlist, (*plan).scan.plan.qual]
+            .into_iter()
+            .fold(ptr::null_mut(), |col_vars, exprs| {
+                let tgt_cols = pg_sys::pull_var_clause(
+                    exprs as _,
+                    (pg_sys::PVC_RECURSE_AGGREGATES | pg_sys::PVC_RECURSE_PLACEHOLDERS)
+                        .try_into()
+                        .unwrap(),
+                );
+                pg_sys::list_union(col_vars, tgt_cols)
+            });
+
+        // get column names from var list
+        if let Some(col_vars) = List::<*mut c_void>::downcast_ptr_in_memcx(col_vars, mcx) {
+            for var in col_vars.iter() {
+                let var: pg_sys::Var = *(*var as *mut pg_sys::Var);
+                if var.varno as c_int != scanrelid {
+                    continue;
+                }
+                let attno = var.varattno;
+                let attname = pg_sys::get_attname(relid, attno, true);
+                if !attname.is_null() {
+                    if pg_sys::get_attgenerated(relid, attno) > 0 {
+                        continue;
+                    }
+
+                    let type_oid = pg_sys::get_atttype(relid, attno);
+                    ret.push(Column {
+                        name: CStr::from_ptr(attname).to_str().unwrap().to_owned(),
+                        num: attno as usize,
+                        type_oid,
+                    });
+                }
+            }
+        }
+    });
+
+    ret
+}
+
 // trait for "serialize" and "deserialize" state from specified memory context,
 // so that it is safe to be carried between the planning and the execution
 pub(super) trait SerdeList {
@@ -0,0 +1,186 @@
+use pgrx::pg_sys::panic::ErrorReport;
+use pgrx::{debug2, pg_guard, pg_sys, PgSqlErrorCode};
+use std::cell::{Cell, RefCell};
+use std::ffi::c_void;
+use std::ptr;
+use std::rc::Rc;
+
+use crate::prelude::*;
+
+// transaction hooks to be called on FDW instance
+#[derive(Debug, Clone, Copy)]
+enum XactHook {
+    Commit,
+    Abort,
+    SubCommit(pg_sys::SubTransactionId),
+    SubAbort(pg_sys::SubTransactionId),
+}
+
+// a FDW instance which has modified foreign tables in current transaction and
+// has transaction hooks, the instance type is erased so instances of different FDWs can be kept in
+// one list
+#[derive(Clone, Copy)]
+struct Participant {
+    instance: *const c_void,
+    call_hook: unsafe fn(*const c_void, XactHook) -> Result<(), ErrorReport>,
+    release: unsafe fn(*const c_void),
+}
+
+thread_local! {
+    static PARTICIPANTS: RefCell<Vec<Participant>> = const { RefCell::new(Vec::new()) };
+
+    static IS_CALLBACK_REGISTERED: Cell<bool> = const { Cell::new(false) };
+}
+
+unsafe fn call_hook<E: Into<ErrorReport>, W: ForeignDataWrapper<E>>(
+    instance: *const c_void,
+    hook: XactHook,
+) -> Result<(), ErrorReport> {
+    let instance = &*(instance as *const RefCell<W>);
+    let mut instance = instance.borrow_mut();
+    match hook {
+        XactHook::Commit => instance.on_xact_commit(),
+        XactHook::Abort => instance.on_xact_abort(),
+        XactHook::SubCommit(subid) => instance.on_subxact_commit(subid),
+        XactHook::SubAbort(subid) => instance.on_subxact_abort(subid),
+    }
+    .map_err(|err| err.into())
+}
+
+unsafe fn release<W>(instance: *const c_void) {
+    drop(Rc::from_raw(instance as *const RefCell<W>));
+}
+
+// register a FDW instance to take part in current transaction, it is kept
+// alive until the transaction ends
+pub(crate) fn register_participant<E: Into<ErrorReport>, W: ForeignDataWrapper<E>>(
+    instance: &Rc<RefCell<W>>,
+) {
+    unsafe {
+        register_callbacks();
+    }
+
+    PARTICIPANTS.with(|participants| {
+        let mut participants = participants.borrow_mut();
+        let ptr = Rc::as_ptr(instance) as *const c_void;
+        if participants.iter().any(|p| p.instance == ptr) {
+            return;
+        }
+        participants.push(Participant {
+            instance: Rc::into_raw(instance.clone()) as _,
+            call_hook: call_hook::<E, W>,
+            release: release::<W>,
+        });
+    });
+}
+
+unsafe fn register_callbacks() {
+    if IS_CALLBACK_REGISTERED.get() {
+        return;
+    }
+    pg_sys::RegisterXactCallback(Some(xact_callback), ptr::null_mut());
+    pg_sys::RegisterSubXactCallback(Some(subxact_callback), ptr::null_mut());
+    IS_CALLBACK_REGISTERED.set(true);
+}
+
+// call hook on all participants, error is raised if `is_error` is true,
+// otherwise it is reported as a warning because error cannot be raised when
+// aborting transaction
+unsafe fn call_participants(hook: XactHook, is_error: bool) {
+    // copy the list out, so the hooks can run without the list borrowed
+    let participants = PARTICIPANTS.with(|participants| participants.borrow().clone());
+    for participant in participants {
+        let result = (participant.call_hook)(participant.instance, hook);
+        if let Err(err) = result {
+            if is_error {
+                Err::<(), _>(err).report_unwrap();
+            } else {
+                report_warning(&format!(
+                    "abort foreign transaction failed: {}",
+                    err.message()
+                ));
+            }
+        }
+    }
+}
+
+// release all participants, the transaction is ended
+unsafe fn release_participants() {
+    let participants = PARTICIPANTS.with(|participants| participants.take());
+    for participant in participants {
+        (participant.release)(participant.instance);
+    }
+}
+
+// commit participants one by one in the order they are registered, the
+// committed participant is released right away, so if a later commit fails
+// only the uncommitted participants are aborted
+unsafe fn commit_participants() {
+    while let Some(participant) =
+        PARTICIPANTS.with(|participants| participants.borrow().first().copied())
+    {
+        (participant.call_hook)(participant.instance, XactHook::Commit).report_unwrap();
+        PARTICIPANTS.with(|participants| participants.borrow_mut().remove(0));
+        (participant.release)(participant.instance);
+    }
+}
+
+#[pg_guard]
+extern "C-unwind" fn xact_callback(event: pg_sys::XactEvent::Type, _arg: *mut c_void) {
+    let has_participants = PARTICIPANTS.with(|participants| !participants.borrow().is_empty());
+    if !has_participants {
+        return;
+    }
+
+    debug2!("---> xact_callback");
+    unsafe {
+        match event {
+            pg_sys::XactEvent::XACT_EVENT_PRE_COMMIT
+            | pg_sys::XactEvent::XACT_EVENT_PARALLEL_PRE_COMMIT => {
+                commit_participants();
+            }
+            pg_sys::XactEvent::XACT_EVENT_PRE_PREPARE => {
+                report_error(
+                    PgSqlErrorCode::ERRCODE_FEATURE_NOT_SUPPORTED,
+                    "cannot PREPARE a transaction that has modified foreign tables",
+                );
+            }
+            pg_sys::XactEvent::XACT_EVENT_ABORT | pg_sys::XactEvent::XACT_EVENT_PARALLEL_ABORT => {
+                call_participants(XactHook::Abort, false);
+                release_participants();
+            }
+            pg_sys::XactEvent::XACT_EVENT_COMMIT
+            | pg_sys::XactEvent::XACT_EVENT_PARALLEL_COMMIT
+            | pg_sys::XactEvent::XACT_EVENT_PREPARE => {
+                release_participants();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[pg_guard]
+extern "C-unwind" fn subxact_callback(
+    event: pg_sys::SubXactEvent::Type,
+    my_subid: pg_sys::SubTransactionId,
+    _parent_subid: pg_sys::SubTransactionId,
+    _arg: *mut c_void,
+) {
+    let has_participants = PARTICIPANTS.with(|participants| !participants.borrow().is_empty());
+    if !has_participants {
+        return;
+    }
+
+    debug2!("---> subxact_callback");
+    unsafe {
+        match event {
+            pg_sys::SubXactEvent::SUBXACT_EVENT_PRE_COMMIT_SUB => {
+                call_participants(XactHook::SubCommit(my_subid), true);
+            }
+            pg_sys::SubXactEvent::SUBXACT_EVENT_ABORT_SUB => {
+                call_participants(XactHook::SubAbort(my_subid), false);
+            }
+            _ => {}
+        }
+    }
+}
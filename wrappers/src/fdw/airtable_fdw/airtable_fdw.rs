@@ -1,5 +1,4 @@
 use crate::stats;
-use pgrx::pg_sys;
 use reqwest::{self, header};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
@@ -38,6 +37,8 @@ pub(crate) struct AirtableFdw {
     client: Option<ClientWithMiddleware>,
     base_url: String,
     scan_result: Option<Vec<Row>>,
+    pages_fetched: i64,
+    bytes_in: i64,
 }
 
 impl AirtableFdw {
@@ -97,10 +98,12 @@ impl ForeignDataWrapper<AirtableFdwError> for AirtableFdw {
             .map(|t| t.to_owned())
             .unwrap_or_else(|| "https://api.airtable.com/v0".to_string());
 
-        let client = match server.options.get("api_key") {
+        // credentials can be from user mapping or server options
+        let cred_opts = server.credential_options(&["api_key", "api_key_id"]);
+        let client = match cred_opts.get("api_key") {
             Some(api_key) => Some(create_client(api_key)?),
             None => {
-                let key_id = require_option("api_key_id", &server.options)?;
+                let key_id = require_option("api_key_id", cred_opts)?;
                 if let Some(api_key) = get_vault_secret(key_id) {
                     Some(create_client(&api_key)?)
                 } else {
@@ -116,6 +119,8 @@ impl ForeignDataWrapper<AirtableFdwError> for AirtableFdw {
             client,
             base_url,
             scan_result: None,
+            pages_fetched: 0,
+            bytes_in: 0,
         })
     }
 
@@ -133,6 +138,8 @@ impl ForeignDataWrapper<AirtableFdwError> for AirtableFdw {
         let url = self.build_url(base_id, table_id, view_id);
 
         let mut rows = Vec::new();
+        self.pages_fetched = 0;
+        self.bytes_in = 0;
         if let Some(client) = &self.client {
             let mut offset: Option<String> = None;
 
@@ -142,16 +149,19 @@ impl ForeignDataWrapper<AirtableFdwError> for AirtableFdw {
                 // state so starting with the simpler solution.
                 let url = self.set_limit_offset(&url, None, offset.as_deref())?;
 
-                let body = self.rt.block_on(client.get(&url).send()).and_then(|resp| {
-                    resp.error_for_status()
-                        .and_then(|resp| self.rt.block_on(resp.text()))
-                        .map_err(reqwest_middleware::Error::from)
-                })?;
+                let body =
+                    block_on_interruptible(&self.rt, client.get(&url).send()).and_then(|resp| {
+                        resp.error_for_status()
+                            .and_then(|resp| block_on_interruptible(&self.rt, resp.text()))
+                            .map_err(reqwest_middleware::Error::from)
+                    })?;
 
                 let (new_rows, new_offset) = self.parse_resp(&body, columns)?;
                 rows.extend(new_rows);
 
                 stats::inc_stats(Self::FDW_NAME, stats::Metric::BytesIn, body.len() as i64);
+                self.pages_fetched += 1;
+                self.bytes_in += body.len() as i64;
 
                 if let Some(new_offset) = new_offset {
                     offset = Some(new_offset);
@@ -185,17 +195,52 @@ impl ForeignDataWrapper<AirtableFdwError> for AirtableFdw {
         Ok(())
     }
 
-    fn validator(
-        options: Vec<Option<String>>,
-        catalog: Option<pg_sys::Oid>,
-    ) -> AirtableFdwResult<()> {
-        if let Some(oid) = catalog {
-            if oid == FOREIGN_TABLE_RELATION_ID {
-                check_options_contain(&options, "base_id")?;
-                check_options_contain(&options, "table_id")?;
-            }
+    fn explain_scan(
+        &mut self,
+        _quals: &[Qual],
+        _columns: &[Column],
+        _sorts: &[Sort],
+        _limit: &Option<Limit>,
+        options: &HashMap<String, String>,
+        explain: &ExplainOptions,
+    ) -> AirtableFdwResult<Vec<ExplainProperty>> {
+        let mut props = Vec::new();
+        if explain.verbose {
+            let base_id = require_option("base_id", options)?;
+            let table_id = require_option("table_id", options)?;
+            let url = self.build_url(base_id, table_id, options.get("view_id"));
+            props.push(ExplainProperty::Text("Remote URL".to_string(), url));
         }
+        if explain.analyze {
+            props.push(ExplainProperty::Integer(
+                "Pages Fetched".to_string(),
+                self.pages_fetched,
+            ));
+            props.push(ExplainProperty::Integer(
+                "Bytes In".to_string(),
+                self.bytes_in,
+            ));
+        }
+        Ok(props)
+    }
 
-        Ok(())
+    fn option_defs() -> Vec<OptionDef> {
+        const CRED: &[OptionContext] = &[OptionContext::Server, OptionContext::UserMapping];
+        vec![
+            OptionDef::new("api_url", &[OptionContext::Server])
+                .default("https://api.airtable.com/v0")
+                .description("Airtable API base URL"),
+            OptionDef::new("api_key", CRED)
+                .secret()
+                .description("Airtable API key or personal access token"),
+            OptionDef::new("api_key_id", CRED).description("Vault secret ID of the API key"),
+            OptionDef::new("base_id", &[OptionContext::Table])
+                .required()
+                .description("Airtable base ID"),
+            OptionDef::new("table_id", &[OptionContext::Table])
+                .required()
+                .description("Airtable table ID"),
+            OptionDef::new("view_id", &[OptionContext::Table]).description("Airtable view ID"),
+        ]
     }
 }
@@ -237,9 +237,12 @@ impl AirtableRecord {
                     return Err(AirtableFdwError::UnsupportedColumnType(col.name.clone()));
                 }
             }
-            .map_err(|_| AirtableFdwError::ColumnTypeNotMatch(col.name.clone()))?;
+            .map_err(|_| AirtableFdwError::ColumnTypeNotMatch(col.name.clone()));
 
-            row.push(&col.name, cell);
+            // type mismatch is handled by the framework according to the
+            // `on_error` option
+            let raw = self.fields.0.get(&col.name).unwrap_or(&Value::Null);
+            row.push_or_reject(&col.name, raw, cell);
         }
 
         Ok(row)
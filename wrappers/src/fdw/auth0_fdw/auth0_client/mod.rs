@@ -56,7 +56,7 @@ impl Auth0Client {
     ) -> Result<ResultPayload, Auth0ClientError> {
         let rt = create_async_runtime()?;
 
-        rt.block_on(async {
+        block_on_interruptible(&rt, async {
             let mut url = self.url.clone();
             url.query_pairs_mut().append_pair("page", &page.to_string());
             if let Some(per_page) = per_page {
@@ -52,10 +52,7 @@ enum Auth0FdwError {
     #[error("no secret found in vault with id {0}")]
     SecretNotFound(String),
 
-    #[error("`url` option must be set")]
-    URLOptionMissing,
-
-    #[error("exactly one of `api_key` or `api_key_id` options must be set")]
+    #[error("only one of `api_key` or `api_key_id` options can be set")]
     SetOneOfApiKeyAndApiKeyIdSet,
 }
 
@@ -96,11 +93,12 @@ impl ForeignDataWrapper<Auth0FdwError> for Auth0Fdw {
 
     fn new(server: ForeignServer) -> Result<Self, Auth0FdwError> {
         let url = require_option("url", &server.options)?.to_string();
-        let api_key = if let Some(api_key) = server.options.get("api_key") {
+        // credentials can be from user mapping or server options
+        let cred_opts = server.credential_options(&["api_key", "api_key_id"]);
+        let api_key = if let Some(api_key) = cred_opts.get("api_key") {
             api_key.clone()
         } else {
-            let api_key_id = server
-                .options
+            let api_key_id = cred_opts
                 .get("api_key_id")
                 .expect("`api_key_id` must be set if `api_key` is not");
             get_vault_secret(api_key_id).ok_or(Auth0FdwError::SecretNotFound(api_key_id.clone()))?
@@ -148,22 +146,34 @@ impl ForeignDataWrapper<Auth0FdwError> for Auth0Fdw {
 
     fn validator(options: Vec<Option<String>>, catalog: Option<pg_sys::Oid>) -> Auth0FdwResult<()> {
         if let Some(oid) = catalog {
-            if oid == FOREIGN_SERVER_RELATION_ID {
+            // credentials can be set in either server or user mapping
+            if oid == FOREIGN_SERVER_RELATION_ID || oid == USER_MAPPING_RELATION_ID {
                 let api_key_exists = check_options_contain(&options, "api_key").is_ok();
                 let api_key_id_exists = check_options_contain(&options, "api_key_id").is_ok();
-                let url_exists = check_options_contain(&options, "url").is_ok();
-                if (api_key_exists && api_key_id_exists) || (!api_key_exists && !api_key_id_exists)
-                {
+                if api_key_exists && api_key_id_exists {
                     return Err(Auth0FdwError::SetOneOfApiKeyAndApiKeyIdSet);
                 }
-                if !url_exists {
-                    return Err(Auth0FdwError::URLOptionMissing);
-                }
-            } else if oid == FOREIGN_TABLE_RELATION_ID {
-                check_options_contain(&options, "object")?;
             }
         }
 
         Ok(())
     }
+
+    fn option_defs() -> Vec<OptionDef> {
+        const CRED: &[OptionContext] = &[OptionContext::Server, OptionContext::UserMapping];
+        vec![
+            OptionDef::new("url", &[OptionContext::Server])
+                .required()
+                .description(
+                    "Auth0 API URL, for example `https://<tenant>.auth0.com/api/v2/users`",
+                ),
+            OptionDef::new("api_key", CRED)
+                .secret()
+                .description("Auth0 API key"),
+            OptionDef::new("api_key_id", CRED).description("Vault secret ID of the API key"),
+            OptionDef::new("object", &[OptionContext::Table])
+                .required()
+                .description("Auth0 object name, only `users` is supported"),
+        ]
+    }
 }
@@ -36,6 +36,10 @@ pub(crate) struct BigQueryFdw {
     page_token: Option<String>,
     scan_result: Option<ResultSet>,
     auth_mock: Option<GoogleAuthMock>,
+    group_by: Vec<Column>,
+    aggregates: Vec<Aggregate>,
+    remote_sql: Option<String>,
+    bytes_processed: i64,
 }
 
 impl BigQueryFdw {
@@ -48,7 +52,14 @@ impl BigQueryFdw {
         sorts: &[Sort],
         limit: &Option<Limit>,
     ) -> String {
-        let tgts = if columns.is_empty() {
+        let tgts = if !self.aggregates.is_empty() || !self.group_by.is_empty() {
+            self.group_by
+                .iter()
+                .map(|c| c.name.clone())
+                .chain(self.aggregates.iter().map(|a| a.deparse_with_alias()))
+                .collect::<Vec<String>>()
+                .join(", ")
+        } else if columns.is_empty() {
             "*".to_string()
         } else {
             columns
@@ -63,17 +74,28 @@ impl BigQueryFdw {
             format!("`{}.{}.{}`", self.project_id, self.dataset_id, self.table,)
         };
 
-        let mut sql = if quals.is_empty() {
+        let cond = quals
+            .iter()
+            .filter_map(|q| SqlDialect::BigQuery.deparse_qual(q))
+            .collect::<Vec<String>>()
+            .join(" and ");
+        let mut sql = if cond.is_empty() {
             format!("select {} from {}", tgts, table)
         } else {
-            let cond = quals
-                .iter()
-                .map(|q| q.deparse())
-                .collect::<Vec<String>>()
-                .join(" and ");
             format!("select {} from {} where {}", tgts, table, cond)
         };
 
+        // push down group by
+        if !self.group_by.is_empty() {
+            let group_by = self
+                .group_by
+                .iter()
+                .map(|c| c.name.clone())
+                .collect::<Vec<String>>()
+                .join(", ");
+            sql.push_str(&format!(" group by {}", group_by));
+        }
+
         // push down sorts
         if !sorts.is_empty() {
             let order_by = sorts
@@ -157,6 +179,94 @@ impl BigQueryFdw {
 
         Ok(true)
     }
+
+    fn execute_query(
+        &mut self,
+        quals: &[Qual],
+        columns: &[Column],
+        sorts: &[Sort],
+        limit: &Option<Limit>,
+        options: &HashMap<String, String>,
+    ) -> BigQueryFdwResult<()> {
+        self.table = require_option("table", options)?.to_string();
+        self.tgt_cols = columns.to_vec();
+
+        let location = options
+            .get("location")
+            .map(|t| t.to_owned())
+            .unwrap_or_else(|| "US".to_string());
+
+        let mut timeout: i32 = 30_000;
+        if let Some(timeout_str) = options.get("timeout") {
+            match timeout_str.parse::<i32>() {
+                Ok(t) => timeout = t,
+                Err(_) => report_error(
+                    PgSqlErrorCode::ERRCODE_FDW_ERROR,
+                    &format!("invalid timeout value: {}", timeout_str),
+                ),
+            }
+        }
+
+        if let Some(client) = &self.client {
+            let sql = self.deparse(quals, columns, sorts, limit);
+            let mut req = QueryRequest::new(sql.clone());
+            req.location = Some(location);
+            req.timeout_ms = Some(timeout);
+
+            // execute query on BigQuery
+            match block_on_interruptible(&self.rt, client.job().query(&self.project_id, req)) {
+                Ok(resp) => {
+                    if resp.job_complete == Some(false) {
+                        report_error(
+                            PgSqlErrorCode::ERRCODE_FDW_ERROR,
+                            &format!("query timeout {}ms expired", timeout),
+                        );
+                    } else {
+                        self.remote_sql = Some(sql);
+                        self.bytes_processed = resp
+                            .total_bytes_processed
+                            .as_ref()
+                            .and_then(|v| v.parse::<i64>().ok())
+                            .unwrap_or(0i64);
+                        stats::inc_stats(
+                            Self::FDW_NAME,
+                            stats::Metric::RowsIn,
+                            resp.total_rows
+                                .as_ref()
+                                .and_then(|v| v.parse::<i64>().ok())
+                                .unwrap_or(0i64),
+                        );
+                        stats::inc_stats(
+                            Self::FDW_NAME,
+                            stats::Metric::RowsOut,
+                            resp.total_rows
+                                .as_ref()
+                                .and_then(|v| v.parse::<i64>().ok())
+                                .unwrap_or(0i64),
+                        );
+                        stats::inc_stats(
+                            Self::FDW_NAME,
+                            stats::Metric::BytesIn,
+                            self.bytes_processed,
+                        );
+                        self.job_ref = resp.job_reference.clone();
+                        self.page_token = resp.page_token.clone();
+                        let rs = ResultSet::new_from_query_response(resp);
+                        self.scan_result = Some(rs);
+                    }
+                }
+                Err(err) => {
+                    self.scan_result = None;
+                    report_error(
+                        PgSqlErrorCode::ERRCODE_FDW_ERROR,
+                        &format!("query failed: {}", err),
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl ForeignDataWrapper<BigQueryFdwError> for BigQueryFdw {
@@ -175,6 +285,10 @@ impl ForeignDataWrapper<BigQueryFdwError> for BigQueryFdw {
             page_token: None,
             scan_result: None,
             auth_mock: None,
+            group_by: Vec::new(),
+            aggregates: Vec::new(),
+            remote_sql: None,
+            bytes_processed: 0,
         };
 
         // Is authentication mocked
@@ -191,6 +305,8 @@ impl ForeignDataWrapper<BigQueryFdwError> for BigQueryFdw {
             .map(|t| t.to_owned())
             .unwrap_or_else(|| "https://bigquery.googleapis.com/bigquery/v2".to_string());
 
+        // credentials can be from user mapping or server options
+        let cred_opts = server.credential_options(&["sa_key", "sa_key_id"]);
         let sa_key_json = match mock_auth {
             true => {
                 // Key file is not required if we're mocking auth
@@ -202,10 +318,10 @@ impl ForeignDataWrapper<BigQueryFdwError> for BigQueryFdw {
                 serde_json::to_string_pretty(&dummy_auth_config)
                     .expect("dummy auth config should not fail to serialize")
             }
-            false => match server.options.get("sa_key") {
+            false => match cred_opts.get("sa_key") {
                 Some(sa_key) => sa_key.to_owned(),
                 None => {
-                    let sa_key_id = require_option("sa_key_id", &server.options)?;
+                    let sa_key_id = require_option("sa_key_id", cred_opts)?;
                     match get_vault_secret(sa_key_id) {
                         Some(sa_key) => sa_key,
                         None => return Ok(ret),
@@ -225,7 +341,8 @@ impl ForeignDataWrapper<BigQueryFdwError> for BigQueryFdw {
             }
         };
 
-        ret.client = match ret.rt.block_on(
+        ret.client = match block_on_interruptible(
+            &ret.rt,
             ClientBuilder::new()
                 .with_v2_base_url(api_endpoint)
                 .build_from_service_account_key(sa_key, false),
@@ -256,6 +373,27 @@ impl ForeignDataWrapper<BigQueryFdwError> for BigQueryFdw {
         Ok((0, 0))
     }
 
+    fn get_scan_pushdown(
+        &mut self,
+        quals: &[Qual],
+        sorts: &[Sort],
+        _limit: &Option<Limit>,
+        _options: &HashMap<String, String>,
+    ) -> BigQueryFdwResult<Option<Pushdown>> {
+        // quals whose operators can be translated, sorts and limit are
+        // deparsed into the remote query, but the remote server sorts strings
+        // by bytes so only the sorts which don't depend on the locale are
+        // pushed down, and limit must be applied after sorting
+        let is_sorted = sorts.iter().all(Sort::is_locale_independent);
+        Ok(Some(Pushdown {
+            quals: (0..quals.len())
+                .filter(|i| SqlDialect::BigQuery.is_pushable(&quals[*i]))
+                .collect(),
+            sorts: is_sorted,
+            limit: is_sorted,
+        }))
+    }
+
     fn begin_scan(
         &mut self,
         quals: &[Qual],
@@ -264,81 +402,39 @@ impl ForeignDataWrapper<BigQueryFdwError> for BigQueryFdw {
         limit: &Option<Limit>,
         options: &HashMap<String, String>,
     ) -> Result<(), BigQueryFdwError> {
-        self.table = require_option("table", options)?.to_string();
-        self.tgt_cols = columns.to_vec();
-
-        let location = options
-            .get("location")
-            .map(|t| t.to_owned())
-            .unwrap_or_else(|| "US".to_string());
+        self.group_by.clear();
+        self.aggregates.clear();
+        self.execute_query(quals, columns, sorts, limit, options)
+    }
 
-        let mut timeout: i32 = 30_000;
-        if let Some(timeout_str) = options.get("timeout") {
-            match timeout_str.parse::<i32>() {
-                Ok(t) => timeout = t,
-                Err(_) => report_error(
-                    PgSqlErrorCode::ERRCODE_FDW_ERROR,
-                    &format!("invalid timeout value: {}", timeout_str),
-                ),
-            }
-        }
+    fn get_aggregate_pushdown(
+        &mut self,
+        quals: &[Qual],
+        _group_by: &[Column],
+        _aggregates: &[Aggregate],
+        _options: &HashMap<String, String>,
+    ) -> Result<bool, BigQueryFdwError> {
+        Ok(quals.iter().all(|q| SqlDialect::BigQuery.is_pushable(q)))
+    }
 
-        if let Some(client) = &self.client {
-            let sql = self.deparse(quals, columns, sorts, limit);
-            let mut req = QueryRequest::new(sql);
-            req.location = Some(location);
-            req.timeout_ms = Some(timeout);
+    fn begin_aggregate_scan(
+        &mut self,
+        quals: &[Qual],
+        group_by: &[Column],
+        aggregates: &[Aggregate],
+        options: &HashMap<String, String>,
+    ) -> Result<(), BigQueryFdwError> {
+        self.group_by = group_by.to_vec();
+        self.aggregates = aggregates.to_vec();
 
-            // execute query on BigQuery
-            match self.rt.block_on(client.job().query(&self.project_id, req)) {
-                Ok(resp) => {
-                    if resp.job_complete == Some(false) {
-                        report_error(
-                            PgSqlErrorCode::ERRCODE_FDW_ERROR,
-                            &format!("query timeout {}ms expired", timeout),
-                        );
-                    } else {
-                        stats::inc_stats(
-                            Self::FDW_NAME,
-                            stats::Metric::RowsIn,
-                            resp.total_rows
-                                .as_ref()
-                                .and_then(|v| v.parse::<i64>().ok())
-                                .unwrap_or(0i64),
-                        );
-                        stats::inc_stats(
-                            Self::FDW_NAME,
-                            stats::Metric::RowsOut,
-                            resp.total_rows
-                                .as_ref()
-                                .and_then(|v| v.parse::<i64>().ok())
-                                .unwrap_or(0i64),
-                        );
-                        stats::inc_stats(
-                            Self::FDW_NAME,
-                            stats::Metric::BytesIn,
-                            resp.total_bytes_processed
-                                .as_ref()
-                                .and_then(|v| v.parse::<i64>().ok())
-                                .unwrap_or(0i64),
-                        );
-                        self.job_ref = resp.job_reference.clone();
-                        self.page_token = resp.page_token.clone();
-                        let rs = ResultSet::new_from_query_response(resp);
-                        self.scan_result = Some(rs);
-                    }
-                }
-                Err(err) => {
-                    self.scan_result = None;
-                    report_error(
-                        PgSqlErrorCode::ERRCODE_FDW_ERROR,
-                        &format!("query failed: {}", err),
-                    );
-                }
-            }
-        }
+        // output columns are group by columns followed by aggregates
+        let columns = group_by
+            .iter()
+            .cloned()
+            .chain(aggregates.iter().map(|agg| agg.as_column()))
+            .collect::<Vec<_>>();
 
-        Ok(())
+        self.execute_query(quals, &columns, &[], &None, options)
     }
 
     fn iter_scan(&mut self, row: &mut Row) -> Result<Option<()>, BigQueryFdwError> {
@@ -356,15 +452,18 @@ impl ForeignDataWrapper<BigQueryFdwError> for BigQueryFdw {
                 // otherwise, dealing with pagination
                 if let Some(job_ref) = &self.job_ref {
                     if let Some(job_id) = &job_ref.job_id {
-                        match self.rt.block_on(client.job().get_query_results(
-                            &self.project_id,
-                            job_id,
-                            GetQueryResultsParameters {
-                                location: job_ref.location.clone(),
-                                page_token: self.page_token.clone(),
-                                ..Default::default()
-                            },
-                        )) {
+                        match block_on_interruptible(
+                            &self.rt,
+                            client.job().get_query_results(
+                                &self.project_id,
+                                job_id,
+                                GetQueryResultsParameters {
+                                    location: job_ref.location.clone(),
+                                    page_token: self.page_token.clone(),
+                                    ..Default::default()
+                                },
+                            ),
+                        ) {
                             Ok(resp) => {
                                 // replace result set with data from the new page
                                 self.job_ref = resp.job_reference.clone();
@@ -394,6 +493,36 @@ impl ForeignDataWrapper<BigQueryFdwError> for BigQueryFdw {
         Ok(())
     }
 
+    fn explain_scan(
+        &mut self,
+        quals: &[Qual],
+        columns: &[Column],
+        sorts: &[Sort],
+        limit: &Option<Limit>,
+        options: &HashMap<String, String>,
+        explain: &ExplainOptions,
+    ) -> Result<Vec<ExplainProperty>, BigQueryFdwError> {
+        let mut props = Vec::new();
+        if explain.verbose {
+            // use the query already sent if the scan is executed
+            let sql = match &self.remote_sql {
+                Some(sql) => sql.clone(),
+                None => {
+                    self.table = require_option("table", options)?.to_string();
+                    self.deparse(quals, columns, sorts, limit)
+                }
+            };
+            props.push(ExplainProperty::Text("Remote SQL".to_string(), sql));
+        }
+        if explain.analyze {
+            props.push(ExplainProperty::Integer(
+                "Bytes Processed".to_string(),
+                self.bytes_processed,
+            ));
+        }
+        Ok(props)
+    }
+
     fn begin_modify(&mut self, options: &HashMap<String, String>) -> Result<(), BigQueryFdwError> {
         self.table = require_option("table", options)?.to_string();
         self.rowid_col = require_option("rowid_column", options)?.to_string();
@@ -402,44 +531,54 @@ impl ForeignDataWrapper<BigQueryFdwError> for BigQueryFdw {
     }
 
     fn insert(&mut self, src: &Row) -> Result<(), BigQueryFdwError> {
+        self.insert_batch(std::slice::from_ref(src))
+    }
+
+    fn insert_batch(&mut self, rows: &[Row]) -> Result<(), BigQueryFdwError> {
         if let Some(ref mut client) = self.client {
             let mut insert_request = TableDataInsertAllRequest::new();
-            let mut row_json = json!({});
 
-            for (col_name, cell) in src.iter() {
-                if let Some(cell) = cell {
-                    match cell {
-                        Cell::Bool(v) => row_json[col_name] = json!(v),
-                        Cell::I8(v) => row_json[col_name] = json!(v),
-                        Cell::I16(v) => row_json[col_name] = json!(v),
-                        Cell::I32(v) => row_json[col_name] = json!(v),
-                        Cell::I64(v) => row_json[col_name] = json!(v),
-                        Cell::F32(v) => row_json[col_name] = json!(v),
-                        Cell::F64(v) => row_json[col_name] = json!(v),
-                        Cell::Numeric(v) => row_json[col_name] = json!(v),
-                        Cell::String(v) => row_json[col_name] = json!(v),
-                        Cell::Date(v) => row_json[col_name] = json!(v),
-                        Cell::Timestamp(v) => row_json[col_name] = json!(v),
-                        Cell::Timestamptz(v) => row_json[col_name] = json!(v),
-                        Cell::Json(v) => row_json[col_name] = json!(v.0.to_string()),
-                        _ => {
-                            return Err(BigQueryFdwError::UnsupportedFieldType(
-                                col_name.to_owned(),
-                            ));
+            for src in rows {
+                let mut row_json = json!({});
+
+                for (col_name, cell) in src.iter() {
+                    if let Some(cell) = cell {
+                        match cell {
+                            Cell::Bool(v) => row_json[col_name] = json!(v),
+                            Cell::I8(v) => row_json[col_name] = json!(v),
+                            Cell::I16(v) => row_json[col_name] = json!(v),
+                            Cell::I32(v) => row_json[col_name] = json!(v),
+                            Cell::I64(v) => row_json[col_name] = json!(v),
+                            Cell::F32(v) => row_json[col_name] = json!(v),
+                            Cell::F64(v) => row_json[col_name] = json!(v),
+                            Cell::Numeric(v) => row_json[col_name] = json!(v),
+                            Cell::String(v) => row_json[col_name] = json!(v),
+                            Cell::Date(v) => row_json[col_name] = json!(v),
+                            Cell::Timestamp(v) => row_json[col_name] = json!(v),
+                            Cell::Timestamptz(v) => row_json[col_name] = json!(v),
+                            Cell::Json(v) => row_json[col_name] = json!(v.0.to_string()),
+                            _ => {
+                                return Err(BigQueryFdwError::UnsupportedFieldType(
+                                    col_name.to_owned(),
+                                ));
+                            }
                         }
                     }
                 }
-            }
 
-            insert_request.add_row(None, row_json)?;
+                insert_request.add_row(None, row_json)?;
+            }
 
             // execute insert job on BigQuery
-            let resp = self.rt.block_on(client.tabledata().insert_all(
-                &self.project_id,
-                &self.dataset_id,
-                &self.table,
-                insert_request,
-            ))?;
+            let resp = block_on_interruptible(
+                &self.rt,
+                client.tabledata().insert_all(
+                    &self.project_id,
+                    &self.dataset_id,
+                    &self.table,
+                    insert_request,
+                ),
+            )?;
             if let Some(errors) = resp.insert_errors {
                 report_error(
                     PgSqlErrorCode::ERRCODE_FDW_ERROR,
@@ -480,7 +619,7 @@ impl ForeignDataWrapper<BigQueryFdwError> for BigQueryFdw {
             let query_job = client.job().query(&self.project_id, QueryRequest::new(sql));
 
             // execute update on BigQuery
-            if let Err(err) = self.rt.block_on(query_job) {
+            if let Err(err) = block_on_interruptible(&self.rt, query_job) {
                 report_error(
                     PgSqlErrorCode::ERRCODE_FDW_ERROR,
                     &format!("update failed: {}", err),
@@ -500,7 +639,7 @@ impl ForeignDataWrapper<BigQueryFdwError> for BigQueryFdw {
             let query_job = client.job().query(&self.project_id, QueryRequest::new(sql));
 
             // execute delete on BigQuery
-            if let Err(err) = self.rt.block_on(query_job) {
+            if let Err(err) = block_on_interruptible(&self.rt, query_job) {
                 report_error(
                     PgSqlErrorCode::ERRCODE_FDW_ERROR,
                     &format!("update failed: {}", err),
@@ -509,6 +648,76 @@ impl ForeignDataWrapper<BigQueryFdwError> for BigQueryFdw {
         }
         Ok(())
     }
+
+    fn truncate(
+        &mut self,
+        tables: &[HashMap<String, String>],
+        _restart_seqs: bool,
+        _cascade: bool,
+    ) -> Result<(), BigQueryFdwError> {
+        let tables = tables
+            .iter()
+            .map(|options| {
+                let table = require_option("table", options)?;
+                if table.starts_with('(') {
+                    return Err(BigQueryFdwError::TruncateSubquery(table.to_string()));
+                }
+                Ok(table.to_string())
+            })
+            .collect::<BigQueryFdwResult<Vec<_>>>()?;
+
+        let Some(ref mut client) = self.client else {
+            return Err(BigQueryFdwError::NoClient);
+        };
+        for table in tables {
+            let sql = format!(
+                "truncate table `{}.{}.{}`",
+                self.project_id, self.dataset_id, table
+            );
+
+            let query_job = client.job().query(&self.project_id, QueryRequest::new(sql));
+
+            // execute truncate on BigQuery
+            block_on_interruptible(&self.rt, query_job)?;
+        }
+        Ok(())
+    }
+
+    fn option_defs() -> Vec<OptionDef> {
+        const CRED: &[OptionContext] = &[OptionContext::Server, OptionContext::UserMapping];
+        vec![
+            OptionDef::new("project_id", &[OptionContext::Server])
+                .required()
+                .description("BigQuery project ID"),
+            OptionDef::new("dataset_id", &[OptionContext::Server])
+                .required()
+                .description("BigQuery dataset ID"),
+            OptionDef::new("sa_key", CRED)
+                .secret()
+                .description("Service account key in JSON format"),
+            OptionDef::new("sa_key_id", CRED)
+                .description("Vault secret ID of the service account key"),
+            OptionDef::new("api_endpoint", &[OptionContext::Server])
+                .default("https://bigquery.googleapis.com/bigquery/v2")
+                .description("BigQuery API endpoint"),
+            OptionDef::new("mock_auth", &[OptionContext::Server])
+                .opt_type(OptionType::Bool)
+                .default("false")
+                .description("Whether to mock the authentication, for testing only"),
+            OptionDef::new("table", &[OptionContext::Table])
+                .required()
+                .description("Source table, view name or subquery"),
+            OptionDef::new("location", &[OptionContext::Table])
+                .default("US")
+                .description("Source table location"),
+            OptionDef::new("timeout", &[OptionContext::Table])
+                .opt_type(OptionType::Integer)
+                .default("30000")
+                .description("Query request timeout in milliseconds"),
+            OptionDef::new("rowid_column", &[OptionContext::Table])
+                .description("Primary key column name, required for data modify"),
+        ]
+    }
 }
 
 use crate::fdw::bigquery_fdw::{BigQueryFdwError, BigQueryFdwResult};
@@ -20,6 +20,12 @@ enum BigQueryFdwError {
     #[error("big query error: {0}")]
     BigQueryError(#[from] BQError),
 
+    #[error("cannot truncate subquery table '{0}'")]
+    TruncateSubquery(String),
+
+    #[error("BigQuery client is not created")]
+    NoClient,
+
     #[error("field {0} type not supported")]
     UnsupportedFieldType(String),
 
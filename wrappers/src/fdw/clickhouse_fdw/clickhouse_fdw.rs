@@ -10,9 +10,11 @@ use clickhouse_rs::{
     ClientHandle, Pool,
 };
 use pgrx::datum::numeric::AnyNumeric;
+use pgrx::pg_sys;
 use pgrx::prelude::to_timestamp;
 use regex::{Captures, Regex};
 use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
 use std::str::FromStr;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -21,7 +23,20 @@ use supabase_wrappers::prelude::*;
 
 use super::{ClickHouseFdwError, ClickHouseFdwResult};
 
-fn field_to_cell(row: &types::Row<types::Complex>, i: usize) -> ClickHouseFdwResult<Option<Cell>> {
+// convert an enum label to cell, it is an enum cell if target column is not text
+fn enum_label_to_cell(label: &str, tgt_col: &Column) -> Cell {
+    if tgt_col.type_oid == pg_sys::TEXTOID {
+        Cell::String(label.to_owned())
+    } else {
+        Cell::Enum(tgt_col.type_oid, label.to_owned())
+    }
+}
+
+fn field_to_cell(
+    row: &types::Row<types::Complex>,
+    i: usize,
+    tgt_col: &Column,
+) -> ClickHouseFdwResult<Option<Cell>> {
     let sql_type = row.sql_type(i)?;
     match sql_type {
         SqlType::Bool => {
@@ -113,6 +128,28 @@ fn field_to_cell(row: &types::Row<types::Complex>, i: usize) -> ClickHouseFdwRes
             let value = row.get::<Uuid, usize>(i)?;
             Ok(Some(Cell::Uuid(pgrx::Uuid::from_bytes(*value.as_bytes()))))
         }
+        SqlType::Ipv4 => {
+            let value = row.get::<Ipv4Addr, usize>(i)?;
+            Ok(Some(Cell::Inet(value.to_string())))
+        }
+        SqlType::Ipv6 => {
+            let value = row.get::<Ipv6Addr, usize>(i)?;
+            Ok(Some(Cell::Inet(value.to_string())))
+        }
+        SqlType::Enum8(ref labels) => {
+            let value = row.get::<types::Enum8, usize>(i)?;
+            Ok(labels
+                .iter()
+                .find(|(_, v)| *v == value.internal())
+                .map(|(label, _)| enum_label_to_cell(label, tgt_col)))
+        }
+        SqlType::Enum16(ref labels) => {
+            let value = row.get::<types::Enum16, usize>(i)?;
+            Ok(labels
+                .iter()
+                .find(|(_, v)| *v == value.internal())
+                .map(|(label, _)| enum_label_to_cell(label, tgt_col)))
+        }
         SqlType::Array(SqlType::Bool) => {
             let value = row
                 .get::<Vec<bool>, usize>(i)?
@@ -169,6 +206,40 @@ fn field_to_cell(row: &types::Row<types::Complex>, i: usize) -> ClickHouseFdwRes
                 .collect();
             Ok(Some(Cell::StringArray(value)))
         }
+        SqlType::Array(SqlType::Decimal(_u, _s)) => {
+            let value = row
+                .get::<Vec<types::Decimal>, usize>(i)?
+                .into_iter()
+                .map(|v| AnyNumeric::try_from(v.to_string().as_str()).map(Some))
+                .collect::<Result<_, _>>()?;
+            Ok(Some(Cell::NumericArray(value)))
+        }
+        SqlType::Array(SqlType::Date) => {
+            let value = row
+                .get::<Vec<NaiveDate>, usize>(i)?
+                .into_iter()
+                .map(|v| {
+                    pgrx::prelude::Date::new(v.year(), v.month() as u8, v.day() as u8).map(Some)
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(Some(Cell::DateArray(value)))
+        }
+        SqlType::Array(SqlType::DateTime(_)) => {
+            let value = row
+                .get::<Vec<DateTime<_>>, usize>(i)?
+                .into_iter()
+                .map(|v| Some(to_timestamp(v.timestamp() as f64).to_utc()))
+                .collect();
+            Ok(Some(Cell::TimestampArray(value)))
+        }
+        SqlType::Array(SqlType::Uuid) => {
+            let value = row
+                .get::<Vec<Uuid>, usize>(i)?
+                .into_iter()
+                .map(|v| Some(pgrx::Uuid::from_bytes(*v.as_bytes())))
+                .collect();
+            Ok(Some(Cell::UuidArray(value)))
+        }
         SqlType::Nullable(v) => match v {
             SqlType::Bool => {
                 let value = row.get::<Option<bool>, usize>(i)?;
@@ -265,6 +336,14 @@ fn field_to_cell(row: &types::Row<types::Complex>, i: usize) -> ClickHouseFdwRes
                     .map(|t| pgrx::Uuid::from_bytes(*t.as_bytes()))
                     .map(Cell::Uuid))
             }
+            SqlType::Ipv4 => {
+                let value = row.get::<Option<Ipv4Addr>, usize>(i)?;
+                Ok(value.map(|t| Cell::Inet(t.to_string())))
+            }
+            SqlType::Ipv6 => {
+                let value = row.get::<Option<Ipv6Addr>, usize>(i)?;
+                Ok(value.map(|t| Cell::Inet(t.to_string())))
+            }
             _ => Err(ClickHouseFdwError::UnsupportedColumnType(
                 sql_type.to_string().into(),
             )),
@@ -299,6 +378,239 @@ where
     Ok(val)
 }
 
+// convert a row to ClickHouse values, the target column types are probed from
+// the block returned by a dummy query
+fn row_to_clickhouse_values(
+    src: &Row,
+    probe: &Block<types::Complex>,
+) -> ClickHouseFdwResult<Vec<(String, ChValue)>> {
+    let mut row = Vec::new();
+    for (col_name, cell) in src.iter() {
+        let col_name = col_name.to_owned();
+        let tgt_col = probe.get_column(col_name.as_ref())?;
+        let tgt_type = tgt_col.sql_type();
+        let is_nullable = matches!(tgt_type, SqlType::Nullable(_));
+
+        let value = cell
+            .as_ref()
+            .map(|c| match c {
+                Cell::Bool(v) => {
+                    let val = if is_nullable {
+                        ChValue::from(Some(*v))
+                    } else {
+                        ChValue::from(*v)
+                    };
+                    Ok(val)
+                }
+                Cell::I8(v) => {
+                    let val = if is_nullable {
+                        ChValue::from(Some(*v))
+                    } else {
+                        ChValue::from(*v)
+                    };
+                    Ok(val)
+                }
+                Cell::I16(v) => match tgt_col.sql_type() {
+                    // i16 can be converted to 2 ClickHouse types: Int16 and UInt8
+                    SqlType::Int16 | SqlType::Nullable(SqlType::Int16) => {
+                        let val = if is_nullable {
+                            ChValue::from(Some(*v))
+                        } else {
+                            ChValue::from(*v)
+                        };
+                        Ok(val)
+                    }
+                    SqlType::UInt8 | SqlType::Nullable(SqlType::UInt8) => {
+                        let val = if is_nullable {
+                            ChValue::from(Some(*v as u8))
+                        } else {
+                            ChValue::from(*v as u8)
+                        };
+                        Ok(val)
+                    }
+                    _ => Err(ClickHouseFdwError::UnsupportedColumnType(
+                        tgt_type.to_string().into(),
+                    )),
+                },
+                Cell::F32(v) => {
+                    let val = if is_nullable {
+                        ChValue::from(Some(*v))
+                    } else {
+                        ChValue::from(*v)
+                    };
+                    Ok(val)
+                }
+                Cell::I32(v) => match tgt_col.sql_type() {
+                    // i32 can be converted to 2 ClickHouse types: Int32 and UInt16
+                    SqlType::Int32 | SqlType::Nullable(SqlType::Int32) => {
+                        let val = if is_nullable {
+                            ChValue::from(Some(*v))
+                        } else {
+                            ChValue::from(*v)
+                        };
+                        Ok(val)
+                    }
+                    SqlType::UInt16 | SqlType::Nullable(SqlType::UInt16) => {
+                        let val = if is_nullable {
+                            ChValue::from(Some(*v as u16))
+                        } else {
+                            ChValue::from(*v as u16)
+                        };
+                        Ok(val)
+                    }
+                    _ => Err(ClickHouseFdwError::UnsupportedColumnType(
+                        tgt_type.to_string().into(),
+                    )),
+                },
+                Cell::F64(v) => {
+                    let val = if is_nullable {
+                        ChValue::from(Some(*v))
+                    } else {
+                        ChValue::from(*v)
+                    };
+                    Ok(val)
+                }
+                Cell::I64(v) => match tgt_col.sql_type() {
+                    // i64 can be converted to 2 ClickHouse types: Int64 and UInt32
+                    SqlType::Int64 | SqlType::Nullable(SqlType::Int64) => {
+                        let val = if is_nullable {
+                            ChValue::from(Some(*v))
+                        } else {
+                            ChValue::from(*v)
+                        };
+                        Ok(val)
+                    }
+                    SqlType::UInt32 | SqlType::Nullable(SqlType::UInt32) => {
+                        let val = if is_nullable {
+                            ChValue::from(Some(*v as u32))
+                        } else {
+                            ChValue::from(*v as u32)
+                        };
+                        Ok(val)
+                    }
+                    _ => Err(ClickHouseFdwError::UnsupportedColumnType(
+                        tgt_type.to_string().into(),
+                    )),
+                },
+                Cell::Numeric(v) => {
+                    let v = types::Decimal::from_str(v.normalize())?;
+                    let val = if is_nullable {
+                        ChValue::from(Some(v))
+                    } else {
+                        ChValue::from(v)
+                    };
+                    Ok(val)
+                }
+                Cell::String(v) => {
+                    let s = v.as_str();
+
+                    // i256 and u256 are saved as string in Postgres, so we parse it
+                    // back to ClickHouse if target column is Int256 or UInt256
+                    let val = match tgt_col.sql_type() {
+                        SqlType::Int256 | SqlType::Nullable(SqlType::Int256) => {
+                            let v = i256::from_str(s)?;
+                            if is_nullable {
+                                ChValue::from(Some(v))
+                            } else {
+                                ChValue::from(v)
+                            }
+                        }
+                        SqlType::UInt256 | SqlType::Nullable(SqlType::UInt256) => {
+                            let v = u256::from_str(s)?;
+                            if is_nullable {
+                                ChValue::from(Some(v))
+                            } else {
+                                ChValue::from(v)
+                            }
+                        }
+                        _ => {
+                            // other than i256 and u256, convert it to string as normal
+                            if is_nullable {
+                                ChValue::from(Some(s))
+                            } else {
+                                ChValue::from(s)
+                            }
+                        }
+                    };
+                    Ok(val)
+                }
+                Cell::Date(_) => {
+                    let s = c.to_string().replace('\'', "");
+                    let tm = NaiveDate::parse_from_str(&s, "%Y-%m-%d")?;
+                    let val = if is_nullable {
+                        ChValue::from(Some(tm))
+                    } else {
+                        let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+                        let duration = tm - epoch;
+                        let dt = duration.num_days() as u16;
+                        ChValue::Date(dt)
+                    };
+                    Ok(val)
+                }
+                Cell::Timestamp(_) => {
+                    let s = c.to_string().replace('\'', "");
+                    let naive_tm = NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S")
+                        .or_else(|_| NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S%.6f"))?;
+                    let tm: DateTime<Utc> = DateTime::from_naive_utc_and_offset(naive_tm, Utc);
+                    let val = if is_nullable {
+                        ChValue::Nullable(either::Either::Right(Box::new(tm.into())))
+                    } else {
+                        ChValue::from(tm)
+                    };
+                    Ok(val)
+                }
+                Cell::Uuid(v) => {
+                    let uuid = Uuid::try_parse(&v.to_string())?;
+                    let val = if is_nullable {
+                        ChValue::Nullable(either::Either::Right(Box::new(ChValue::Uuid(
+                            *uuid.as_bytes(),
+                        ))))
+                    } else {
+                        ChValue::from(uuid)
+                    };
+                    Ok(val)
+                }
+                Cell::BoolArray(v) => {
+                    array_cell_to_clickhouse_value(v, &SqlType::Bool, is_nullable)
+                }
+                Cell::I16Array(v) => {
+                    array_cell_to_clickhouse_value(v, &SqlType::Int16, is_nullable)
+                }
+                Cell::I32Array(v) => {
+                    array_cell_to_clickhouse_value(v, &SqlType::Int32, is_nullable)
+                }
+                Cell::I64Array(v) => {
+                    array_cell_to_clickhouse_value(v, &SqlType::Int64, is_nullable)
+                }
+                Cell::F32Array(v) => {
+                    array_cell_to_clickhouse_value(v, &SqlType::Float32, is_nullable)
+                }
+                Cell::F64Array(v) => {
+                    array_cell_to_clickhouse_value(v, &SqlType::Float64, is_nullable)
+                }
+                Cell::StringArray(v) => {
+                    array_cell_to_clickhouse_value(v, &SqlType::String, is_nullable)
+                }
+                _ => Err(ClickHouseFdwError::UnsupportedColumnType(
+                    tgt_type.to_string().into(),
+                )),
+            })
+            .transpose()?;
+
+        if let Some(v) = value {
+            row.push((col_name, v));
+        }
+    }
+    Ok(row)
+}
+
+// a write to ClickHouse which can be deferred until the local transaction
+// commits
+enum PendingWrite {
+    Insert(Vec<Row>),
+    Execute(String),
+}
+
 #[wrappers_fdw(
     version = "0.1.7",
     author = "Supabase",
@@ -306,8 +618,8 @@ where
     error_type = "ClickHouseFdwError"
 )]
 pub(crate) struct ClickHouseFdw {
-    rt: Runtime,
-    conn_str: String,
+    rt: Arc<Runtime>,
+    pool: Pool,
     client: Option<ClientHandle>,
     table: String,
     rowid_col: String,
@@ -315,14 +627,76 @@ pub(crate) struct ClickHouseFdw {
     scan_blk: Option<Block<types::Complex>>,
     row_idx: usize,
     params: Vec<Qual>,
+    group_by: Vec<Column>,
+    aggregates: Vec<Aggregate>,
+    filters: Vec<Filter>,
+    remote_sql: Option<String>,
+
+    // writes deferred until the local transaction commits, with the id of the
+    // subtransaction they are made in, none if writes are not deferred
+    pending_writes: Option<Vec<(pg_sys::SubTransactionId, PendingWrite)>>,
 }
 
 impl ClickHouseFdw {
     const FDW_NAME: &'static str = "ClickHouseFdw";
 
+    // maximum number of rows read by analyze, in times of sample size
+    const ANALYZE_MAX_SCAN_RATIO: usize = 100;
+
     fn create_client(&mut self) -> ClickHouseFdwResult<()> {
-        let pool = Pool::new(self.conn_str.as_str());
-        self.client = Some(self.rt.block_on(pool.get_handle())?);
+        self.client = Some(block_on_interruptible(&self.rt, self.pool.get_handle())?);
+        Ok(())
+    }
+
+    // execute the write on ClickHouse, or keep it pending until the local
+    // transaction commits if writes are deferred
+    fn write(&mut self, write: PendingWrite) -> ClickHouseFdwResult<()> {
+        if let Some(ref mut pending_writes) = self.pending_writes {
+            let subxact_id = unsafe { pg_sys::GetCurrentSubTransactionId() };
+            pending_writes.push((subxact_id, write));
+            return Ok(());
+        }
+        self.execute_write(write)
+    }
+
+    fn execute_write(&mut self, write: PendingWrite) -> ClickHouseFdwResult<()> {
+        let Some(ref mut client) = self.client else {
+            return Ok(());
+        };
+
+        match write {
+            PendingWrite::Insert(rows) => {
+                // use a dummy query to probe column types
+                let sql = format!("select * from {} where false", self.table);
+                let probe = block_on_interruptible(&self.rt, client.query(&sql).fetch_all())?;
+
+                // add rows to blocks, null cells are not sent so the column
+                // default value can be applied, thus a new block is started
+                // when the column list changes
+                let mut blocks: Vec<(Vec<String>, Block)> = Vec::new();
+                for src in &rows {
+                    let row = row_to_clickhouse_values(src, &probe)?;
+                    let cols = row.iter().map(|(c, _)| c.clone()).collect::<Vec<_>>();
+                    match blocks.last_mut() {
+                        Some((last_cols, block)) if *last_cols == cols => block.push(row)?,
+                        _ => {
+                            let mut block = Block::new();
+                            block.push(row)?;
+                            blocks.push((cols, block));
+                        }
+                    }
+                }
+
+                // execute query on ClickHouse
+                for (_, block) in blocks {
+                    block_on_interruptible(&self.rt, client.insert(&self.table, block))?;
+                }
+            }
+            PendingWrite::Execute(sql) => {
+                // execute query on ClickHouse
+                block_on_interruptible(&self.rt, client.execute(&sql))?;
+            }
+        }
         Ok(())
     }
 
@@ -343,6 +717,39 @@ impl ClickHouseFdw {
         Ok(new)
     }
 
+    // deparse an aggregate, its result is casted to the ClickHouse type which
+    // matches the Postgres result type
+    fn deparse_aggregate(aggregate: &Aggregate) -> String {
+        let ch_type = match aggregate.type_oid {
+            pg_sys::INT2OID => Some("Int16"),
+            pg_sys::INT4OID => Some("Int32"),
+            pg_sys::INT8OID => Some("Int64"),
+            pg_sys::FLOAT4OID => Some("Float32"),
+            pg_sys::FLOAT8OID => Some("Float64"),
+            pg_sys::NUMERICOID => Some("Decimal(38, 10)"),
+            _ => None,
+        };
+        match (aggregate.kind, ch_type) {
+            (AggregateKind::Min | AggregateKind::Max, _) | (_, None) => {
+                aggregate.deparse_with_alias()
+            }
+            (_, Some(ch_type)) => format!(
+                "cast({} as Nullable({})) as {}",
+                aggregate.deparse(),
+                ch_type,
+                aggregate.alias
+            ),
+        }
+    }
+
+    // deparse a relation restriction of join, which has been checked to be
+    // pushable when planning
+    fn deparse_join_qual(qual: &Qual) -> String {
+        SqlDialect::ClickHouse
+            .deparse_qual(qual)
+            .expect("join restriction is pushable")
+    }
+
     fn deparse(
         &mut self,
         quals: &[Qual],
@@ -356,7 +763,7 @@ impl ClickHouseFdw {
             let mut replacement = |caps: &Captures| -> ClickHouseFdwResult<String> {
                 let param = &caps[1];
                 for qual in quals.iter() {
-                    if qual.field == param {
+                    if qual.field == param && qual.field_expr.is_none() {
                         params.push(qual.clone());
                         match &qual.value {
                             Value::Cell(cell) => return Ok(cell.to_string()),
@@ -378,7 +785,14 @@ impl ClickHouseFdw {
             self.table.clone()
         };
 
-        let tgts = if columns.is_empty() {
+        let tgts = if !self.aggregates.is_empty() || !self.group_by.is_empty() {
+            self.group_by
+                .iter()
+                .map(|c| c.name.clone())
+                .chain(self.aggregates.iter().map(Self::deparse_aggregate))
+                .collect::<Vec<String>>()
+                .join(", ")
+        } else if columns.is_empty() {
             "*".to_string()
         } else {
             columns
@@ -391,17 +805,31 @@ impl ClickHouseFdw {
 
         let mut sql = format!("select {} from {}", tgts, &table);
 
-        if !quals.is_empty() {
-            let cond = quals
+        let cond = quals
+            .iter()
+            .filter(|q| !self.params.iter().any(|p| p.field == q.field))
+            .filter_map(|q| SqlDialect::ClickHouse.deparse_qual(q))
+            .chain(
+                self.filters
+                    .iter()
+                    .filter_map(|f| SqlDialect::ClickHouse.deparse_filter(f))
+                    .map(|f| format!("({})", f)),
+            )
+            .collect::<Vec<String>>()
+            .join(" and ");
+        if !cond.is_empty() {
+            sql.push_str(&format!(" where {}", cond));
+        }
+
+        // push down group by
+        if !self.group_by.is_empty() {
+            let group_by = self
+                .group_by
                 .iter()
-                .filter(|q| !self.params.iter().any(|p| p.field == q.field))
-                .map(|q| q.deparse())
+                .map(|c| c.name.clone())
                 .collect::<Vec<String>>()
-                .join(" and ");
-
-            if !cond.is_empty() {
-                sql.push_str(&format!(" where {}", cond));
-            }
+                .join(", ");
+            sql.push_str(&format!(" group by {}", group_by));
         }
 
         // push down sorts
@@ -425,24 +853,75 @@ impl ClickHouseFdw {
 
         Ok(sql)
     }
+
+    fn fetch_scan_block(
+        &mut self,
+        quals: &[Qual],
+        columns: &[Column],
+        sorts: &[Sort],
+        limit: &Option<Limit>,
+        options: &HashMap<String, String>,
+    ) -> ClickHouseFdwResult<()> {
+        self.create_client()?;
+
+        self.table = require_option("table", options)?.to_string();
+        self.tgt_cols = columns.to_vec();
+        self.row_idx = 0;
+
+        let sql = self.deparse(quals, columns, sorts, limit)?;
+
+        self.query_scan_block(&sql)
+    }
+
+    fn query_scan_block(&mut self, sql: &str) -> ClickHouseFdwResult<()> {
+        if let Some(ref mut client) = self.client {
+            // for simplicity purpose, we fetch whole query result to local,
+            // may need optimization in the future.
+            let block = block_on_interruptible(&self.rt, client.query(sql).fetch_all())?;
+            stats::inc_stats(
+                Self::FDW_NAME,
+                stats::Metric::RowsIn,
+                block.row_count() as i64,
+            );
+            stats::inc_stats(
+                Self::FDW_NAME,
+                stats::Metric::RowsOut,
+                block.row_count() as i64,
+            );
+            self.scan_blk = Some(block);
+            self.remote_sql = Some(sql.to_owned());
+        }
+
+        Ok(())
+    }
 }
 
 impl ForeignDataWrapper<ClickHouseFdwError> for ClickHouseFdw {
     fn new(server: ForeignServer) -> ClickHouseFdwResult<Self> {
-        let rt = create_async_runtime()?;
-        let conn_str = match server.options.get("conn_string") {
+        // credentials can be from user mapping or server options
+        let cred_opts = server.credential_options(&["conn_string", "conn_string_id"]);
+        let conn_str = match cred_opts.get("conn_string") {
             Some(conn_str) => conn_str.to_owned(),
             None => {
-                let conn_str_id = require_option("conn_string_id", &server.options)?;
+                let conn_str_id = require_option("conn_string_id", cred_opts)?;
                 get_vault_secret(conn_str_id).unwrap_or_default()
             }
         };
 
+        // runtime and connection pool are cached and reused across statements,
+        // because the pooled connections are bound to the runtime
+        let (rt, pool) = server.get_or_create_cached(&conn_str, || -> ClickHouseFdwResult<_> {
+            Ok((
+                Arc::new(create_async_runtime()?),
+                Pool::new(conn_str.as_str()),
+            ))
+        })?;
+
         stats::inc_stats(Self::FDW_NAME, stats::Metric::CreateTimes, 1);
 
         Ok(Self {
             rt,
-            conn_str,
+            pool,
             client: None,
             table: String::default(),
             rowid_col: String::default(),
@@ -450,9 +929,59 @@ impl ForeignDataWrapper<ClickHouseFdwError> for ClickHouseFdw {
             scan_blk: None,
             row_idx: 0,
             params: Vec::new(),
+            group_by: Vec::new(),
+            aggregates: Vec::new(),
+            filters: Vec::new(),
+            remote_sql: None,
+            pending_writes: None,
         })
     }
 
+    fn field_expr_allowlist() -> &'static [&'static str] {
+        // functions behave the same on ClickHouse, string functions like
+        // lower() are not included because they are not UTF-8 aware
+        &["abs", "ceil", "floor"]
+    }
+
+    fn get_filter_pushdown(
+        &mut self,
+        filters: &[Filter],
+        options: &HashMap<String, String>,
+    ) -> ClickHouseFdwResult<bool> {
+        // parametrized view's parameters cannot be in filters
+        let table = require_option("table", options)?;
+        if table.starts_with('(')
+            || !filters
+                .iter()
+                .all(|f| SqlDialect::ClickHouse.is_filter_pushable(f))
+        {
+            return Ok(false);
+        }
+        self.filters = filters.to_vec();
+        Ok(true)
+    }
+
+    fn get_scan_pushdown(
+        &mut self,
+        quals: &[Qual],
+        sorts: &[Sort],
+        _limit: &Option<Limit>,
+        _options: &HashMap<String, String>,
+    ) -> ClickHouseFdwResult<Option<Pushdown>> {
+        // quals whose operators can be translated, sorts and limit are
+        // deparsed into the remote query, but the remote server sorts strings
+        // by bytes so only the sorts which don't depend on the locale are
+        // pushed down, and limit must be applied after sorting
+        let is_sorted = sorts.iter().all(Sort::is_locale_independent);
+        Ok(Some(Pushdown {
+            quals: (0..quals.len())
+                .filter(|i| SqlDialect::ClickHouse.is_pushable(&quals[*i]))
+                .collect(),
+            sorts: is_sorted,
+            limit: is_sorted,
+        }))
+    }
+
     fn begin_scan(
         &mut self,
         quals: &[Qual],
@@ -461,32 +990,80 @@ impl ForeignDataWrapper<ClickHouseFdwError> for ClickHouseFdw {
         limit: &Option<Limit>,
         options: &HashMap<String, String>,
     ) -> ClickHouseFdwResult<()> {
+        self.group_by.clear();
+        self.aggregates.clear();
+        self.fetch_scan_block(quals, columns, sorts, limit, options)
+    }
+
+    fn get_aggregate_pushdown(
+        &mut self,
+        quals: &[Qual],
+        _group_by: &[Column],
+        _aggregates: &[Aggregate],
+        options: &HashMap<String, String>,
+    ) -> ClickHouseFdwResult<bool> {
+        // subquery is not aggregated, because parametrized view's parameters
+        // are also returned as columns
+        let table = require_option("table", options)?;
+        Ok(!table.starts_with('(') && quals.iter().all(|q| SqlDialect::ClickHouse.is_pushable(q)))
+    }
+
+    fn begin_aggregate_scan(
+        &mut self,
+        quals: &[Qual],
+        group_by: &[Column],
+        aggregates: &[Aggregate],
+        options: &HashMap<String, String>,
+    ) -> ClickHouseFdwResult<()> {
+        self.group_by = group_by.to_vec();
+        self.aggregates = aggregates.to_vec();
+
+        // output columns are group by columns followed by aggregates
+        let columns = group_by
+            .iter()
+            .cloned()
+            .chain(aggregates.iter().map(|agg| agg.as_column()))
+            .collect::<Vec<_>>();
+
+        self.fetch_scan_block(quals, &columns, &[], &None, options)
+    }
+
+    fn get_join_pushdown(&mut self, join: &Join) -> ClickHouseFdwResult<bool> {
+        // subquery is not joined, same as aggregate pushdown, and only equi-join
+        // is supported by all ClickHouse versions
+        let outer_table = require_option("table", &join.outer.options)?;
+        let inner_table = require_option("table", &join.inner.options)?;
+        Ok(!outer_table.starts_with('(')
+            && !inner_table.starts_with('(')
+            && join.quals.iter().all(|q| q.operator == "=")
+            && SqlDialect::ClickHouse.is_join_pushable(join))
+    }
+
+    fn begin_join_scan(&mut self, join: &Join) -> ClickHouseFdwResult<()> {
+        self.group_by.clear();
+        self.aggregates.clear();
+        self.params.clear();
+
         self.create_client()?;
 
-        self.table = require_option("table", options)?.to_string();
-        self.tgt_cols = columns.to_vec();
+        self.tgt_cols = join.output_columns();
         self.row_idx = 0;
 
-        let sql = self.deparse(quals, columns, sorts, limit)?;
-
-        if let Some(ref mut client) = self.client {
-            // for simplicity purpose, we fetch whole query result to local,
-            // may need optimization in the future.
-            let block = self.rt.block_on(client.query(&sql).fetch_all())?;
-            stats::inc_stats(
-                Self::FDW_NAME,
-                stats::Metric::RowsIn,
-                block.row_count() as i64,
-            );
-            stats::inc_stats(
-                Self::FDW_NAME,
-                stats::Metric::RowsOut,
-                block.row_count() as i64,
-            );
-            self.scan_blk = Some(block);
+        let outer_table = require_option("table", &join.outer.options)?;
+        let inner_table = require_option("table", &join.inner.options)?;
+        let mut sql = format!(
+            "select {} from {}",
+            join.deparse_columns(),
+            join.deparse_from(outer_table, inner_table, Self::deparse_join_qual)
+        );
+        if let Some(cond) = join.deparse_where(Self::deparse_join_qual) {
+            sql.push_str(&format!(" where {}", cond));
         }
 
-        Ok(())
+        // fill non-matched columns with NULL in outer join, same as Postgres
+        sql.push_str(" settings join_use_nulls = 1");
+
+        self.query_scan_block(&sql)
     }
 
     fn iter_scan(&mut self, row: &mut Row) -> ClickHouseFdwResult<Option<()>> {
@@ -508,7 +1085,7 @@ impl ForeignDataWrapper<ClickHouseFdwError> for ClickHouseFdw {
                         .enumerate()
                         .find(|(_, c)| c.name() == tgt_col.name)
                     {
-                        field_to_cell(&src_row, i)?
+                        field_to_cell(&src_row, i, tgt_col)?
                     } else {
                         None
                     };
@@ -526,252 +1103,111 @@ impl ForeignDataWrapper<ClickHouseFdwError> for ClickHouseFdw {
         Ok(())
     }
 
+    fn explain_scan(
+        &mut self,
+        quals: &[Qual],
+        columns: &[Column],
+        sorts: &[Sort],
+        limit: &Option<Limit>,
+        options: &HashMap<String, String>,
+        explain: &ExplainOptions,
+    ) -> ClickHouseFdwResult<Vec<ExplainProperty>> {
+        if !explain.verbose {
+            return Ok(Vec::new());
+        }
+        // use the query already sent if the scan is executed
+        let sql = match &self.remote_sql {
+            Some(sql) => sql.clone(),
+            None => {
+                self.table = require_option("table", options)?.to_string();
+                self.deparse(quals, columns, sorts, limit)?
+            }
+        };
+        Ok(vec![ExplainProperty::Text("Remote SQL".to_string(), sql)])
+    }
+
+    fn is_analyze_supported() -> bool {
+        true
+    }
+
+    fn analyze(
+        &mut self,
+        columns: &[Column],
+        sample_size: usize,
+        options: &HashMap<String, String>,
+    ) -> ClickHouseFdwResult<Option<(i64, Vec<Row>)>> {
+        // parametrized view cannot be analyzed as its parameters are unknown
+        let table = require_option("table", options)?;
+        if table.starts_with('(') {
+            return Ok(None);
+        }
+
+        self.group_by.clear();
+        self.aggregates.clear();
+        self.params.clear();
+
+        self.create_client()?;
+
+        self.table = table.to_string();
+        self.tgt_cols = columns.to_vec();
+        self.row_idx = 0;
+
+        // get total row count
+        let mut total_rows = 0;
+        if let Some(ref mut client) = self.client {
+            let sql = format!("select count() from {}", self.table);
+            let block = block_on_interruptible(&self.rt, client.query(sql).fetch_all())?;
+            total_rows = block.get::<u64, _>(0, 0)? as i64;
+        }
+
+        // fetch random sample rows, instead of sorting the whole table by
+        // rand(), a limited number of rows are read and each of them is
+        // picked with the same probability
+        let max_read_rows = sample_size.saturating_mul(Self::ANALYZE_MAX_SCAN_RATIO);
+        let read_rows = (total_rows.max(0) as usize).min(max_read_rows);
+        let mut sql = self.deparse(&[], columns, &[], &None)?;
+        if read_rows > sample_size {
+            // rand() returns a random UInt32
+            let threshold = (sample_size as f64 / read_rows as f64 * u32::MAX as f64).ceil();
+            sql.push_str(&format!(" where rand() < {}", threshold as u64));
+        }
+        sql.push_str(&format!(
+            " limit {} settings max_rows_to_read = {}, read_overflow_mode = 'break'",
+            sample_size, max_read_rows
+        ));
+        self.query_scan_block(&sql)?;
+
+        let mut sample = Vec::new();
+        let mut row = Row::new();
+        while self.iter_scan(&mut row)?.is_some() {
+            sample.push(row.clone());
+            row.clear();
+        }
+        self.scan_blk.take();
+
+        Ok(Some((total_rows, sample)))
+    }
+
     fn begin_modify(&mut self, options: &HashMap<String, String>) -> ClickHouseFdwResult<()> {
         self.create_client()?;
 
         self.table = require_option("table", options)?.to_string();
         self.rowid_col = require_option("rowid_column", options)?.to_string();
+        self.pending_writes =
+            (require_option_or("defer_writes", options, "false") == "true").then(Vec::new);
         Ok(())
     }
 
     fn insert(&mut self, src: &Row) -> ClickHouseFdwResult<()> {
-        if let Some(ref mut client) = self.client {
-            // use a dummy query to probe column types
-            let sql = format!("select * from {} where false", self.table);
-            let probe = self.rt.block_on(client.query(&sql).fetch_all())?;
-
-            // add row to block
-            let mut row = Vec::new();
-            for (col_name, cell) in src.iter() {
-                let col_name = col_name.to_owned();
-                let tgt_col = probe.get_column(col_name.as_ref())?;
-                let tgt_type = tgt_col.sql_type();
-                let is_nullable = matches!(tgt_type, SqlType::Nullable(_));
-
-                let value = cell
-                    .as_ref()
-                    .map(|c| match c {
-                        Cell::Bool(v) => {
-                            let val = if is_nullable {
-                                ChValue::from(Some(*v))
-                            } else {
-                                ChValue::from(*v)
-                            };
-                            Ok(val)
-                        }
-                        Cell::I8(v) => {
-                            let val = if is_nullable {
-                                ChValue::from(Some(*v))
-                            } else {
-                                ChValue::from(*v)
-                            };
-                            Ok(val)
-                        }
-                        Cell::I16(v) => match tgt_col.sql_type() {
-                            // i16 can be converted to 2 ClickHouse types: Int16 and UInt8
-                            SqlType::Int16 | SqlType::Nullable(SqlType::Int16) => {
-                                let val = if is_nullable {
-                                    ChValue::from(Some(*v))
-                                } else {
-                                    ChValue::from(*v)
-                                };
-                                Ok(val)
-                            }
-                            SqlType::UInt8 | SqlType::Nullable(SqlType::UInt8) => {
-                                let val = if is_nullable {
-                                    ChValue::from(Some(*v as u8))
-                                } else {
-                                    ChValue::from(*v as u8)
-                                };
-                                Ok(val)
-                            }
-                            _ => Err(ClickHouseFdwError::UnsupportedColumnType(
-                                tgt_type.to_string().into(),
-                            )),
-                        },
-                        Cell::F32(v) => {
-                            let val = if is_nullable {
-                                ChValue::from(Some(*v))
-                            } else {
-                                ChValue::from(*v)
-                            };
-                            Ok(val)
-                        }
-                        Cell::I32(v) => match tgt_col.sql_type() {
-                            // i32 can be converted to 2 ClickHouse types: Int32 and UInt16
-                            SqlType::Int32 | SqlType::Nullable(SqlType::Int32) => {
-                                let val = if is_nullable {
-                                    ChValue::from(Some(*v))
-                                } else {
-                                    ChValue::from(*v)
-                                };
-                                Ok(val)
-                            }
-                            SqlType::UInt16 | SqlType::Nullable(SqlType::UInt16) => {
-                                let val = if is_nullable {
-                                    ChValue::from(Some(*v as u16))
-                                } else {
-                                    ChValue::from(*v as u16)
-                                };
-                                Ok(val)
-                            }
-                            _ => Err(ClickHouseFdwError::UnsupportedColumnType(
-                                tgt_type.to_string().into(),
-                            )),
-                        },
-                        Cell::F64(v) => {
-                            let val = if is_nullable {
-                                ChValue::from(Some(*v))
-                            } else {
-                                ChValue::from(*v)
-                            };
-                            Ok(val)
-                        }
-                        Cell::I64(v) => match tgt_col.sql_type() {
-                            // i64 can be converted to 2 ClickHouse types: Int64 and UInt32
-                            SqlType::Int64 | SqlType::Nullable(SqlType::Int64) => {
-                                let val = if is_nullable {
-                                    ChValue::from(Some(*v))
-                                } else {
-                                    ChValue::from(*v)
-                                };
-                                Ok(val)
-                            }
-                            SqlType::UInt32 | SqlType::Nullable(SqlType::UInt32) => {
-                                let val = if is_nullable {
-                                    ChValue::from(Some(*v as u32))
-                                } else {
-                                    ChValue::from(*v as u32)
-                                };
-                                Ok(val)
-                            }
-                            _ => Err(ClickHouseFdwError::UnsupportedColumnType(
-                                tgt_type.to_string().into(),
-                            )),
-                        },
-                        Cell::Numeric(v) => {
-                            let v = types::Decimal::from_str(v.normalize())?;
-                            let val = if is_nullable {
-                                ChValue::from(Some(v))
-                            } else {
-                                ChValue::from(v)
-                            };
-                            Ok(val)
-                        }
-                        Cell::String(v) => {
-                            let s = v.as_str();
-
-                            // i256 and u256 are saved as string in Postgres, so we parse it
-                            // back to ClickHouse if target column is Int256 or UInt256
-                            let val = match tgt_col.sql_type() {
-                                SqlType::Int256 | SqlType::Nullable(SqlType::Int256) => {
-                                    let v = i256::from_str(s)?;
-                                    if is_nullable {
-                                        ChValue::from(Some(v))
-                                    } else {
-                                        ChValue::from(v)
-                                    }
-                                }
-                                SqlType::UInt256 | SqlType::Nullable(SqlType::UInt256) => {
-                                    let v = u256::from_str(s)?;
-                                    if is_nullable {
-                                        ChValue::from(Some(v))
-                                    } else {
-                                        ChValue::from(v)
-                                    }
-                                }
-                                _ => {
-                                    // other than i256 and u256, convert it to string as normal
-                                    if is_nullable {
-                                        ChValue::from(Some(s))
-                                    } else {
-                                        ChValue::from(s)
-                                    }
-                                }
-                            };
-                            Ok(val)
-                        }
-                        Cell::Date(_) => {
-                            let s = c.to_string().replace('\'', "");
-                            let tm = NaiveDate::parse_from_str(&s, "%Y-%m-%d")?;
-                            let val = if is_nullable {
-                                ChValue::from(Some(tm))
-                            } else {
-                                let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
-                                let duration = tm - epoch;
-                                let dt = duration.num_days() as u16;
-                                ChValue::Date(dt)
-                            };
-                            Ok(val)
-                        }
-                        Cell::Timestamp(_) => {
-                            let s = c.to_string().replace('\'', "");
-                            let naive_tm = NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S")
-                                .or_else(|_| {
-                                NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S%.6f")
-                            })?;
-                            let tm: DateTime<Utc> =
-                                DateTime::from_naive_utc_and_offset(naive_tm, Utc);
-                            let val = if is_nullable {
-                                ChValue::Nullable(either::Either::Right(Box::new(tm.into())))
-                            } else {
-                                ChValue::from(tm)
-                            };
-                            Ok(val)
-                        }
-                        Cell::Uuid(v) => {
-                            let uuid = Uuid::try_parse(&v.to_string())?;
-                            let val = if is_nullable {
-                                ChValue::Nullable(either::Either::Right(Box::new(ChValue::Uuid(
-                                    *uuid.as_bytes(),
-                                ))))
-                            } else {
-                                ChValue::from(uuid)
-                            };
-                            Ok(val)
-                        }
-                        Cell::BoolArray(v) => {
-                            array_cell_to_clickhouse_value(v, &SqlType::Bool, is_nullable)
-                        }
-                        Cell::I16Array(v) => {
-                            array_cell_to_clickhouse_value(v, &SqlType::Int16, is_nullable)
-                        }
-                        Cell::I32Array(v) => {
-                            array_cell_to_clickhouse_value(v, &SqlType::Int32, is_nullable)
-                        }
-                        Cell::I64Array(v) => {
-                            array_cell_to_clickhouse_value(v, &SqlType::Int64, is_nullable)
-                        }
-                        Cell::F32Array(v) => {
-                            array_cell_to_clickhouse_value(v, &SqlType::Float32, is_nullable)
-                        }
-                        Cell::F64Array(v) => {
-                            array_cell_to_clickhouse_value(v, &SqlType::Float64, is_nullable)
-                        }
-                        Cell::StringArray(v) => {
-                            array_cell_to_clickhouse_value(v, &SqlType::String, is_nullable)
-                        }
-                        _ => Err(ClickHouseFdwError::UnsupportedColumnType(
-                            tgt_type.to_string().into(),
-                        )),
-                    })
-                    .transpose()?;
-
-                if let Some(v) = value {
-                    row.push((col_name, v));
-                }
-            }
-            let mut block = Block::new();
-            block.push(row)?;
+        self.insert_batch(std::slice::from_ref(src))
+    }
 
-            // execute query on ClickHouse
-            self.rt.block_on(client.insert(&self.table, block))?;
-        }
-        Ok(())
+    fn insert_batch(&mut self, rows: &[Row]) -> ClickHouseFdwResult<()> {
+        self.write(PendingWrite::Insert(rows.to_vec()))
     }
 
     fn update(&mut self, rowid: &Cell, new_row: &Row) -> ClickHouseFdwResult<()> {
-        if let Some(ref mut client) = self.client {
+        if self.client.is_some() {
             let mut sets = Vec::new();
             for (col, cell) in new_row.iter() {
                 if col == &self.rowid_col {
@@ -793,23 +1229,94 @@ impl ForeignDataWrapper<ClickHouseFdwError> for ClickHouseFdw {
                 self.rowid_col,
                 rowid
             );
-
-            // execute query on ClickHouse
-            self.rt.block_on(client.execute(&sql))?;
+            self.write(PendingWrite::Execute(sql))?;
         }
         Ok(())
     }
 
     fn delete(&mut self, rowid: &Cell) -> ClickHouseFdwResult<()> {
-        if let Some(ref mut client) = self.client {
+        if self.client.is_some() {
             let sql = format!(
                 "alter table {} delete where {} = {}",
                 self.table, self.rowid_col, rowid
             );
+            self.write(PendingWrite::Execute(sql))?;
+        }
+        Ok(())
+    }
+
+    fn truncate(
+        &mut self,
+        tables: &[HashMap<String, String>],
+        _restart_seqs: bool,
+        _cascade: bool,
+    ) -> ClickHouseFdwResult<()> {
+        let tables = tables
+            .iter()
+            .map(|options| {
+                let table = require_option("table", options)?;
+                if table.starts_with('(') {
+                    return Err(ClickHouseFdwError::TruncateSubquery(table.to_string()));
+                }
+                Ok(table.to_string())
+            })
+            .collect::<ClickHouseFdwResult<Vec<_>>>()?;
 
-            // execute query on ClickHouse
-            self.rt.block_on(client.execute(&sql))?;
+        self.create_client()?;
+        if let Some(ref mut client) = self.client {
+            for table in tables {
+                let sql = format!("truncate table {}", table);
+
+                // execute query on ClickHouse
+                block_on_interruptible(&self.rt, client.execute(&sql))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn has_xact_hooks(&self) -> bool {
+        self.pending_writes.is_some()
+    }
+
+    fn on_xact_commit(&mut self) -> ClickHouseFdwResult<()> {
+        let pending_writes = self.pending_writes.take().unwrap_or_default();
+        for (_, write) in pending_writes {
+            self.execute_write(write)?;
         }
         Ok(())
     }
+
+    fn on_xact_abort(&mut self) -> ClickHouseFdwResult<()> {
+        self.pending_writes.take();
+        Ok(())
+    }
+
+    fn on_subxact_abort(&mut self, subxact_id: u32) -> ClickHouseFdwResult<()> {
+        // discard the writes made in the subtransaction and its children,
+        // whose ids are always greater
+        if let Some(ref mut pending_writes) = self.pending_writes {
+            pending_writes.retain(|(id, _)| *id < subxact_id);
+        }
+        Ok(())
+    }
+
+    fn option_defs() -> Vec<OptionDef> {
+        const CRED: &[OptionContext] = &[OptionContext::Server, OptionContext::UserMapping];
+        vec![
+            OptionDef::new("conn_string", CRED)
+                .secret()
+                .description("ClickHouse connection string"),
+            OptionDef::new("conn_string_id", CRED)
+                .description("Vault secret ID of the connection string"),
+            OptionDef::new("table", &[OptionContext::Table])
+                .required()
+                .description("Source table name, subquery or parametrized view"),
+            OptionDef::new("rowid_column", &[OptionContext::Table])
+                .description("Primary key column name, required for data modify"),
+            OptionDef::new("defer_writes", &[OptionContext::Table])
+                .opt_type(OptionType::Bool)
+                .default("false")
+                .description("Whether to send the writes when the local transaction commits"),
+        ]
+    }
 }
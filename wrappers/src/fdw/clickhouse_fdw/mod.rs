@@ -17,6 +17,9 @@ enum ClickHouseFdwError {
     #[error("unmatched query parameter: {0}")]
     UnmatchedParameter(String),
 
+    #[error("cannot truncate subquery table '{0}'")]
+    TruncateSubquery(String),
+
     #[error("column data type '{0}' is not supported")]
     UnsupportedColumnType(String),
 
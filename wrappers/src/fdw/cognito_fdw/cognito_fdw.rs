@@ -33,13 +33,18 @@ impl ForeignDataWrapper<CognitoFdwError> for CognitoFdw {
         let user_pool_id = require_option("user_pool_id", &server.options)?.to_string();
         let aws_region = require_option("region", &server.options)?.to_string();
 
-        let aws_access_key_id = require_option("aws_access_key_id", &server.options)?.to_string();
+        // credentials can be from user mapping or server options
+        let cred_opts = server.credential_options(&[
+            "aws_access_key_id",
+            "aws_secret_access_key",
+            "api_key_id",
+        ]);
+        let aws_access_key_id = require_option("aws_access_key_id", cred_opts)?.to_string();
         let aws_secret_access_key =
-            if let Some(aws_secret_access_key) = server.options.get("aws_secret_access_key") {
+            if let Some(aws_secret_access_key) = cred_opts.get("aws_secret_access_key") {
                 aws_secret_access_key.clone()
             } else {
-                let aws_secret_access_key = server
-                    .options
+                let aws_secret_access_key = cred_opts
                     .get("api_key_id")
                     .expect("`api_key_id` must be set if `aws_secret_access_key` is not");
                 get_vault_secret(aws_secret_access_key).ok_or(CognitoFdwError::SecretNotFound(
@@ -48,7 +53,7 @@ impl ForeignDataWrapper<CognitoFdwError> for CognitoFdw {
             };
 
         let rt = create_async_runtime()?;
-        let client = rt.block_on(async {
+        let client = block_on_interruptible(&rt, async {
             env::set_var("AWS_ACCESS_KEY_ID", aws_access_key_id);
             env::set_var("AWS_SECRET_ACCESS_KEY", aws_secret_access_key);
             env::set_var("AWS_REGION", aws_region);
@@ -135,25 +140,42 @@ impl ForeignDataWrapper<CognitoFdwError> for CognitoFdw {
         catalog: Option<pg_sys::Oid>,
     ) -> CognitoFdwResult<()> {
         if let Some(oid) = catalog {
-            if oid == FOREIGN_SERVER_RELATION_ID {
-                let access_key_exists =
-                    check_options_contain(&options, "aws_access_key_id").is_ok();
+            // credentials can be set in either server or user mapping
+            if oid == FOREIGN_SERVER_RELATION_ID || oid == USER_MAPPING_RELATION_ID {
                 let secret_access_key_exists =
                     check_options_contain(&options, "aws_secret_access_key").is_ok();
                 let api_key_id_exists = check_options_contain(&options, "api_key_id").is_ok();
-                if !access_key_exists && !secret_access_key_exists {
-                    return Err(CognitoFdwError::ApiKeyAndSecretKeySet);
-                }
-                if (api_key_id_exists && secret_access_key_exists)
-                    || (!api_key_id_exists && !secret_access_key_exists)
-                {
+                if api_key_id_exists && secret_access_key_exists {
                     return Err(CognitoFdwError::SetOneOfSecretKeyAndApiKeyIdSet);
                 }
-            } else if oid == FOREIGN_TABLE_RELATION_ID {
-                check_options_contain(&options, "object")?;
             }
         }
 
         Ok(())
     }
+
+    fn option_defs() -> Vec<OptionDef> {
+        const CRED: &[OptionContext] = &[OptionContext::Server, OptionContext::UserMapping];
+        vec![
+            OptionDef::new("user_pool_id", &[OptionContext::Server])
+                .required()
+                .description("Cognito user pool ID"),
+            OptionDef::new("region", &[OptionContext::Server])
+                .required()
+                .description("AWS region of the user pool"),
+            OptionDef::new("endpoint_url", &[OptionContext::Server])
+                .description("URL of Cognito-compatible service"),
+            OptionDef::new("aws_access_key_id", CRED)
+                .secret()
+                .description("AWS access key ID"),
+            OptionDef::new("aws_secret_access_key", CRED)
+                .secret()
+                .description("AWS secret access key"),
+            OptionDef::new("api_key_id", CRED)
+                .description("Vault secret ID of the AWS secret access key"),
+            OptionDef::new("object", &[OptionContext::Table])
+                .required()
+                .description("Cognito object name, only `users` is supported"),
+        ]
+    }
 }
@@ -41,10 +41,7 @@ enum CognitoFdwError {
     #[error("no secret found in vault with id {0}")]
     SecretNotFound(String),
 
-    #[error("both `api_key` and `api_secret_key` options must be set")]
-    ApiKeyAndSecretKeySet,
-
-    #[error("exactly one of `aws_secret_access_key` or `api_key_id` options must be set")]
+    #[error("only one of `aws_secret_access_key` or `api_key_id` options can be set")]
     SetOneOfSecretKeyAndApiKeyIdSet,
 }
 
@@ -1,5 +1,5 @@
 use crate::stats;
-use pgrx::{pg_sys, prelude::*, JsonB};
+use pgrx::{prelude::*, JsonB};
 use regex::Regex;
 use reqwest::{self, header};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
@@ -16,7 +16,7 @@ use super::{FirebaseFdwError, FirebaseFdwResult};
 
 fn get_oauth2_token(sa_key: &str, rt: &Runtime) -> FirebaseFdwResult<AccessToken> {
     let creds = yup_oauth2::parse_service_account_key(sa_key.as_bytes())?;
-    let sa = rt.block_on(ServiceAccountAuthenticator::builder(creds).build())?;
+    let sa = block_on_interruptible(rt, ServiceAccountAuthenticator::builder(creds).build())?;
     let scopes = &[
         "https://www.googleapis.com/auth/cloud-platform",
         "https://www.googleapis.com/auth/firebase.database",
@@ -24,7 +24,7 @@ fn get_oauth2_token(sa_key: &str, rt: &Runtime) -> FirebaseFdwResult<AccessToken
         "https://www.googleapis.com/auth/identitytoolkit",
         "https://www.googleapis.com/auth/userinfo.email",
     ];
-    Ok(rt.block_on(sa.token(scopes))?)
+    Ok(block_on_interruptible(rt, sa.token(scopes))?)
 }
 
 fn body_to_rows(
@@ -139,6 +139,8 @@ pub(crate) struct FirebaseFdw {
     project_id: String,
     client: Option<ClientWithMiddleware>,
     scan_result: Vec<Row>,
+    pages_fetched: i64,
+    bytes_in: i64,
 }
 
 impl FirebaseFdw {
@@ -217,17 +219,22 @@ impl ForeignDataWrapper<FirebaseFdwError> for FirebaseFdw {
             project_id: require_option("project_id", &server.options)?.to_string(),
             client: None,
             scan_result: Vec::default(),
+            pages_fetched: 0,
+            bytes_in: 0,
         };
 
+        // credentials can be from user mapping or server options
+        let cred_opts = server.credential_options(&["access_token", "sa_key", "sa_key_id"]);
+
         // get oauth2 access token if it is directly defined in options
-        let token = if let Some(access_token) = server.options.get("access_token") {
+        let token = if let Some(access_token) = cred_opts.get("access_token") {
             access_token.to_owned()
         } else {
             // otherwise, get it from the options or Vault
-            let sa_key = match server.options.get("sa_key") {
+            let sa_key = match cred_opts.get("sa_key") {
                 Some(sa_key) => sa_key.to_owned(),
                 None => {
-                    let sa_key_id = require_option("sa_key_id", &server.options)?;
+                    let sa_key_id = require_option("sa_key_id", cred_opts)?;
                     match get_vault_secret(sa_key_id) {
                         Some(sa_key) => sa_key,
                         None => return Ok(ret),
@@ -278,6 +285,8 @@ impl ForeignDataWrapper<FirebaseFdwError> for FirebaseFdw {
             .unwrap_or(Self::DEFAULT_ROWS_LIMIT);
 
         self.scan_result = Vec::new();
+        self.pages_fetched = 0;
+        self.bytes_in = 0;
 
         if let Some(client) = &self.client {
             let mut next_page: Option<String> = None;
@@ -286,17 +295,19 @@ impl ForeignDataWrapper<FirebaseFdwError> for FirebaseFdw {
             loop {
                 let url = self.build_url(obj, &next_page, options);
 
-                let body = self.rt.block_on(client.get(&url).send()).and_then(|resp| {
-                    stats::inc_stats(
-                        Self::FDW_NAME,
-                        stats::Metric::BytesIn,
-                        resp.content_length().unwrap_or(0) as i64,
-                    );
+                let mut bytes_in = 0;
+                let body =
+                    block_on_interruptible(&self.rt, client.get(&url).send()).and_then(|resp| {
+                        bytes_in = resp.content_length().unwrap_or(0) as i64;
+                        stats::inc_stats(Self::FDW_NAME, stats::Metric::BytesIn, bytes_in);
 
-                    resp.error_for_status()
-                        .and_then(|resp| self.rt.block_on(resp.text()))
-                        .map_err(reqwest_middleware::Error::from)
-                })?;
+                        resp.error_for_status()
+                            .and_then(|resp| block_on_interruptible(&self.rt, resp.text()))
+                            .map_err(reqwest_middleware::Error::from)
+                    })?;
+
+                self.pages_fetched += 1;
+                self.bytes_in += bytes_in;
 
                 let json: JsonValue = serde_json::from_str(&body)?;
                 let mut rows = resp_to_rows(obj, &json, columns)?;
@@ -340,16 +351,57 @@ impl ForeignDataWrapper<FirebaseFdwError> for FirebaseFdw {
         Ok(())
     }
 
-    fn validator(
-        options: Vec<Option<String>>,
-        catalog: Option<pg_sys::Oid>,
-    ) -> FirebaseFdwResult<()> {
-        if let Some(oid) = catalog {
-            if oid == FOREIGN_TABLE_RELATION_ID {
-                check_options_contain(&options, "object")?;
-            }
+    fn explain_scan(
+        &mut self,
+        _quals: &[Qual],
+        _columns: &[Column],
+        _sorts: &[Sort],
+        _limit: &Option<Limit>,
+        options: &HashMap<String, String>,
+        explain: &ExplainOptions,
+    ) -> FirebaseFdwResult<Vec<ExplainProperty>> {
+        let mut props = Vec::new();
+        if explain.verbose {
+            let obj = require_option("object", options)?;
+            let url = self.build_url(obj, &None, options);
+            props.push(ExplainProperty::Text("Remote URL".to_string(), url));
         }
+        if explain.analyze {
+            props.push(ExplainProperty::Integer(
+                "Pages Fetched".to_string(),
+                self.pages_fetched,
+            ));
+            props.push(ExplainProperty::Integer(
+                "Bytes In".to_string(),
+                self.bytes_in,
+            ));
+        }
+        Ok(props)
+    }
 
-        Ok(())
+    fn option_defs() -> Vec<OptionDef> {
+        const CRED: &[OptionContext] = &[OptionContext::Server, OptionContext::UserMapping];
+        vec![
+            OptionDef::new("project_id", &[OptionContext::Server])
+                .required()
+                .description("Firebase project ID"),
+            OptionDef::new("access_token", CRED)
+                .secret()
+                .description("OAuth2 access token"),
+            OptionDef::new("sa_key", CRED)
+                .secret()
+                .description("Service account key in JSON format"),
+            OptionDef::new("sa_key_id", CRED)
+                .description("Vault secret ID of the service account key"),
+            OptionDef::new("object", &[OptionContext::Table])
+                .required()
+                .description("`auth/users` or `firestore/<collection_id>`"),
+            OptionDef::new("base_url", &[OptionContext::Table])
+                .description("Base URL of Firebase API, defaults to Google's API of the object"),
+            OptionDef::new("limit", &[OptionContext::Table])
+                .opt_type(OptionType::Integer)
+                .default("10000")
+                .description("Maximum number of rows to fetch"),
+        ]
     }
 }
@@ -92,4 +92,14 @@ impl ForeignDataWrapper<HelloWorldFdwError> for HelloWorldFdw {
         // we do nothing here, but you can do things like resource cleanup and etc.
         Ok(())
     }
+
+    // Define the options supported by this FDW and where they can be used, any
+    // other option is rejected when creating the server or foreign table. The
+    // definitions can be listed by `select * from hello_world_fdw_options()`.
+    fn option_defs() -> Vec<OptionDef> {
+        vec![
+            OptionDef::new("foo", &[OptionContext::Server, OptionContext::Table])
+                .description("Example option, not used"),
+        ]
+    }
 }
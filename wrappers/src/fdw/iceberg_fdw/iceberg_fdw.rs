@@ -1,8 +1,8 @@
 use arrow_array::RecordBatch;
-use futures::StreamExt;
+use futures::{stream, StreamExt, TryStreamExt};
 use iceberg::{
     expr::Predicate,
-    scan::ArrowRecordBatchStream,
+    scan::{ArrowRecordBatchStream, FileScanTask},
     spec::{PrimitiveType, Type},
     table::Table,
     Catalog, NamespaceIdent, TableIdent,
@@ -33,15 +33,60 @@ pub(crate) struct IcebergFdw {
     batch: Option<RecordBatch>,
     mapper: Mapper,
     rec_offset: usize,
+
+    // parallel scan, the data files planned by the leader are claimed one by one
+    parallel_scan: Option<ParallelScan>,
+    tasks: Vec<FileScanTask>,
 }
 
 impl IcebergFdw {
     const FDW_NAME: &'static str = "IcebergFdw";
 
+    // load table specified in table options
+    fn load_table(&self, options: &HashMap<String, String>) -> IcebergFdwResult<Table> {
+        let tbl_ident = TableIdent::from_strs(require_option("table", options)?.split("."))?;
+        let table = block_on_interruptible(&self.rt, self.catalog.load_table(&tbl_ident))?;
+        Ok(table)
+    }
+
+    // claim next data file in parallel scan and open record stream for it
+    // Returns:
+    //   Some - a data file is claimed
+    //   None - all data files have been claimed
+    fn open_next_task(&mut self) -> IcebergFdwResult<Option<()>> {
+        let (Some(scan), Some(table)) = (&self.parallel_scan, &self.table) else {
+            return Ok(None);
+        };
+        let Some(idx) = scan.claim_unit(self.tasks.len()) else {
+            return Ok(None);
+        };
+
+        let tasks = stream::iter([Ok(self.tasks[idx].clone())]).boxed();
+        let _guard = self.rt.enter();
+        self.stream = table.reader_builder().build().read(tasks)?.into();
+        self.batch = None;
+        self.mapper.reset();
+        self.rec_offset = 0;
+
+        Ok(Some(()))
+    }
+
     // fetch next record batch from Arrow record batch stream
     fn next_batch(&mut self) -> IcebergFdwResult<()> {
+        loop {
+            self.next_batch_in_stream()?;
+
+            // move on to next data file in parallel scan when current one is
+            // exhausted
+            if self.batch.is_some() || self.open_next_task()?.is_none() {
+                return Ok(());
+            }
+        }
+    }
+
+    fn next_batch_in_stream(&mut self) -> IcebergFdwResult<()> {
         if let Some(stream) = &mut self.stream {
-            self.batch = if let Some(result) = self.rt.block_on(stream.next()) {
+            self.batch = if let Some(result) = block_on_interruptible(&self.rt, stream.next()) {
                 let batch = result?;
                 if batch.num_rows() > 0 {
                     stats::inc_stats(Self::FDW_NAME, stats::Metric::RowsIn, batch.num_rows() as _);
@@ -118,8 +163,8 @@ impl IcebergFdw {
 
             // debug the record count and data files has been scanned
             if cfg!(debug_assertions) {
-                let mut scan_files = self.rt.block_on(scan.plan_files())?;
-                while let Some(sf) = self.rt.block_on(scan_files.next()) {
+                let mut scan_files = block_on_interruptible(&self.rt, scan.plan_files())?;
+                while let Some(sf) = block_on_interruptible(&self.rt, scan_files.next()) {
                     let sf = sf.unwrap();
                     log_debug1(&format!(
                         "file scan: {:?}, {}",
@@ -129,7 +174,7 @@ impl IcebergFdw {
             }
 
             // save record stream
-            self.stream = self.rt.block_on(scan.to_arrow())?.into();
+            self.stream = block_on_interruptible(&self.rt, scan.to_arrow())?.into();
         }
 
         Ok(())
@@ -146,9 +191,16 @@ impl IcebergFdw {
 impl ForeignDataWrapper<IcebergFdwError> for IcebergFdw {
     fn new(server: ForeignServer) -> IcebergFdwResult<Self> {
         // get aws config from server options
-        let (aws_key_id, aws_secret_key) = match server.options.get("vault_access_key_id") {
+        // credentials can be from user mapping or server options
+        let cred_opts = server.credential_options(&[
+            "vault_access_key_id",
+            "vault_secret_access_key",
+            "aws_access_key_id",
+            "aws_secret_access_key",
+        ]);
+        let (aws_key_id, aws_secret_key) = match cred_opts.get("vault_access_key_id") {
             Some(key_id) => {
-                let secret_key = require_option("vault_secret_access_key", &server.options)?;
+                let secret_key = require_option("vault_secret_access_key", cred_opts)?;
                 get_vault_secret(key_id)
                     .zip(get_vault_secret(secret_key))
                     .ok_or(IcebergFdwError::VaultError(
@@ -156,9 +208,8 @@ impl ForeignDataWrapper<IcebergFdwError> for IcebergFdw {
                     ))?
             }
             None => {
-                let key_id = require_option("aws_access_key_id", &server.options)?.to_string();
-                let secret_key =
-                    require_option("aws_secret_access_key", &server.options)?.to_string();
+                let key_id = require_option("aws_access_key_id", cred_opts)?.to_string();
+                let secret_key = require_option("aws_secret_access_key", cred_opts)?.to_string();
                 (key_id, secret_key)
             }
         };
@@ -189,7 +240,10 @@ impl ForeignDataWrapper<IcebergFdwError> for IcebergFdw {
                     .table_bucket_arn(aws_s3table_arn.into())
                     .properties(props)
                     .build();
-                Box::new(rt.block_on(S3TablesCatalog::new(catalog_config))?)
+                Box::new(block_on_interruptible(
+                    &rt,
+                    S3TablesCatalog::new(catalog_config),
+                )?)
             } else {
                 let catalog_uri = require_option("catalog_uri", &server.options)?;
                 let catalog_config = RestCatalogConfig::builder()
@@ -211,6 +265,8 @@ impl ForeignDataWrapper<IcebergFdwError> for IcebergFdw {
             batch: None,
             mapper: Mapper::default(),
             rec_offset: 0,
+            parallel_scan: None,
+            tasks: Vec::new(),
         })
     }
 
@@ -222,8 +278,7 @@ impl ForeignDataWrapper<IcebergFdwError> for IcebergFdw {
         _limit: &Option<Limit>,
         options: &HashMap<String, String>,
     ) -> IcebergFdwResult<()> {
-        let tbl_ident = TableIdent::from_strs(require_option("table", options)?.split("."))?;
-        let table = self.rt.block_on(self.catalog.load_table(&tbl_ident))?;
+        let table = self.load_table(options)?;
         self.predicate = try_pushdown(&table, quals)?.unwrap_or(Predicate::AlwaysTrue);
         self.table = table.into();
         self.tgt_cols = columns.to_vec();
@@ -256,9 +311,123 @@ impl ForeignDataWrapper<IcebergFdwError> for IcebergFdw {
 
     fn end_scan(&mut self) -> IcebergFdwResult<()> {
         self.reset();
+        self.parallel_scan = None;
+        self.tasks.clear();
         Ok(())
     }
 
+    fn explain_scan(
+        &mut self,
+        quals: &[Qual],
+        columns: &[Column],
+        _sorts: &[Sort],
+        _limit: &Option<Limit>,
+        options: &HashMap<String, String>,
+        explain: &ExplainOptions,
+    ) -> IcebergFdwResult<Vec<ExplainProperty>> {
+        let mut props = Vec::new();
+        if !explain.verbose && !explain.analyze {
+            return Ok(props);
+        }
+
+        // only table metadata is loaded if the scan is not begun
+        let (table, predicate) = match &self.table {
+            Some(table) => (table.clone(), self.predicate.clone()),
+            None => {
+                let table = self.load_table(options)?;
+                let predicate = try_pushdown(&table, quals)?.unwrap_or(Predicate::AlwaysTrue);
+                (table, predicate)
+            }
+        };
+
+        if explain.verbose {
+            props.push(ExplainProperty::Text(
+                "Remote Predicate".to_string(),
+                predicate.to_string(),
+            ));
+        }
+
+        // planning data files reads the manifests, so only do it when the
+        // scan is executed
+        if explain.analyze {
+            let files = if self.tasks.is_empty() {
+                let scan = table
+                    .scan()
+                    .select(columns.iter().map(|c| c.name.clone()))
+                    .with_filter(predicate)
+                    .build()?;
+                let tasks: Vec<FileScanTask> = block_on_interruptible(&self.rt, async {
+                    scan.plan_files().await?.try_collect().await
+                })?;
+                tasks.len()
+            } else {
+                self.tasks.len()
+            };
+            props.push(ExplainProperty::Integer(
+                "Data Files".to_string(),
+                files as i64,
+            ));
+        }
+
+        Ok(props)
+    }
+
+    fn is_parallel_safe(options: &HashMap<String, String>) -> bool {
+        options.contains_key("parallel_workers")
+    }
+
+    fn get_parallel_workers(
+        &mut self,
+        _quals: &[Qual],
+        options: &HashMap<String, String>,
+    ) -> IcebergFdwResult<usize> {
+        let workers = require_option_or("parallel_workers", options, "0");
+        workers
+            .parse::<usize>()
+            .map_err(|_| IcebergFdwError::InvalidParallelWorkersOption(workers.to_string()))
+    }
+
+    fn init_parallel_scan(
+        &mut self,
+        quals: &[Qual],
+        columns: &[Column],
+        options: &HashMap<String, String>,
+    ) -> IcebergFdwResult<Vec<u8>> {
+        // plan data files in the leader, so all the participants scan the
+        // same table snapshot
+        let table = self.load_table(options)?;
+        let predicate = try_pushdown(&table, quals)?.unwrap_or(Predicate::AlwaysTrue);
+        let scan = table
+            .scan()
+            .select(columns.iter().map(|c| c.name.clone()))
+            .with_filter(predicate)
+            .build()?;
+        let tasks: Vec<FileScanTask> = block_on_interruptible(&self.rt, async {
+            scan.plan_files().await?.try_collect().await
+        })?;
+
+        Ok(serde_json::to_vec(&tasks)?)
+    }
+
+    fn begin_parallel_scan(
+        &mut self,
+        _quals: &[Qual],
+        columns: &[Column],
+        options: &HashMap<String, String>,
+        scan: &ParallelScan,
+    ) -> IcebergFdwResult<bool> {
+        self.reset();
+        self.table = self.load_table(options)?.into();
+        self.tgt_cols = columns.to_vec();
+        self.tasks = serde_json::from_slice(scan.data())?;
+        self.parallel_scan = Some(scan.clone());
+
+        // open the first data file, the others are opened on iteration
+        self.open_next_task()?;
+
+        Ok(true)
+    }
+
     fn import_foreign_schema(
         &mut self,
         stmt: ImportForeignSchemaStmt,
@@ -268,7 +437,7 @@ impl ForeignDataWrapper<IcebergFdwError> for IcebergFdw {
 
         // get table list under specified remote schema
         let ns = NamespaceIdent::from_strs(stmt.remote_schema.split('.'))?;
-        let tbl_idents = self.rt.block_on(self.catalog.list_tables(&ns))?;
+        let tbl_idents = block_on_interruptible(&self.rt, self.catalog.list_tables(&ns))?;
 
         // filter out selected table name list
         let all_tables: HashSet<&str> =
@@ -288,7 +457,7 @@ impl ForeignDataWrapper<IcebergFdwError> for IcebergFdw {
         let tbls: Vec<Table> = tbl_idents
             .iter()
             .filter(|t| selected.contains(t.name.as_str()))
-            .map(|t| self.rt.block_on(self.catalog.load_table(t)))
+            .map(|t| block_on_interruptible(&self.rt, self.catalog.load_table(t)))
             .collect::<Result<Vec<_>, _>>()?;
 
         let mut ret: Vec<String> = Vec::new();
@@ -359,7 +528,7 @@ impl ForeignDataWrapper<IcebergFdwError> for IcebergFdw {
         catalog: Option<pg_sys::Oid>,
     ) -> IcebergFdwResult<()> {
         if let Some(oid) = catalog {
-            if oid == FOREIGN_SERVER_RELATION_ID {
+            if oid == FOREIGN_SERVER_RELATION_ID || oid == USER_MAPPING_RELATION_ID {
                 // AWS credential pair must be specified together
                 let a = check_options_contain(&options, "aws_access_key_id");
                 let b = check_options_contain(&options, "aws_secret_access_key");
@@ -368,12 +537,45 @@ impl ForeignDataWrapper<IcebergFdwError> for IcebergFdw {
                     (Ok(_), b @ Err(_)) => b.map(|_| ())?,
                     _ => (),
                 }
-            } else if oid == FOREIGN_TABLE_RELATION_ID {
-                // check required option
-                check_options_contain(&options, "table")?;
             }
         }
 
         Ok(())
     }
+
+    fn option_defs() -> Vec<OptionDef> {
+        const CRED: &[OptionContext] = &[OptionContext::Server, OptionContext::UserMapping];
+        vec![
+            OptionDef::new("aws_access_key_id", CRED)
+                .secret()
+                .description("AWS access key ID"),
+            OptionDef::new("aws_secret_access_key", CRED)
+                .secret()
+                .description("AWS secret access key"),
+            OptionDef::new("vault_access_key_id", CRED)
+                .description("Vault secret ID of the AWS access key ID"),
+            OptionDef::new("vault_secret_access_key", CRED)
+                .description("Vault secret ID of the AWS secret access key"),
+            OptionDef::new("aws_region", &[OptionContext::Server])
+                .default("us-east-1")
+                .description("AWS region"),
+            OptionDef::new("s3_endpoint_url", &[OptionContext::Server])
+                .description("URL of S3-compatible storage"),
+            OptionDef::new("aws_s3table_bucket_arn", &[OptionContext::Server])
+                .description("S3 table bucket ARN, used instead of `catalog_uri` for S3 Tables"),
+            OptionDef::new("catalog_uri", &[OptionContext::Server])
+                .description("REST catalog URI, required if `aws_s3table_bucket_arn` is not set"),
+            OptionDef::new("table", &[OptionContext::Table])
+                .required()
+                .description("Fully qualified source table name with all namespaces"),
+            OptionDef::new("parallel_workers", &[OptionContext::Table])
+                .opt_type(OptionType::Integer)
+                .default("0")
+                .description("Number of parallel workers to scan the table"),
+            OptionDef::new("strict", &[OptionContext::Import])
+                .opt_type(OptionType::Bool)
+                .default("false")
+                .description("Whether to fail on the columns of unsupported types"),
+        ]
+    }
 }
@@ -1,7 +1,7 @@
 use arrow_array::{array, timezone::Tz, RecordBatch};
 use arrow_json::ArrayWriter;
 use chrono::{DateTime, NaiveDateTime};
-use iceberg::spec::{PrimitiveType, Type};
+use iceberg::spec::{ListType, PrimitiveType, StructType, Type};
 use pgrx::{
     datum::{self, datetime_support::DateTimeConversionError, JsonB},
     pg_sys, varlena,
@@ -10,6 +10,7 @@ use serde_json::value::Value as JsonValue;
 use std::cell::RefCell;
 use std::ops::Deref;
 use std::str::FromStr;
+use std::sync::Arc;
 use uuid::Uuid;
 
 use super::{IcebergFdwError, IcebergFdwResult};
@@ -267,7 +268,12 @@ impl Mapper {
                 }
             }
             pg_sys::JSONBOID => match src_type {
-                Type::Struct(_) | Type::List(_) | Type::Map(_) => {
+                // the record batch JSON can only be located by top level column
+                Type::Struct(_) | Type::List(_) | Type::Map(_)
+                    if batch
+                        .column_by_name(col_name)
+                        .is_some_and(|c| Arc::ptr_eq(c, src_array)) =>
+                {
                     if let Some(json) = self.get_batch_json(batch)?.borrow().deref() {
                         let ptr = format!("/{}/{}", rec_offset, col_name);
                         let val = json.pointer(&ptr).cloned().unwrap_or_default();
@@ -303,10 +309,155 @@ impl Mapper {
                         .transpose()?;
                 }
             }
+            pg_sys::BOOLARRAYOID
+            | pg_sys::FLOAT4ARRAYOID
+            | pg_sys::INT4ARRAYOID
+            | pg_sys::FLOAT8ARRAYOID
+            | pg_sys::INT8ARRAYOID
+            | pg_sys::NUMERICARRAYOID
+            | pg_sys::TEXTARRAYOID
+            | pg_sys::DATEARRAYOID
+            | pg_sys::TIMEARRAYOID
+            | pg_sys::TIMESTAMPARRAYOID
+            | pg_sys::TIMESTAMPTZARRAYOID
+            | pg_sys::UUIDARRAYOID
+            | pg_sys::BYTEAARRAYOID => {
+                if let Type::List(list_type) = src_type {
+                    cell = self.map_list_cell(batch, tgt_col, src_array, list_type, rec_offset)?;
+                }
+            }
+            _ if unsafe { pg_sys::type_is_rowtype(tgt_col.type_oid) } => {
+                if let Type::Struct(struct_type) = src_type {
+                    cell =
+                        self.map_struct_cell(batch, tgt_col, src_array, struct_type, rec_offset)?;
+                }
+            }
             _ => {
                 return Err(IcebergFdwError::UnsupportedColumnType(col_name.into()));
             }
         }
         Ok(cell)
     }
+
+    // map Iceberg list to Wrappers array cell, each element is mapped to the
+    // array element type
+    fn map_list_cell(
+        &self,
+        batch: &RecordBatch,
+        tgt_col: &Column,
+        src_array: &array::ArrayRef,
+        list_type: &ListType,
+        rec_offset: usize,
+    ) -> IcebergFdwResult<Option<Cell>> {
+        let Some(arr) = src_array.as_any().downcast_ref::<array::ListArray>() else {
+            return Ok(None);
+        };
+        let elem_col = Column {
+            name: tgt_col.name.clone(),
+            num: tgt_col.num,
+            type_oid: unsafe { pg_sys::get_element_type(tgt_col.type_oid) },
+        };
+        let elem_type = list_type.element_field.field_type.as_ref();
+
+        let elems = arr.value(rec_offset);
+        let mut cells = Vec::with_capacity(elems.len());
+        for idx in 0..elems.len() {
+            if elems.is_null(idx) {
+                cells.push(None);
+                continue;
+            }
+            let cell = self.map_cell(batch, &elem_col, &elems, elem_type, idx)?;
+            if cell.is_none() {
+                // element type is not compatible
+                return Ok(None);
+            }
+            cells.push(cell);
+        }
+
+        macro_rules! to_array_cell {
+            ($cell_type:ident, $elem_cell_type:ident) => {
+                Cell::$cell_type(
+                    cells
+                        .into_iter()
+                        .map(|c| match c {
+                            Some(Cell::$elem_cell_type(v)) => Some(v),
+                            _ => None,
+                        })
+                        .collect(),
+                )
+            };
+        }
+
+        let cell = match tgt_col.type_oid {
+            pg_sys::BOOLARRAYOID => to_array_cell!(BoolArray, Bool),
+            pg_sys::FLOAT4ARRAYOID => to_array_cell!(F32Array, F32),
+            pg_sys::INT4ARRAYOID => to_array_cell!(I32Array, I32),
+            pg_sys::FLOAT8ARRAYOID => to_array_cell!(F64Array, F64),
+            pg_sys::INT8ARRAYOID => to_array_cell!(I64Array, I64),
+            pg_sys::NUMERICARRAYOID => to_array_cell!(NumericArray, Numeric),
+            pg_sys::TEXTARRAYOID => to_array_cell!(StringArray, String),
+            pg_sys::DATEARRAYOID => to_array_cell!(DateArray, Date),
+            pg_sys::TIMEARRAYOID => to_array_cell!(TimeArray, Time),
+            pg_sys::TIMESTAMPARRAYOID => to_array_cell!(TimestampArray, Timestamp),
+            pg_sys::TIMESTAMPTZARRAYOID => to_array_cell!(TimestamptzArray, Timestamptz),
+            pg_sys::UUIDARRAYOID => to_array_cell!(UuidArray, Uuid),
+            pg_sys::BYTEAARRAYOID => Cell::ByteaArray(
+                cells
+                    .into_iter()
+                    .map(|c| match c {
+                        Some(Cell::Bytea(v)) => {
+                            Some(unsafe { varlena::varlena_to_byte_slice(v) }.to_vec())
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            _ => return Ok(None),
+        };
+        Ok(Some(cell))
+    }
+
+    // map Iceberg struct to Wrappers composite cell, the struct fields are
+    // matched to the composite type attributes by name
+    fn map_struct_cell(
+        &self,
+        batch: &RecordBatch,
+        tgt_col: &Column,
+        src_array: &array::ArrayRef,
+        struct_type: &StructType,
+        rec_offset: usize,
+    ) -> IcebergFdwResult<Option<Cell>> {
+        let Some(arr) = src_array.as_any().downcast_ref::<array::StructArray>() else {
+            return Ok(None);
+        };
+
+        let mut composite = Composite::new(tgt_col.type_oid);
+        for field_col in Composite::type_columns(tgt_col.type_oid) {
+            let (Some(field), Some(field_array)) = (
+                struct_type.field_by_name(&field_col.name),
+                arr.column_by_name(&field_col.name),
+            ) else {
+                // the attribute doesn't exist in struct, leave it as null
+                composite.push(&field_col.name, None);
+                continue;
+            };
+            if field_array.is_null(rec_offset) {
+                composite.push(&field_col.name, None);
+                continue;
+            }
+            let cell = self.map_cell(
+                batch,
+                &field_col,
+                field_array,
+                field.field_type.as_ref(),
+                rec_offset,
+            )?;
+            if cell.is_none() {
+                // field type is not compatible
+                return Ok(None);
+            }
+            composite.push(&field_col.name, cell);
+        }
+        Ok(Some(Cell::Composite(composite)))
+    }
 }
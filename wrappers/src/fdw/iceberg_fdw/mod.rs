@@ -27,6 +27,9 @@ enum IcebergFdwError {
     #[error("vault error: '{0}'")]
     VaultError(String),
 
+    #[error("invalid parallel_workers option: {0}")]
+    InvalidParallelWorkersOption(String),
+
     #[error("decimal conversion error: {0}")]
     DecimalConversionError(#[from] rust_decimal::Error),
 
@@ -193,6 +193,29 @@ mod tests {
                 .map(|v| v.0.clone())
                 .collect::<Vec<_>>();
             assert_eq!(results, vec![json!({"nn": "qq", "nn2": "pp"})]);
+
+            // scan data files in parallel workers
+            c.update(
+                "ALTER FOREIGN TABLE iceberg.bids OPTIONS (ADD parallel_workers '2')",
+                None,
+                &[],
+            )
+            .unwrap();
+            c.update("SET parallel_setup_cost = 0", None, &[]).unwrap();
+            c.update("SET parallel_tuple_cost = 0", None, &[]).unwrap();
+            let plan = c
+                .select("EXPLAIN SELECT * FROM iceberg.bids", None, &[])
+                .unwrap()
+                .filter_map(|r| r.get_by_name::<&str, _>("QUERY PLAN").unwrap())
+                .filter(|line| line.contains("Parallel Foreign Scan"))
+                .collect::<Vec<_>>();
+            assert_eq!(plan.len(), 1);
+            let results = c
+                .select("SELECT * FROM iceberg.bids order by symbol", None, &[])
+                .unwrap()
+                .filter_map(|r| r.get_by_name::<&str, _>("symbol").unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(results, vec!["APL", "MCS"]);
         });
     }
 }
@@ -170,16 +170,19 @@ impl LogflareFdw {
                                             None
                                         }
                                     })
-                                } else {
-                                    // add normal cell
-                                    if let Some(s) = r.get(&tgt_col.name) {
-                                        match json_value_to_cell(tgt_col, s) {
-                                            Ok(cell) => Some(cell),
-                                            Err(err) => return Err(err),
+                                } else if let Some(s) = r.get(&tgt_col.name) {
+                                    // add normal cell, value conversion error is
+                                    // handled by the framework
+                                    match json_value_to_cell(tgt_col, s) {
+                                        Ok(cell) => Some(cell),
+                                        Err(err @ LogflareFdwError::ColumnTypeNotMatch(_)) => {
+                                            row.push_or_reject(&tgt_col.name, s, Err(err));
+                                            continue;
                                         }
-                                    } else {
-                                        None
+                                        Err(err) => return Err(err),
                                     }
+                                } else {
+                                    None
                                 };
                                 row.push(&tgt_col.name, cell);
                             }
@@ -206,10 +209,12 @@ impl ForeignDataWrapper<LogflareFdwError> for LogflareFdw {
                 }
             })
             .unwrap_or_else(|| LogflareFdw::BASE_URL.to_string());
-        let client = match server.options.get("api_key") {
+        // credentials can be from user mapping or server options
+        let cred_opts = server.credential_options(&["api_key", "api_key_id"]);
+        let client = match cred_opts.get("api_key") {
             Some(api_key) => Some(create_client(api_key)),
             None => {
-                let key_id = require_option("api_key_id", &server.options)?;
+                let key_id = require_option("api_key_id", cred_opts)?;
                 get_vault_secret(key_id).map(|api_key| create_client(&api_key))
             }
         }
@@ -252,23 +257,24 @@ impl ForeignDataWrapper<LogflareFdwError> for LogflareFdw {
             let url = url.unwrap();
 
             // make api call
-            let body: JsonValue = self.rt.block_on(client.get(url).send()).and_then(|resp| {
-                stats::inc_stats(
-                    Self::FDW_NAME,
-                    stats::Metric::BytesIn,
-                    resp.content_length().unwrap_or(0) as i64,
-                );
+            let body: JsonValue = block_on_interruptible(&self.rt, client.get(url).send())
+                .and_then(|resp| {
+                    stats::inc_stats(
+                        Self::FDW_NAME,
+                        stats::Metric::BytesIn,
+                        resp.content_length().unwrap_or(0) as i64,
+                    );
 
-                if resp.status() == StatusCode::NOT_FOUND {
-                    // if it is 404 error, we should treat it as an empty
-                    // result rather than a request error
-                    return Ok(JsonValue::Null);
-                }
+                    if resp.status() == StatusCode::NOT_FOUND {
+                        // if it is 404 error, we should treat it as an empty
+                        // result rather than a request error
+                        return Ok(JsonValue::Null);
+                    }
 
-                resp.error_for_status()
-                    .and_then(|resp| self.rt.block_on(resp.json()))
-                    .map_err(reqwest_middleware::Error::from)
-            })?;
+                    resp.error_for_status()
+                        .and_then(|resp| block_on_interruptible(&self.rt, resp.json()))
+                        .map_err(reqwest_middleware::Error::from)
+                })?;
             if body.is_null() {
                 return Ok(());
             }
@@ -300,16 +306,19 @@ impl ForeignDataWrapper<LogflareFdwError> for LogflareFdw {
         Ok(())
     }
 
-    fn validator(
-        options: Vec<Option<String>>,
-        catalog: Option<pg_sys::Oid>,
-    ) -> LogflareFdwResult<()> {
-        if let Some(oid) = catalog {
-            if oid == FOREIGN_TABLE_RELATION_ID {
-                check_options_contain(&options, "endpoint")?;
-            }
-        }
-
-        Ok(())
+    fn option_defs() -> Vec<OptionDef> {
+        const CRED: &[OptionContext] = &[OptionContext::Server, OptionContext::UserMapping];
+        vec![
+            OptionDef::new("api_url", &[OptionContext::Server])
+                .default(LogflareFdw::BASE_URL)
+                .description("Logflare endpoints query API URL"),
+            OptionDef::new("api_key", CRED)
+                .secret()
+                .description("Logflare API key"),
+            OptionDef::new("api_key_id", CRED).description("Vault secret ID of the API key"),
+            OptionDef::new("endpoint", &[OptionContext::Table])
+                .required()
+                .description("Logflare endpoint UUID or name"),
+        ]
     }
 }
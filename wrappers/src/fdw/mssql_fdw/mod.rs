@@ -13,6 +13,9 @@ enum MssqlFdwError {
     #[error("syntax error: {0}")]
     SyntaxError(String),
 
+    #[error("cannot truncate subquery table '{0}'")]
+    TruncateSubquery(String),
+
     #[error("column '{0}' data type is not supported")]
     UnsupportedColumnType(String),
 
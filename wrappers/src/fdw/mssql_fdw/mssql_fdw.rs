@@ -1,11 +1,11 @@
 use crate::stats;
 use num_traits::cast::ToPrimitive;
-use pgrx::{prelude::to_timestamp, PgBuiltInOids, PgOid};
+use pgrx::{pg_sys, prelude::to_timestamp, PgBuiltInOids, PgOid};
 use std::collections::HashMap;
 use tiberius::{
     numeric::Decimal,
-    time::chrono::{NaiveDate, NaiveDateTime},
-    Client, Config,
+    time::chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime},
+    Client, ColumnType, Config, Uuid,
 };
 use tokio::net::TcpStream;
 use tokio_util::compat::TokioAsyncWriteCompatExt;
@@ -68,11 +68,26 @@ fn field_to_cell(src_row: &tiberius::Row, tgt_col: &Column) -> MssqlFdwResult<Op
             })
         }
         PgOid::BuiltIn(PgBuiltInOids::TIMESTAMPTZOID) => {
-            src_row.try_get::<NaiveDateTime, &str>(col_name)?.map(|v| {
-                let ts = to_timestamp(v.and_utc().timestamp() as f64);
-                Cell::Timestamptz(ts)
-            })
+            let is_offset = src_row
+                .columns()
+                .iter()
+                .find(|c| c.name() == col_name)
+                .is_some_and(|c| c.column_type() == ColumnType::DatetimeOffsetn);
+            if is_offset {
+                // datetimeoffset carries its own time zone offset
+                src_row
+                    .try_get::<DateTime<FixedOffset>, &str>(col_name)?
+                    .map(|v| Cell::Timestamptz(to_timestamp(v.timestamp() as f64)))
+            } else {
+                src_row.try_get::<NaiveDateTime, &str>(col_name)?.map(|v| {
+                    let ts = to_timestamp(v.and_utc().timestamp() as f64);
+                    Cell::Timestamptz(ts)
+                })
+            }
         }
+        PgOid::BuiltIn(PgBuiltInOids::UUIDOID) => src_row
+            .try_get::<Uuid, &str>(col_name)?
+            .map(|v| Cell::Uuid(pgrx::Uuid::from_bytes(*v.as_bytes()))),
         _ => {
             return Err(MssqlFdwError::UnsupportedColumnType(tgt_col.name.clone()));
         }
@@ -106,11 +121,57 @@ pub(crate) struct MssqlFdw {
     tgt_cols: Vec<Column>,
     scan_result: Vec<tiberius::Row>,
     iter_idx: usize,
+    group_by: Vec<Column>,
+    aggregates: Vec<Aggregate>,
+    filters: Vec<Filter>,
+    remote_sql: Option<String>,
 }
 
 impl MssqlFdw {
     const FDW_NAME: &'static str = "MssqlFdw";
 
+    // deparse an aggregate, its argument is casted to the SQL Server type which
+    // matches the Postgres result type, so that no overflow or integer division
+    // happens on remote
+    fn deparse_aggregate(aggregate: &Aggregate) -> String {
+        let sql_type = match aggregate.type_oid {
+            pg_sys::INT2OID => "smallint",
+            pg_sys::INT4OID => "int",
+            pg_sys::INT8OID => "bigint",
+            pg_sys::FLOAT4OID => "real",
+            pg_sys::FLOAT8OID => "float",
+            pg_sys::NUMERICOID => "decimal(38, 10)",
+            _ => return aggregate.deparse_with_alias(),
+        };
+        match (aggregate.kind, &aggregate.column) {
+            (AggregateKind::Count, Some(col)) => {
+                format!("count_big({}) as {}", col.name, aggregate.alias)
+            }
+            (AggregateKind::Count, None) => format!("count_big(*) as {}", aggregate.alias),
+            (AggregateKind::Sum | AggregateKind::Avg, Some(col)) => format!(
+                "{}(cast({} as {})) as {}",
+                aggregate.kind.name(),
+                col.name,
+                sql_type,
+                aggregate.alias
+            ),
+            _ => aggregate.deparse_with_alias(),
+        }
+    }
+
+    // deparse qual in T-SQL, return None if its operator cannot be translated,
+    // boolean test qual is deparsed like "bool_col is true" => "bool_col = 1"
+    fn deparse_qual(qual: &Qual) -> Option<String> {
+        let mut fmt = MssqlCellFormatter {};
+        SqlDialect::TSql.deparse_qual_with_fmt(qual, &mut fmt)
+    }
+
+    // deparse a relation restriction of join, which has been checked to be
+    // pushable when planning
+    fn deparse_join_qual(qual: &Qual) -> String {
+        Self::deparse_qual(qual).expect("join restriction is pushable")
+    }
+
     fn deparse(
         &self,
         quals: &[Qual],
@@ -118,7 +179,14 @@ impl MssqlFdw {
         sorts: &[Sort],
         limit: &Option<Limit>,
     ) -> MssqlFdwResult<String> {
-        let tgts = if columns.is_empty() {
+        let tgts = if !self.aggregates.is_empty() || !self.group_by.is_empty() {
+            self.group_by
+                .iter()
+                .map(|c| c.name.clone())
+                .chain(self.aggregates.iter().map(Self::deparse_aggregate))
+                .collect::<Vec<String>>()
+                .join(", ")
+        } else if columns.is_empty() {
             "*".to_string()
         } else {
             columns
@@ -130,30 +198,30 @@ impl MssqlFdw {
 
         let mut sql = format!("select {} from {} as _wrappers_tbl", tgts, &self.table);
 
-        if !quals.is_empty() {
-            let cond = quals
+        let cond = quals
+            .iter()
+            .filter_map(Self::deparse_qual)
+            .chain(self.filters.iter().filter_map(|f| {
+                let mut fmt = MssqlCellFormatter {};
+                SqlDialect::TSql
+                    .deparse_filter_with_fmt(f, &mut fmt)
+                    .map(|f| format!("({})", f))
+            }))
+            .collect::<Vec<String>>()
+            .join(" and ");
+        if !cond.is_empty() {
+            sql.push_str(&format!(" where {}", cond));
+        }
+
+        // push down group by
+        if !self.group_by.is_empty() {
+            let group_by = self
+                .group_by
                 .iter()
-                .map(|q| {
-                    let oper = q.operator.as_str();
-                    let mut fmt = MssqlCellFormatter {};
-                    if let Value::Cell(cell) = &q.value {
-                        // deparse boolean test qual, e.g. "bool_col is true" => "bool_col = 1"
-                        if let Cell::Bool(_) = cell {
-                            if oper == "is" {
-                                return format!("{} = {}", q.field, fmt.fmt_cell(cell));
-                            } else if oper == "is not" {
-                                return format!("{} <> {}", q.field, fmt.fmt_cell(cell));
-                            }
-                        }
-                    }
-                    q.deparse_with_fmt(&mut fmt)
-                })
+                .map(|c| c.name.clone())
                 .collect::<Vec<String>>()
-                .join(" and ");
-
-            if !cond.is_empty() {
-                sql.push_str(&format!(" where {}", cond));
-            }
+                .join(", ");
+            sql.push_str(&format!(" group by {}", group_by));
         }
 
         // push down sorts
@@ -194,15 +262,66 @@ impl MssqlFdw {
 
         Ok(sql)
     }
+
+    fn fetch_scan_result(
+        &mut self,
+        quals: &[Qual],
+        columns: &[Column],
+        sorts: &[Sort],
+        limit: &Option<Limit>,
+        options: &HashMap<String, String>,
+    ) -> MssqlFdwResult<()> {
+        self.table = require_option("table", options)?.to_string();
+        self.tgt_cols = columns.to_vec();
+
+        // compile sql query to run on remote
+        let sql = self.deparse(quals, columns, sorts, limit)?;
+
+        self.query_scan_result(sql)
+    }
+
+    fn query_scan_result(&mut self, sql: String) -> MssqlFdwResult<()> {
+        self.iter_idx = 0;
+
+        // create sql server client
+        let tcp = block_on_interruptible(&self.rt, TcpStream::connect(self.config.get_addr()))?;
+        tcp.set_nodelay(true)?;
+        let mut client = block_on_interruptible(
+            &self.rt,
+            Client::connect(self.config.clone(), tcp.compat_write()),
+        )?;
+
+        // run query on remote sql server and store full result set locally
+        self.scan_result = block_on_interruptible(
+            &self.rt,
+            block_on_interruptible(&self.rt, client.simple_query(&sql))?.into_first_result(),
+        )?;
+        self.remote_sql = Some(sql);
+
+        stats::inc_stats(
+            Self::FDW_NAME,
+            stats::Metric::RowsIn,
+            self.scan_result.len() as i64,
+        );
+        stats::inc_stats(
+            Self::FDW_NAME,
+            stats::Metric::RowsOut,
+            self.scan_result.len() as i64,
+        );
+
+        Ok(())
+    }
 }
 
 impl ForeignDataWrapper<MssqlFdwError> for MssqlFdw {
     fn new(server: ForeignServer) -> MssqlFdwResult<Self> {
         let rt = create_async_runtime()?;
-        let conn_str = match server.options.get("conn_string") {
+        // credentials can be from user mapping or server options
+        let cred_opts = server.credential_options(&["conn_string", "conn_string_id"]);
+        let conn_str = match cred_opts.get("conn_string") {
             Some(conn_str) => conn_str.to_owned(),
             None => {
-                let conn_str_id = require_option("conn_string_id", &server.options)?;
+                let conn_str_id = require_option("conn_string_id", cred_opts)?;
                 get_vault_secret(conn_str_id).unwrap_or_default()
             }
         };
@@ -217,9 +336,55 @@ impl ForeignDataWrapper<MssqlFdwError> for MssqlFdw {
             tgt_cols: Vec::new(),
             scan_result: Vec::new(),
             iter_idx: 0,
+            group_by: Vec::new(),
+            aggregates: Vec::new(),
+            filters: Vec::new(),
+            remote_sql: None,
         })
     }
 
+    fn field_expr_allowlist() -> &'static [&'static str] {
+        &["abs", "lower", "upper"]
+    }
+
+    fn get_filter_pushdown(
+        &mut self,
+        filters: &[Filter],
+        _options: &HashMap<String, String>,
+    ) -> MssqlFdwResult<bool> {
+        if !filters
+            .iter()
+            .all(|f| SqlDialect::TSql.is_filter_pushable(f))
+        {
+            return Ok(false);
+        }
+        self.filters = filters.to_vec();
+        Ok(true)
+    }
+
+    fn get_scan_pushdown(
+        &mut self,
+        quals: &[Qual],
+        sorts: &[Sort],
+        _limit: &Option<Limit>,
+        _options: &HashMap<String, String>,
+    ) -> MssqlFdwResult<Option<Pushdown>> {
+        // quals whose operators can be translated are deparsed into the remote
+        // query, but SQL Server always sorts nulls as the lowest values, its
+        // string order depends on its own collation, and it only supports limit
+        // with sorts
+        let is_sorted = sorts
+            .iter()
+            .all(|s| s.nulls_first != s.reversed && s.is_locale_independent());
+        Ok(Some(Pushdown {
+            quals: (0..quals.len())
+                .filter(|i| SqlDialect::TSql.is_pushable(&quals[*i]))
+                .collect(),
+            sorts: is_sorted,
+            limit: is_sorted && !sorts.is_empty(),
+        }))
+    }
+
     fn begin_scan(
         &mut self,
         quals: &[Qual],
@@ -228,42 +393,62 @@ impl ForeignDataWrapper<MssqlFdwError> for MssqlFdw {
         limit: &Option<Limit>,
         options: &HashMap<String, String>,
     ) -> MssqlFdwResult<()> {
-        self.table = require_option("table", options)?.to_string();
-        self.tgt_cols = columns.to_vec();
+        self.group_by.clear();
+        self.aggregates.clear();
+        self.fetch_scan_result(quals, columns, sorts, limit, options)
+    }
 
-        self.iter_idx = 0;
+    fn get_aggregate_pushdown(
+        &mut self,
+        quals: &[Qual],
+        _group_by: &[Column],
+        _aggregates: &[Aggregate],
+        _options: &HashMap<String, String>,
+    ) -> MssqlFdwResult<bool> {
+        Ok(quals.iter().all(|q| SqlDialect::TSql.is_pushable(q)))
+    }
 
-        // create sql server client
-        let tcp = self
-            .rt
-            .block_on(TcpStream::connect(self.config.get_addr()))?;
-        tcp.set_nodelay(true)?;
-        let mut client = self
-            .rt
-            .block_on(Client::connect(self.config.clone(), tcp.compat_write()))?;
+    fn begin_aggregate_scan(
+        &mut self,
+        quals: &[Qual],
+        group_by: &[Column],
+        aggregates: &[Aggregate],
+        options: &HashMap<String, String>,
+    ) -> MssqlFdwResult<()> {
+        self.group_by = group_by.to_vec();
+        self.aggregates = aggregates.to_vec();
 
-        // compile sql query to run on remote
-        let sql = self.deparse(quals, columns, sorts, limit)?;
+        // output columns are group by columns followed by aggregates
+        let columns = group_by
+            .iter()
+            .cloned()
+            .chain(aggregates.iter().map(|agg| agg.as_column()))
+            .collect::<Vec<_>>();
 
-        // run query on remote sql server and store full result set locally
-        self.scan_result = self.rt.block_on(
-            self.rt
-                .block_on(client.simple_query(sql))?
-                .into_first_result(),
-        )?;
+        self.fetch_scan_result(quals, &columns, &[], &None, options)
+    }
 
-        stats::inc_stats(
-            Self::FDW_NAME,
-            stats::Metric::RowsIn,
-            self.scan_result.len() as i64,
-        );
-        stats::inc_stats(
-            Self::FDW_NAME,
-            stats::Metric::RowsOut,
-            self.scan_result.len() as i64,
+    fn get_join_pushdown(&mut self, join: &Join) -> MssqlFdwResult<bool> {
+        Ok(SqlDialect::TSql.is_join_pushable(join))
+    }
+
+    fn begin_join_scan(&mut self, join: &Join) -> MssqlFdwResult<()> {
+        self.group_by.clear();
+        self.aggregates.clear();
+        self.tgt_cols = join.output_columns();
+
+        let outer_table = require_option("table", &join.outer.options)?;
+        let inner_table = require_option("table", &join.inner.options)?;
+        let mut sql = format!(
+            "select {} from {}",
+            join.deparse_columns(),
+            join.deparse_from(outer_table, inner_table, Self::deparse_join_qual)
         );
+        if let Some(cond) = join.deparse_where(Self::deparse_join_qual) {
+            sql.push_str(&format!(" where {}", cond));
+        }
 
-        Ok(())
+        self.query_scan_result(sql)
     }
 
     fn iter_scan(&mut self, row: &mut Row) -> MssqlFdwResult<Option<()>> {
@@ -294,4 +479,75 @@ impl ForeignDataWrapper<MssqlFdwError> for MssqlFdw {
         self.scan_result.clear();
         Ok(())
     }
+
+    fn explain_scan(
+        &mut self,
+        quals: &[Qual],
+        columns: &[Column],
+        sorts: &[Sort],
+        limit: &Option<Limit>,
+        options: &HashMap<String, String>,
+        explain: &ExplainOptions,
+    ) -> MssqlFdwResult<Vec<ExplainProperty>> {
+        if !explain.verbose {
+            return Ok(Vec::new());
+        }
+        // use the query already sent if the scan is executed
+        let sql = match &self.remote_sql {
+            Some(sql) => sql.clone(),
+            None => {
+                self.table = require_option("table", options)?.to_string();
+                self.deparse(quals, columns, sorts, limit)?
+            }
+        };
+        Ok(vec![ExplainProperty::Text("Remote SQL".to_string(), sql)])
+    }
+
+    fn truncate(
+        &mut self,
+        tables: &[HashMap<String, String>],
+        _restart_seqs: bool,
+        _cascade: bool,
+    ) -> MssqlFdwResult<()> {
+        let tables = tables
+            .iter()
+            .map(|options| {
+                let table = require_option("table", options)?;
+                if table.starts_with('(') {
+                    return Err(MssqlFdwError::TruncateSubquery(table.to_string()));
+                }
+                Ok(table.to_string())
+            })
+            .collect::<MssqlFdwResult<Vec<_>>>()?;
+
+        // create sql server client
+        let tcp = block_on_interruptible(&self.rt, TcpStream::connect(self.config.get_addr()))?;
+        tcp.set_nodelay(true)?;
+        let mut client = block_on_interruptible(
+            &self.rt,
+            Client::connect(self.config.clone(), tcp.compat_write()),
+        )?;
+
+        // run truncate on remote sql server
+        for table in tables {
+            let sql = format!("truncate table {}", table);
+            block_on_interruptible(&self.rt, client.execute(sql, &[]))?;
+        }
+
+        Ok(())
+    }
+
+    fn option_defs() -> Vec<OptionDef> {
+        const CRED: &[OptionContext] = &[OptionContext::Server, OptionContext::UserMapping];
+        vec![
+            OptionDef::new("conn_string", CRED)
+                .secret()
+                .description("SQL Server ADO.NET connection string"),
+            OptionDef::new("conn_string_id", CRED)
+                .description("Vault secret ID of the connection string"),
+            OptionDef::new("table", &[OptionContext::Table])
+                .required()
+                .description("Source table, view name or subquery"),
+        ]
+    }
 }
@@ -58,6 +58,19 @@ mod tests {
         })
         .expect("insert test data");
 
+        rt.block_on(async {
+            client
+                .execute(
+                    r#"
+                    CREATE TABLE users_trunc (id bigint);
+                    INSERT INTO users_trunc(id) VALUES (1), (2);
+                    "#,
+                    &[],
+                )
+                .await
+        })
+        .expect("create truncate test table in SQL Server");
+
         Spi::connect_mut(|c| {
             c.update(
                 r#"CREATE FOREIGN DATA WRAPPER mssql_wrapper
@@ -189,6 +202,47 @@ mod tests {
                 .filter_map(|r| r.get_by_name::<&str, _>("name").unwrap())
                 .collect::<Vec<_>>();
             assert_eq!(results, vec!["bar"]);
+
+            // test join pushdown
+            let results = c
+                .select(
+                    "SELECT u.name FROM mssql_users u
+                     JOIN mssql_users_cust_sql s ON u.id = s.id
+                     WHERE u.is_admin is false",
+                    None,
+                    &[],
+                )
+                .unwrap()
+                .filter_map(|r| r.get_by_name::<&str, _>("name").unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(results, vec!["foo"]);
+
+            // test truncate foreign table
+            c.update(
+                r#"
+                  CREATE FOREIGN TABLE mssql_users_trunc (
+                    id bigint
+                  )
+                  SERVER mssql_server
+                  OPTIONS (
+                    table 'users_trunc'
+                  )
+             "#,
+                None,
+                &[],
+            )
+            .unwrap();
+            assert_eq!(
+                c.select("SELECT * FROM mssql_users_trunc", None, &[])
+                    .unwrap()
+                    .len(),
+                2
+            );
+            c.update("TRUNCATE mssql_users_trunc", None, &[]).unwrap();
+            assert!(c
+                .select("SELECT * FROM mssql_users_trunc", None, &[])
+                .unwrap()
+                .is_empty());
         });
 
         let result = std::panic::catch_unwind(|| {
@@ -241,10 +241,12 @@ impl RedisFdw {
 
 impl ForeignDataWrapper<RedisFdwError> for RedisFdw {
     fn new(server: ForeignServer) -> RedisFdwResult<Self> {
-        let conn_url = match server.options.get("conn_url") {
+        // credentials can be from user mapping or server options
+        let cred_opts = server.credential_options(&["conn_url", "conn_url_id"]);
+        let conn_url = match cred_opts.get("conn_url") {
             Some(url) => url.to_owned(),
             None => {
-                let conn_url_id = require_option("conn_url_id", &server.options)?;
+                let conn_url_id = require_option("conn_url_id", cred_opts)?;
                 get_vault_secret(conn_url_id).unwrap_or_default()
             }
         };
@@ -395,4 +397,64 @@ impl ForeignDataWrapper<RedisFdwError> for RedisFdw {
         self.scan_result_stream.clear();
         Ok(())
     }
+
+    fn truncate(
+        &mut self,
+        tables: &[HashMap<String, String>],
+        _restart_seqs: bool,
+        _cascade: bool,
+    ) -> RedisFdwResult<()> {
+        let mut conn = self.client.get_connection()?;
+
+        for options in tables {
+            let src_type = require_option("src_type", options)?;
+            let src_key = require_option("src_key", options)?;
+
+            match src_type {
+                "list" | "set" | "zset" | "hash" | "stream" => {
+                    let _: () = conn.del(src_key)?;
+                }
+                "multi_list" | "multi_set" | "multi_zset" | "multi_hash" => {
+                    // src_key is a key pattern, delete all the matched keys
+                    let keys = conn.scan_match(src_key)?.collect::<Vec<String>>();
+                    if !keys.is_empty() {
+                        let _: () = conn.del(keys)?;
+                    }
+                }
+                _ => {
+                    return Err(RedisFdwError::UnsupportedSourceType(src_type.to_string()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn option_defs() -> Vec<OptionDef> {
+        const CRED: &[OptionContext] = &[OptionContext::Server, OptionContext::UserMapping];
+        vec![
+            OptionDef::new("conn_url", CRED)
+                .secret()
+                .description("Redis connection URL, for example `redis://127.0.0.1:6379/0`"),
+            OptionDef::new("conn_url_id", CRED)
+                .description("Vault secret ID of the connection URL"),
+            OptionDef::new("src_type", &[OptionContext::Table])
+                .required()
+                .allowed(&[
+                    "list",
+                    "set",
+                    "zset",
+                    "hash",
+                    "stream",
+                    "multi_list",
+                    "multi_set",
+                    "multi_zset",
+                    "multi_hash",
+                ])
+                .description("Source type in Redis"),
+            OptionDef::new("src_key", &[OptionContext::Table])
+                .required()
+                .description("Source object key, or key pattern for `multi_*` source types"),
+        ]
+    }
 }
@@ -396,6 +396,24 @@ mod tests {
                     ("zset:200", r#"["qux", "baz"]"#)
                 ]
             );
+
+            // truncate deletes the key, or all the keys matching the pattern
+            c.update("TRUNCATE redis_list, redis_multi_lists", None, &[])
+                .unwrap();
+            assert!(c
+                .select("SELECT * FROM redis_list", None, &[])
+                .unwrap()
+                .is_empty());
+            assert!(c
+                .select("SELECT * FROM redis_multi_lists", None, &[])
+                .unwrap()
+                .is_empty());
+            assert_eq!(
+                c.select("SELECT * FROM redis_multi_sets", None, &[])
+                    .unwrap()
+                    .len(),
+                2
+            );
         });
     }
 }
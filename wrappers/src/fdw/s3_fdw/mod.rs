@@ -23,6 +23,9 @@ enum S3FdwError {
     #[error("invalid compression option: {0}")]
     InvalidCompressOption(String),
 
+    #[error("invalid parallel_workers option: {0}")]
+    InvalidParallelWorkersOption(String),
+
     #[error("read line failed: {0}")]
     ReadLineError(#[from] std::io::Error),
 
@@ -8,8 +8,8 @@ use parquet::arrow::async_reader::{
 };
 use parquet::arrow::ProjectionMask;
 use pgrx::datum::datetime_support::to_timestamp;
-use pgrx::pg_sys;
 use pgrx::prelude::Date;
+use pgrx::{pg_sys, IntoDatum};
 use std::cmp::min;
 use std::io::{Cursor, Error as IoError, ErrorKind, Result as IoResult, SeekFrom};
 use std::pin::Pin;
@@ -142,6 +142,9 @@ pub(super) struct S3Parquet {
     // a record batch
     batch: Option<RecordBatch>,
     batch_idx: usize,
+
+    // total size of the record batches read
+    pub(super) bytes_in: i64,
 }
 
 impl S3Parquet {
@@ -156,17 +159,15 @@ impl S3Parquet {
         Ok(())
     }
 
-    // open async record batch stream
+    // create parquet record batch stream builder
     //
-    // This is done by spawning a thread to create builder and then get read stream
+    // This is done by spawning a thread to create builder
     // Note: this function should be called on a tokio runtime executor thread
-    pub(super) async fn open_async_stream(
-        &mut self,
+    async fn create_builder(
         client: &s3::Client,
         bucket: &str,
         object: &str,
-        tgt_cols: &[Column],
-    ) -> S3FdwResult<()> {
+    ) -> S3FdwResult<ParquetRecordBatchStreamBuilder<Box<dyn AsyncFileReader>>> {
         let handle = Handle::current();
         let rdr = S3ParquetReader::new(client, bucket, object);
 
@@ -182,32 +183,63 @@ impl S3Parquet {
             })
             .await;
 
-        let stream = task
-            .expect("create parquet batch stream builder failed")
+        let builder = task
             .expect("create parquet batch stream builder failed")
-            .and_then(|builder| {
-                // get parquet file metadata
-                let file_metadata = builder.metadata().file_metadata();
-                let schema = file_metadata.schema_descr();
-                let cols = schema.columns();
-
-                // find target column indexes in parquest columns
-                let project_indexes = tgt_cols
+            .expect("create parquet batch stream builder failed")?;
+
+        Ok(builder)
+    }
+
+    // get the number of row groups in parquet file
+    // Note: this function should be called on a tokio runtime executor thread
+    pub(super) async fn get_num_row_groups(
+        client: &s3::Client,
+        bucket: &str,
+        object: &str,
+    ) -> S3FdwResult<usize> {
+        let builder = Self::create_builder(client, bucket, object).await?;
+        Ok(builder.metadata().num_row_groups())
+    }
+
+    // open async record batch stream, only the specified row groups are read
+    // if `row_groups` is not None
+    // Note: this function should be called on a tokio runtime executor thread
+    pub(super) async fn open_async_stream(
+        &mut self,
+        client: &s3::Client,
+        bucket: &str,
+        object: &str,
+        tgt_cols: &[Column],
+        row_groups: Option<Vec<usize>>,
+    ) -> S3FdwResult<()> {
+        let builder = Self::create_builder(client, bucket, object).await?;
+
+        // get parquet file metadata
+        let file_metadata = builder.metadata().file_metadata();
+        let schema = file_metadata.schema_descr();
+        let fields = schema.root_schema().get_fields();
+
+        // find target column indexes in parquest root fields, so nested list
+        // and struct columns can be projected as a whole
+        let project_indexes = tgt_cols
+            .iter()
+            .map(|tgt_col| {
+                fields
                     .iter()
-                    .map(|tgt_col| {
-                        cols.iter()
-                            .position(|col| col.name() == tgt_col.name)
-                            .unwrap_or_else(|| {
-                                panic!("column '{}' not found in parquet file", tgt_col.name)
-                            })
+                    .position(|field| field.name() == tgt_col.name)
+                    .unwrap_or_else(|| {
+                        panic!("column '{}' not found in parquet file", tgt_col.name)
                     })
-                    .collect::<Vec<usize>>();
-
-                // set up projections for the builder
-                let mask = ProjectionMask::roots(schema, project_indexes);
-                builder.with_projection(mask).build()
             })
-            .map_err(|err| parquet::errors::ParquetError::General(err.to_string()))?;
+            .collect::<Vec<usize>>();
+
+        // set up projections and row groups for the builder
+        let mask = ProjectionMask::roots(schema, project_indexes);
+        let mut builder = builder.with_projection(mask);
+        if let Some(row_groups) = row_groups {
+            builder = builder.with_row_groups(row_groups);
+        }
+        let stream = builder.build()?;
 
         self.stream = Some(stream);
         self.batch = None;
@@ -229,16 +261,14 @@ impl S3Parquet {
         if let Some(ref mut stream) = &mut self.stream {
             let result = stream.try_next().await?;
             return Ok(result.map(|batch| {
+                let bytes_in = batch.get_array_memory_size() as i64;
                 stats::inc_stats(
                     Self::FDW_NAME,
                     stats::Metric::RowsIn,
                     batch.num_rows() as i64,
                 );
-                stats::inc_stats(
-                    Self::FDW_NAME,
-                    stats::Metric::BytesIn,
-                    batch.get_array_memory_size() as i64,
-                );
+                stats::inc_stats(Self::FDW_NAME, stats::Metric::BytesIn, bytes_in);
+                self.bytes_in += bytes_in;
 
                 self.batch = Some(batch);
                 self.batch_idx = 0;
@@ -259,102 +289,12 @@ impl S3Parquet {
                 let col = batch
                     .column_by_name(&tgt_col.name)
                     .ok_or(S3FdwError::ColumnNotFound(tgt_col.name.clone()))?;
-
-                macro_rules! col_to_cell {
-                    ($array_type:ident, $cell_type:ident) => {{
-                        let arr = col
-                            .as_any()
-                            .downcast_ref::<array::$array_type>()
-                            .ok_or(S3FdwError::ColumnTypeNotMatch(tgt_col.name.clone()))?;
-                        if arr.is_null(self.batch_idx) {
-                            None
-                        } else {
-                            Some(Cell::$cell_type(arr.value(self.batch_idx)))
-                        }
-                    }};
-                }
-
-                let cell = match tgt_col.type_oid {
-                    pg_sys::BOOLOID => col_to_cell!(BooleanArray, Bool),
-                    pg_sys::CHAROID => col_to_cell!(Int8Array, I8),
-                    pg_sys::INT2OID => col_to_cell!(Int16Array, I16),
-                    pg_sys::FLOAT4OID => col_to_cell!(Float32Array, F32),
-                    pg_sys::INT4OID => col_to_cell!(Int32Array, I32),
-                    pg_sys::FLOAT8OID => col_to_cell!(Float64Array, F64),
-                    pg_sys::INT8OID => col_to_cell!(Int64Array, I64),
-                    pg_sys::NUMERICOID => {
-                        let arr = col
-                            .as_any()
-                            .downcast_ref::<array::Float64Array>()
-                            .ok_or(S3FdwError::ColumnTypeNotMatch(tgt_col.name.clone()))?;
-                        if arr.is_null(self.batch_idx) {
-                            None
-                        } else {
-                            let value = arr.value(self.batch_idx);
-                            let num = pgrx::AnyNumeric::try_from(value)?;
-                            Some(Cell::Numeric(num))
-                        }
-                    }
-                    pg_sys::TEXTOID => {
-                        let arr = col
-                            .as_any()
-                            .downcast_ref::<array::BinaryArray>()
-                            .ok_or(S3FdwError::ColumnTypeNotMatch(tgt_col.name.clone()))?;
-                        if arr.is_null(self.batch_idx) {
-                            None
-                        } else {
-                            let s = String::from_utf8_lossy(arr.value(self.batch_idx));
-                            Some(Cell::String(s.to_string()))
-                        }
-                    }
-                    pg_sys::DATEOID => {
-                        let arr = col
-                            .as_any()
-                            .downcast_ref::<array::Date64Array>()
-                            .ok_or(S3FdwError::ColumnTypeNotMatch(tgt_col.name.clone()))?;
-                        if arr.is_null(self.batch_idx) {
-                            None
-                        } else {
-                            arr.value_as_date(self.batch_idx).map(|dt| {
-                                let epoch = NaiveDate::from_ymd_opt(1970, 1, 1)
-                                    .expect("1/1/1970 is a valid NaiveDate");
-                                let seconds_from_epoch =
-                                    dt.signed_duration_since(epoch).num_seconds();
-                                let ts = to_timestamp(seconds_from_epoch as f64);
-                                Cell::Date(Date::from(ts))
-                            })
-                        }
-                    }
-                    pg_sys::TIMESTAMPOID => {
-                        let arr = col
-                            .as_any()
-                            .downcast_ref::<array::TimestampNanosecondArray>()
-                            .ok_or(S3FdwError::ColumnTypeNotMatch(tgt_col.name.clone()))?;
-                        if arr.is_null(self.batch_idx) {
-                            None
-                        } else {
-                            arr.value_as_datetime(self.batch_idx).map(|ts| {
-                                let ts = to_timestamp(ts.and_utc().timestamp() as f64);
-                                Cell::Timestamp(ts.to_utc())
-                            })
-                        }
-                    }
-                    pg_sys::TIMESTAMPTZOID => {
-                        let arr = col
-                            .as_any()
-                            .downcast_ref::<array::TimestampNanosecondArray>()
-                            .ok_or(S3FdwError::ColumnTypeNotMatch(tgt_col.name.clone()))?;
-                        if arr.is_null(self.batch_idx) {
-                            None
-                        } else {
-                            arr.value_as_datetime(self.batch_idx).map(|ts| {
-                                let ts = to_timestamp(ts.and_utc().timestamp() as f64);
-                                Cell::Timestamptz(ts)
-                            })
-                        }
-                    }
-                    _ => return Err(S3FdwError::UnsupportedColumnType(tgt_col.name.clone())),
-                };
+                let cell = array_value_to_cell(
+                    col.as_ref(),
+                    self.batch_idx,
+                    &tgt_col.name,
+                    tgt_col.type_oid,
+                )?;
                 row.push(&tgt_col.name, cell);
             }
             self.batch_idx += 1;
@@ -363,3 +303,200 @@ impl S3Parquet {
         Ok(None)
     }
 }
+
+// convert one value in arrow array to cell, list and struct values are
+// converted recursively
+fn array_value_to_cell(
+    col: &dyn Array,
+    idx: usize,
+    col_name: &str,
+    type_oid: pg_sys::Oid,
+) -> S3FdwResult<Option<Cell>> {
+    macro_rules! col_to_cell {
+        ($array_type:ident, $cell_type:ident) => {{
+            let arr = col
+                .as_any()
+                .downcast_ref::<array::$array_type>()
+                .ok_or(S3FdwError::ColumnTypeNotMatch(col_name.to_owned()))?;
+            if arr.is_null(idx) {
+                None
+            } else {
+                Some(Cell::$cell_type(arr.value(idx)))
+            }
+        }};
+    }
+
+    // convert list value by converting each of its elements
+    macro_rules! list_to_cell {
+        ($elem_type_oid:expr, $cell_type:ident, $elem_cell_type:ident) => {{
+            let arr = col
+                .as_any()
+                .downcast_ref::<array::ListArray>()
+                .ok_or(S3FdwError::ColumnTypeNotMatch(col_name.to_owned()))?;
+            if arr.is_null(idx) {
+                None
+            } else {
+                let elems = arr.value(idx);
+                let mut values = Vec::with_capacity(elems.len());
+                for i in 0..elems.len() {
+                    let value =
+                        match array_value_to_cell(elems.as_ref(), i, col_name, $elem_type_oid)? {
+                            Some(Cell::$elem_cell_type(v)) => Some(v),
+                            _ => None,
+                        };
+                    values.push(value);
+                }
+                Some(Cell::$cell_type(values))
+            }
+        }};
+    }
+
+    let cell = match type_oid {
+        pg_sys::BOOLOID => col_to_cell!(BooleanArray, Bool),
+        pg_sys::CHAROID => col_to_cell!(Int8Array, I8),
+        pg_sys::INT2OID => col_to_cell!(Int16Array, I16),
+        pg_sys::FLOAT4OID => col_to_cell!(Float32Array, F32),
+        pg_sys::INT4OID => col_to_cell!(Int32Array, I32),
+        pg_sys::FLOAT8OID => col_to_cell!(Float64Array, F64),
+        pg_sys::INT8OID => col_to_cell!(Int64Array, I64),
+        pg_sys::NUMERICOID => {
+            let arr = col
+                .as_any()
+                .downcast_ref::<array::Float64Array>()
+                .ok_or(S3FdwError::ColumnTypeNotMatch(col_name.to_owned()))?;
+            if arr.is_null(idx) {
+                None
+            } else {
+                let value = arr.value(idx);
+                let num = pgrx::AnyNumeric::try_from(value)?;
+                Some(Cell::Numeric(num))
+            }
+        }
+        pg_sys::TEXTOID => {
+            let arr = col
+                .as_any()
+                .downcast_ref::<array::BinaryArray>()
+                .ok_or(S3FdwError::ColumnTypeNotMatch(col_name.to_owned()))?;
+            if arr.is_null(idx) {
+                None
+            } else {
+                let s = String::from_utf8_lossy(arr.value(idx));
+                Some(Cell::String(s.to_string()))
+            }
+        }
+        pg_sys::BYTEAOID => {
+            let arr = col
+                .as_any()
+                .downcast_ref::<array::BinaryArray>()
+                .ok_or(S3FdwError::ColumnTypeNotMatch(col_name.to_owned()))?;
+            if arr.is_null(idx) {
+                None
+            } else {
+                arr.value(idx)
+                    .to_vec()
+                    .into_datum()
+                    .map(|datum| Cell::Bytea(datum.cast_mut_ptr()))
+            }
+        }
+        pg_sys::DATEOID => {
+            let arr = col
+                .as_any()
+                .downcast_ref::<array::Date64Array>()
+                .ok_or(S3FdwError::ColumnTypeNotMatch(col_name.to_owned()))?;
+            if arr.is_null(idx) {
+                None
+            } else {
+                arr.value_as_date(idx).map(|dt| {
+                    let epoch =
+                        NaiveDate::from_ymd_opt(1970, 1, 1).expect("1/1/1970 is a valid NaiveDate");
+                    let seconds_from_epoch = dt.signed_duration_since(epoch).num_seconds();
+                    let ts = to_timestamp(seconds_from_epoch as f64);
+                    Cell::Date(Date::from(ts))
+                })
+            }
+        }
+        pg_sys::TIMESTAMPOID => {
+            let arr = col
+                .as_any()
+                .downcast_ref::<array::TimestampNanosecondArray>()
+                .ok_or(S3FdwError::ColumnTypeNotMatch(col_name.to_owned()))?;
+            if arr.is_null(idx) {
+                None
+            } else {
+                arr.value_as_datetime(idx).map(|ts| {
+                    let ts = to_timestamp(ts.and_utc().timestamp() as f64);
+                    Cell::Timestamp(ts.to_utc())
+                })
+            }
+        }
+        pg_sys::TIMESTAMPTZOID => {
+            let arr = col
+                .as_any()
+                .downcast_ref::<array::TimestampNanosecondArray>()
+                .ok_or(S3FdwError::ColumnTypeNotMatch(col_name.to_owned()))?;
+            if arr.is_null(idx) {
+                None
+            } else {
+                arr.value_as_datetime(idx).map(|ts| {
+                    let ts = to_timestamp(ts.and_utc().timestamp() as f64);
+                    Cell::Timestamptz(ts)
+                })
+            }
+        }
+        pg_sys::BOOLARRAYOID => list_to_cell!(pg_sys::BOOLOID, BoolArray, Bool),
+        pg_sys::INT2ARRAYOID => list_to_cell!(pg_sys::INT2OID, I16Array, I16),
+        pg_sys::FLOAT4ARRAYOID => list_to_cell!(pg_sys::FLOAT4OID, F32Array, F32),
+        pg_sys::INT4ARRAYOID => list_to_cell!(pg_sys::INT4OID, I32Array, I32),
+        pg_sys::FLOAT8ARRAYOID => list_to_cell!(pg_sys::FLOAT8OID, F64Array, F64),
+        pg_sys::INT8ARRAYOID => list_to_cell!(pg_sys::INT8OID, I64Array, I64),
+        pg_sys::NUMERICARRAYOID => list_to_cell!(pg_sys::NUMERICOID, NumericArray, Numeric),
+        pg_sys::TEXTARRAYOID => list_to_cell!(pg_sys::TEXTOID, StringArray, String),
+        pg_sys::DATEARRAYOID => list_to_cell!(pg_sys::DATEOID, DateArray, Date),
+        pg_sys::TIMESTAMPARRAYOID => list_to_cell!(pg_sys::TIMESTAMPOID, TimestampArray, Timestamp),
+        pg_sys::TIMESTAMPTZARRAYOID => {
+            list_to_cell!(pg_sys::TIMESTAMPTZOID, TimestamptzArray, Timestamptz)
+        }
+        pg_sys::BYTEAARRAYOID => {
+            let arr = col
+                .as_any()
+                .downcast_ref::<array::ListArray>()
+                .ok_or(S3FdwError::ColumnTypeNotMatch(col_name.to_owned()))?;
+            if arr.is_null(idx) {
+                None
+            } else {
+                let elems = arr.value(idx);
+                let elems = elems
+                    .as_any()
+                    .downcast_ref::<array::BinaryArray>()
+                    .ok_or(S3FdwError::ColumnTypeNotMatch(col_name.to_owned()))?;
+                let values = elems
+                    .iter()
+                    .map(|v| v.map(|v| v.to_vec()))
+                    .collect::<Vec<_>>();
+                Some(Cell::ByteaArray(values))
+            }
+        }
+        _ if unsafe { pg_sys::type_is_rowtype(type_oid) } => {
+            let arr = col
+                .as_any()
+                .downcast_ref::<array::StructArray>()
+                .ok_or(S3FdwError::ColumnTypeNotMatch(col_name.to_owned()))?;
+            if arr.is_null(idx) {
+                None
+            } else {
+                let mut composite = Composite::new(type_oid);
+                for field in Composite::type_columns(type_oid) {
+                    let field_col = arr
+                        .column_by_name(&field.name)
+                        .ok_or(S3FdwError::ColumnNotFound(field.name.clone()))?;
+                    let cell =
+                        array_value_to_cell(field_col.as_ref(), idx, &field.name, field.type_oid)?;
+                    composite.push(&field.name, cell);
+                }
+                Some(Cell::Composite(composite))
+            }
+        }
+        _ => return Err(S3FdwError::UnsupportedColumnType(col_name.to_owned())),
+    };
+    Ok(cell)
+}
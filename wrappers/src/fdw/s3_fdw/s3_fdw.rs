@@ -3,12 +3,12 @@ use async_compression::tokio::bufread::{BzDecoder, GzipDecoder, XzDecoder, ZlibD
 use aws_config::BehaviorVersion;
 use aws_sdk_s3 as s3;
 use http::Uri;
-use pgrx::pg_sys;
 use serde_json::{self, Value as JsonValue};
 use std::collections::{HashMap, VecDeque};
 use std::env;
 use std::io::Cursor;
 use std::pin::Pin;
+use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
 
 use super::parquet::*;
@@ -16,11 +16,21 @@ use supabase_wrappers::prelude::*;
 
 use super::{S3FdwError, S3FdwResult};
 
+// join fields of a malformed csv record as its raw text
+fn csv_raw_record(record: &csv::ByteRecord) -> String {
+    record
+        .iter()
+        .map(String::from_utf8_lossy)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
 // record parser for a S3 file
 enum Parser {
     Csv(csv::Reader<Cursor<Vec<u8>>>),
-    // JSON lines text file format: https://jsonlines.org/
-    JsonLine(VecDeque<JsonValue>),
+    // JSON lines text file format: https://jsonlines.org/, malformed line is
+    // kept as raw text along with its parse error
+    JsonLine(VecDeque<Result<JsonValue, (String, serde_json::Error)>>),
     Parquet(S3Parquet),
 }
 
@@ -31,24 +41,89 @@ enum Parser {
     error_type = "S3FdwError"
 )]
 pub(crate) struct S3Fdw {
-    rt: Runtime,
+    rt: Arc<Runtime>,
     client: Option<s3::Client>,
     rdr: Option<BufReader<Pin<Box<dyn AsyncRead>>>>,
     parser: Parser,
     tgt_cols: Vec<Column>,
     rows_out: i64,
+    bytes_in: i64,
 
     // local string buffer for CSV and JSONL
     buf: String,
+
+    // parallel scan of parquet file, its row groups are claimed one by one
+    parallel_scan: Option<ParallelScan>,
+    num_row_groups: usize,
+    bucket: String,
+    object: String,
 }
 
 impl S3Fdw {
+    fn with_client(rt: Arc<Runtime>, client: Option<s3::Client>) -> Self {
+        S3Fdw {
+            rt,
+            client,
+            rdr: None,
+            parser: Parser::JsonLine(VecDeque::new()),
+            tgt_cols: Vec::new(),
+            rows_out: 0,
+            bytes_in: 0,
+            buf: String::new(),
+            parallel_scan: None,
+            num_row_groups: 0,
+            bucket: String::new(),
+            object: String::new(),
+        }
+    }
+
     const FDW_NAME: &'static str = "S3Fdw";
 
     // local string line buffer size, in bytes
     // Note: this is not a hard limit, just an indication of full buffer
     const BUF_SIZE: usize = 256 * 1024;
 
+    // extract s3 bucket and object path from uri option
+    fn parse_uri(options: &HashMap<String, String>) -> S3FdwResult<(String, String)> {
+        let uri = require_option("uri", options)?.parse::<Uri>()?;
+        if uri.scheme_str() != Option::Some("s3") || uri.host().is_none() || uri.path().is_empty() {
+            return Err(S3FdwError::InvalidS3Uri(uri.to_string()));
+        }
+        // exclude 1st "/" char in the path as s3 object path doesn't like it
+        Ok((
+            uri.host()
+                .expect("host is not None as tested in if condition above")
+                .to_owned(),
+            uri.path()[1..].to_string(),
+        ))
+    }
+
+    // claim next row group in parallel scan and open async read stream for it
+    // Returns:
+    //   Some - a row group is claimed
+    //   None - all row groups have been claimed
+    fn open_next_row_group(&mut self) -> S3FdwResult<Option<()>> {
+        let (Some(scan), Some(client)) = (&self.parallel_scan, &self.client) else {
+            return Ok(None);
+        };
+        let Some(row_group) = scan.claim_unit(self.num_row_groups) else {
+            return Ok(None);
+        };
+        if let Parser::Parquet(ref mut s3parquet) = &mut self.parser {
+            block_on_interruptible(
+                &self.rt,
+                s3parquet.open_async_stream(
+                    client,
+                    &self.bucket,
+                    &self.object,
+                    &self.tgt_cols,
+                    Some(vec![row_group]),
+                ),
+            )?;
+        }
+        Ok(Some(()))
+    }
+
     // fetch remote data to local string line buffer when it is empty and set
     // up record parser.
     // Returns:
@@ -64,7 +139,7 @@ impl S3Fdw {
             let mut total_lines = 0;
             let mut total_bytes = 0;
             loop {
-                let num_bytes = self.rt.block_on(rdr.read_line(&mut self.buf))?;
+                let num_bytes = block_on_interruptible(&self.rt, rdr.read_line(&mut self.buf))?;
                 total_lines += 1;
                 total_bytes += num_bytes;
                 if num_bytes == 0 || self.buf.len() > Self::BUF_SIZE {
@@ -74,6 +149,7 @@ impl S3Fdw {
 
             stats::inc_stats(Self::FDW_NAME, stats::Metric::RowsIn, total_lines);
             stats::inc_stats(Self::FDW_NAME, stats::Metric::BytesIn, total_bytes as i64);
+            self.bytes_in += total_bytes as i64;
         }
 
         if self.buf.is_empty() {
@@ -89,20 +165,17 @@ impl S3Fdw {
                     .from_reader(Cursor::new(buf));
             }
             Parser::JsonLine(records) => {
-                // enclose json lines into a json string and then parse it
-                let s = self
+                // parse each line separately, so a malformed line can be
+                // rejected without failing others
+                *records = self
                     .buf
                     .split('\n')
                     .map(|s| s.trim())
-                    .collect::<Vec<&str>>()
-                    .join(",");
-                let json_str = format!("{{ \"rows\": [{}] }}", s.trim_end_matches(','));
-                let rows = serde_json::from_str::<JsonValue>(&json_str)?;
-                let rows = rows
-                    .get("rows")
-                    .and_then(|arr| arr.as_array())
-                    .ok_or(S3FdwError::ReadJsonlError(json_str))?;
-                *records = VecDeque::from(rows.to_vec());
+                    .filter(|s| !s.is_empty())
+                    .map(|s| {
+                        serde_json::from_str::<JsonValue>(s).map_err(|err| (s.to_owned(), err))
+                    })
+                    .collect();
             }
             _ => unreachable!(),
         }
@@ -115,86 +188,93 @@ impl ForeignDataWrapper<S3FdwError> for S3Fdw {
     fn new(server: ForeignServer) -> S3FdwResult<Self> {
         // cannot use create_async_runtime() as the runtime needs to be created
         // for multiple threads
-        let rt = tokio::runtime::Runtime::new()
-            .map_err(CreateRuntimeError::FailedToCreateAsyncRuntime)?;
-        let mut ret = S3Fdw {
-            rt,
-            client: None,
-            rdr: None,
-            parser: Parser::JsonLine(VecDeque::new()),
-            tgt_cols: Vec::new(),
-            rows_out: 0,
-            buf: String::new(),
+        let create_runtime = || {
+            tokio::runtime::Runtime::new()
+                .map(Arc::new)
+                .map_err(CreateRuntimeError::FailedToCreateAsyncRuntime)
         };
 
-        // get credentials
+        // get credentials, they can be from user mapping or server options
+        let cred_opts = server.credential_options(&[
+            "vault_access_key_id",
+            "vault_secret_access_key",
+            "aws_access_key_id",
+            "aws_secret_access_key",
+        ]);
         let creds = {
-            match server.options.get("vault_access_key_id") {
+            match cred_opts.get("vault_access_key_id") {
                 Some(vault_access_key_id) => {
                     // if using credentials stored in Vault
                     let vault_secret_access_key =
-                        require_option("vault_secret_access_key", &server.options)?;
+                        require_option("vault_secret_access_key", cred_opts)?;
                     get_vault_secret(vault_access_key_id)
                         .zip(get_vault_secret(vault_secret_access_key))
                 }
                 None => {
                     // if using credentials directly specified
                     let aws_access_key_id =
-                        require_option("aws_access_key_id", &server.options)?.to_string();
+                        require_option("aws_access_key_id", cred_opts)?.to_string();
                     let aws_secret_access_key =
-                        require_option("aws_secret_access_key", &server.options)?.to_string();
+                        require_option("aws_secret_access_key", cred_opts)?.to_string();
                     Some((aws_access_key_id, aws_secret_access_key))
                 }
             }
         };
 
         let Some(creds) = creds else {
-            return Ok(ret);
+            return Ok(Self::with_client(create_runtime()?, None));
         };
 
-        // get region
-        let region = require_option_or("aws_region", &server.options, "us-east-1");
+        // runtime and S3 client are cached and reused across statements,
+        // because the client's connections are bound to the runtime
+        let (rt, client) = server.get_or_create_cached(&creds, || -> S3FdwResult<_> {
+            let rt = create_runtime()?;
 
-        // set AWS environment variables and create shared config from them
-        env::set_var("AWS_ACCESS_KEY_ID", creds.0);
-        env::set_var("AWS_SECRET_ACCESS_KEY", creds.1);
-        env::set_var("AWS_REGION", region);
+            // get region
+            let region = require_option_or("aws_region", &server.options, "us-east-1");
 
-        let mut config_loader = aws_config::defaults(BehaviorVersion::latest());
+            // set AWS environment variables and create shared config from them
+            env::set_var("AWS_ACCESS_KEY_ID", &creds.0);
+            env::set_var("AWS_SECRET_ACCESS_KEY", &creds.1);
+            env::set_var("AWS_REGION", region);
 
-        // endpoint_url not supported as env var in rust https://github.com/awslabs/aws-sdk-rust/issues/932
-        if let Some(endpoint_url) = server.options.get("endpoint_url") {
-            if endpoint_url.ends_with('/') {
-                config_loader = config_loader.endpoint_url(endpoint_url);
-            } else {
-                config_loader = config_loader.endpoint_url(format!("{}/", endpoint_url));
-            };
-        }
+            let mut config_loader = aws_config::defaults(BehaviorVersion::latest());
 
-        // get path_style_url flag
-        //
-        // path style has been deprecated, but other s3-compatible services are
-        // still using it.
-        //
-        // Examples:
-        // path_style: https://s3.amazonaws.com/bucket/image.png
-        // virtual-hosted style: https://bucket.s3.amazonaws.com/image.png
-        //
-        // ref: https://docs.aws.amazon.com/AmazonS3/latest/userguide/VirtualHosting.html
-        let path_style_url =
-            server.options.get("path_style_url").map(|s| s.as_str()) == Some("true");
-
-        let config = ret.rt.block_on(config_loader.load());
+            // endpoint_url not supported as env var in rust https://github.com/awslabs/aws-sdk-rust/issues/932
+            if let Some(endpoint_url) = server.options.get("endpoint_url") {
+                if endpoint_url.ends_with('/') {
+                    config_loader = config_loader.endpoint_url(endpoint_url);
+                } else {
+                    config_loader = config_loader.endpoint_url(format!("{}/", endpoint_url));
+                };
+            }
 
-        stats::inc_stats(Self::FDW_NAME, stats::Metric::CreateTimes, 1);
+            // get path_style_url flag
+            //
+            // path style has been deprecated, but other s3-compatible services are
+            // still using it.
+            //
+            // Examples:
+            // path_style: https://s3.amazonaws.com/bucket/image.png
+            // virtual-hosted style: https://bucket.s3.amazonaws.com/image.png
+            //
+            // ref: https://docs.aws.amazon.com/AmazonS3/latest/userguide/VirtualHosting.html
+            let path_style_url =
+                server.options.get("path_style_url").map(|s| s.as_str()) == Some("true");
+
+            let config = block_on_interruptible(&rt, config_loader.load());
+
+            // create S3 client
+            let mut s3_config_builder = s3::config::Builder::from(&config);
+            s3_config_builder = s3_config_builder.force_path_style(path_style_url);
+            let client = s3::Client::from_conf(s3_config_builder.build());
+
+            Ok((rt, client))
+        })?;
 
-        // create S3 client
-        let mut s3_config_builder = s3::config::Builder::from(&config);
-        s3_config_builder = s3_config_builder.force_path_style(path_style_url);
-        let client = s3::Client::from_conf(s3_config_builder.build());
-        ret.client = Some(client);
+        stats::inc_stats(Self::FDW_NAME, stats::Metric::CreateTimes, 1);
 
-        Ok(ret)
+        Ok(Self::with_client(rt, Some(client)))
     }
 
     fn begin_scan(
@@ -205,23 +285,7 @@ impl ForeignDataWrapper<S3FdwError> for S3Fdw {
         _limit: &Option<Limit>,
         options: &HashMap<String, String>,
     ) -> S3FdwResult<()> {
-        // extract s3 bucket and object path from uri option
-        let (bucket, object) = {
-            let uri = require_option("uri", options)?.parse::<Uri>()?;
-            if uri.scheme_str() != Option::Some("s3")
-                || uri.host().is_none()
-                || uri.path().is_empty()
-            {
-                return Err(S3FdwError::InvalidS3Uri(uri.to_string()));
-            }
-            // exclude 1st "/" char in the path as s3 object path doesn't like it
-            (
-                uri.host()
-                    .expect("host is not None as tested in if condition above")
-                    .to_owned(),
-                uri.path()[1..].to_string(),
-            )
-        };
+        let (bucket, object) = Self::parse_uri(options)?;
 
         let has_header: bool = options.get("has_header") == Some(&"true".to_string());
 
@@ -238,11 +302,12 @@ impl ForeignDataWrapper<S3FdwError> for S3Fdw {
                 _ => return Err(S3FdwError::InvalidFormatOption(format.to_string())),
             }
 
-            let stream = self
-                .rt
-                .block_on(client.get_object().bucket(&bucket).key(&object).send())?
-                .body
-                .into_async_read();
+            let stream = block_on_interruptible(
+                &self.rt,
+                client.get_object().bucket(&bucket).key(&object).send(),
+            )?
+            .body
+            .into_async_read();
 
             let mut boxed_stream: Pin<Box<dyn AsyncRead>> =
                 if let Some(compress) = options.get("compress") {
@@ -264,18 +329,15 @@ impl ForeignDataWrapper<S3FdwError> for S3Fdw {
                 if options.get("compress").is_some() {
                     // read all contents to local
                     let mut buf = Vec::new();
-                    self.rt
-                        .block_on(boxed_stream.read_to_end(&mut buf))
+                    block_on_interruptible(&self.rt, boxed_stream.read_to_end(&mut buf))
                         .expect("read compressed parquet file failed");
-                    self.rt.block_on(s3parquet.open_local_stream(buf))?;
+                    block_on_interruptible(&self.rt, s3parquet.open_local_stream(buf))?;
                 } else {
                     // open async read stream
-                    self.rt.block_on(s3parquet.open_async_stream(
-                        client,
-                        &bucket,
-                        &object,
-                        &self.tgt_cols,
-                    ))?;
+                    block_on_interruptible(
+                        &self.rt,
+                        s3parquet.open_async_stream(client, &bucket, &object, &self.tgt_cols, None),
+                    )?;
                 }
                 return Ok(());
             }
@@ -286,7 +348,7 @@ impl ForeignDataWrapper<S3FdwError> for S3Fdw {
             if let Parser::Csv(_) = self.parser {
                 if has_header {
                     let mut header = String::new();
-                    self.rt.block_on(rdr.read_line(&mut header))?;
+                    block_on_interruptible(&self.rt, rdr.read_line(&mut header))?;
                 }
             }
 
@@ -298,10 +360,23 @@ impl ForeignDataWrapper<S3FdwError> for S3Fdw {
 
     fn iter_scan(&mut self, row: &mut Row) -> S3FdwResult<Option<()>> {
         // read parquet record
-        if let Parser::Parquet(ref mut s3parquet) = &mut self.parser {
-            if self.rt.block_on(s3parquet.refill())?.is_none() {
-                return Ok(None);
+        if let Parser::Parquet(_) = self.parser {
+            // move on to next row group in parallel scan when current one is
+            // exhausted
+            loop {
+                let Parser::Parquet(ref mut s3parquet) = &mut self.parser else {
+                    unreachable!()
+                };
+                if block_on_interruptible(&self.rt, s3parquet.refill())?.is_some() {
+                    break;
+                }
+                if self.open_next_row_group()?.is_none() {
+                    return Ok(None);
+                }
             }
+            let Parser::Parquet(ref mut s3parquet) = &mut self.parser else {
+                unreachable!()
+            };
             let ret = s3parquet.read_into_row(row, &self.tgt_cols)?;
             if ret.is_some() {
                 self.rows_out += 1;
@@ -320,14 +395,29 @@ impl ForeignDataWrapper<S3FdwError> for S3Fdw {
             // parse local buffer data to records
             match &mut self.parser {
                 Parser::Csv(rdr) => {
-                    let mut record = csv::StringRecord::new();
-                    let result = rdr.read_record(&mut record)?;
+                    let mut record = csv::ByteRecord::new();
+                    let result = match rdr.read_byte_record(&mut record) {
+                        Ok(result) => result,
+                        // malformed record is rejected and handled by the
+                        // framework, the reader can go on to next record
+                        Err(err) if !err.is_io_error() => {
+                            row.reject(csv_raw_record(&record), S3FdwError::from(err));
+                            return Ok(Some(()));
+                        }
+                        Err(err) => return Err(err.into()),
+                    };
                     if result {
-                        for col in &self.tgt_cols {
-                            let cell = record.get(col.num - 1).map(|s| Cell::String(s.to_owned()));
-                            row.push(&col.name, cell);
+                        match csv::StringRecord::from_byte_record(record) {
+                            Ok(record) => {
+                                for col in &self.tgt_cols {
+                                    let cell =
+                                        record.get(col.num - 1).map(|s| Cell::String(s.to_owned()));
+                                    row.push(&col.name, cell);
+                                }
+                                self.rows_out += 1;
+                            }
+                            Err(err) => row.reject(csv_raw_record(err.as_byte_record()), &err),
                         }
-                        self.rows_out += 1;
                         return Ok(Some(()));
                     } else {
                         // no more records left in the local buffer, refill from remote
@@ -336,7 +426,11 @@ impl ForeignDataWrapper<S3FdwError> for S3Fdw {
                 }
                 Parser::JsonLine(records) => {
                     match records.pop_front() {
-                        Some(record) => {
+                        Some(Err((line, err))) => {
+                            row.reject(line, S3FdwError::ReadJsonlError(err.to_string()));
+                            return Ok(Some(()));
+                        }
+                        Some(Ok(record)) => {
                             if let Some(obj) = record.as_object() {
                                 for col in &self.tgt_cols {
                                     let cell = obj
@@ -383,17 +477,138 @@ impl ForeignDataWrapper<S3FdwError> for S3Fdw {
         // release local resources
         self.rdr.take();
         self.parser = Parser::JsonLine(VecDeque::new());
+        self.parallel_scan = None;
         Ok(())
     }
 
-    fn validator(options: Vec<Option<String>>, catalog: Option<pg_sys::Oid>) -> S3FdwResult<()> {
-        if let Some(oid) = catalog {
-            if oid == FOREIGN_TABLE_RELATION_ID {
-                check_options_contain(&options, "uri")?;
-                check_options_contain(&options, "format")?;
-            }
+    fn explain_scan(
+        &mut self,
+        _quals: &[Qual],
+        _columns: &[Column],
+        _sorts: &[Sort],
+        _limit: &Option<Limit>,
+        options: &HashMap<String, String>,
+        explain: &ExplainOptions,
+    ) -> S3FdwResult<Vec<ExplainProperty>> {
+        let mut props = Vec::new();
+        if explain.verbose {
+            let uri = require_option("uri", options)?;
+            props.push(ExplainProperty::Text(
+                "Remote URL".to_string(),
+                uri.to_string(),
+            ));
         }
+        if explain.analyze {
+            let bytes_in = match &self.parser {
+                Parser::Parquet(s3parquet) => s3parquet.bytes_in,
+                _ => self.bytes_in,
+            };
+            props.push(ExplainProperty::Integer("Bytes In".to_string(), bytes_in));
+        }
+        Ok(props)
+    }
 
-        Ok(())
+    fn is_parallel_safe(options: &HashMap<String, String>) -> bool {
+        // only uncompressed parquet file can be split by row groups
+        options.get("format").map(|s| s.as_str()) == Some("parquet")
+            && !options.contains_key("compress")
+    }
+
+    fn get_parallel_workers(
+        &mut self,
+        _quals: &[Qual],
+        options: &HashMap<String, String>,
+    ) -> S3FdwResult<usize> {
+        let workers = require_option_or("parallel_workers", options, "0");
+        workers
+            .parse::<usize>()
+            .map_err(|_| S3FdwError::InvalidParallelWorkersOption(workers.to_string()))
+    }
+
+    fn init_parallel_scan(
+        &mut self,
+        _quals: &[Qual],
+        _columns: &[Column],
+        options: &HashMap<String, String>,
+    ) -> S3FdwResult<Vec<u8>> {
+        // share the number of row groups, so the workers don't need to read
+        // parquet file metadata again
+        let (bucket, object) = Self::parse_uri(options)?;
+        let num_row_groups = match &self.client {
+            Some(client) => block_on_interruptible(
+                &self.rt,
+                S3Parquet::get_num_row_groups(client, &bucket, &object),
+            )?,
+            None => 0,
+        };
+        Ok((num_row_groups as u64).to_le_bytes().to_vec())
+    }
+
+    fn begin_parallel_scan(
+        &mut self,
+        _quals: &[Qual],
+        columns: &[Column],
+        options: &HashMap<String, String>,
+        scan: &ParallelScan,
+    ) -> S3FdwResult<bool> {
+        let (bucket, object) = Self::parse_uri(options)?;
+        let num_row_groups = scan
+            .data()
+            .try_into()
+            .map(u64::from_le_bytes)
+            .unwrap_or_default();
+
+        self.tgt_cols = columns.to_vec();
+        self.parser = Parser::Parquet(S3Parquet::default());
+        self.parallel_scan = Some(scan.clone());
+        self.num_row_groups = num_row_groups as usize;
+        self.bucket = bucket;
+        self.object = object;
+
+        // row groups are claimed and read on iteration
+        Ok(true)
+    }
+
+    fn option_defs() -> Vec<OptionDef> {
+        const CRED: &[OptionContext] = &[OptionContext::Server, OptionContext::UserMapping];
+        vec![
+            OptionDef::new("aws_access_key_id", CRED)
+                .secret()
+                .description("AWS access key ID"),
+            OptionDef::new("aws_secret_access_key", CRED)
+                .secret()
+                .description("AWS secret access key"),
+            OptionDef::new("vault_access_key_id", CRED)
+                .description("Vault secret ID of the AWS access key ID"),
+            OptionDef::new("vault_secret_access_key", CRED)
+                .description("Vault secret ID of the AWS secret access key"),
+            OptionDef::new("aws_region", &[OptionContext::Server])
+                .default("us-east-1")
+                .description("Region of the bucket"),
+            OptionDef::new("endpoint_url", &[OptionContext::Server])
+                .description("URL of S3-compatible service"),
+            OptionDef::new("path_style_url", &[OptionContext::Server])
+                .opt_type(OptionType::Bool)
+                .default("false")
+                .description("Whether to use path-style URL access"),
+            OptionDef::new("uri", &[OptionContext::Table])
+                .required()
+                .description("S3 URI, for example `s3://bucket/s3_table.csv`"),
+            OptionDef::new("format", &[OptionContext::Table])
+                .required()
+                .allowed(&["csv", "jsonl", "parquet"])
+                .description("File format"),
+            OptionDef::new("has_header", &[OptionContext::Table])
+                .opt_type(OptionType::Bool)
+                .default("false")
+                .description("If the CSV file has header"),
+            OptionDef::new("compress", &[OptionContext::Table])
+                .allowed(&["bzip2", "gzip", "xz", "zlib"])
+                .description("Compression algorithm"),
+            OptionDef::new("parallel_workers", &[OptionContext::Table])
+                .opt_type(OptionType::Integer)
+                .default("0")
+                .description("Number of parallel workers to scan an uncompressed Parquet file"),
+        ]
     }
 }
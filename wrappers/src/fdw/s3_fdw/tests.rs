@@ -191,6 +191,128 @@ mod tests {
 
             check_parquet_table("s3_test_table_parquet");
             check_parquet_table("s3_test_table_parquet_gz");
+
+            // scan parquet file in parallel workers, its row groups are split
+            // among the workers
+            let sql = "SELECT count(*) FROM s3_test_table_parquet";
+            let expected = c
+                .select(sql, None, &[])
+                .unwrap()
+                .first()
+                .get_one::<i64>()
+                .unwrap();
+            c.update(
+                "ALTER FOREIGN TABLE s3_test_table_parquet OPTIONS (ADD parallel_workers '2')",
+                None,
+                &[],
+            )
+            .unwrap();
+            c.update("SET parallel_setup_cost = 0", None, &[]).unwrap();
+            c.update("SET parallel_tuple_cost = 0", None, &[]).unwrap();
+            let plan = c
+                .select(&format!("EXPLAIN {}", sql), None, &[])
+                .unwrap()
+                .filter_map(|r| r.get_by_name::<&str, _>("QUERY PLAN").unwrap())
+                .filter(|line| line.contains("Parallel Foreign Scan"))
+                .collect::<Vec<_>>();
+            assert_eq!(plan.len(), 1);
+            assert_eq!(
+                c.select(sql, None, &[])
+                    .unwrap()
+                    .first()
+                    .get_one::<i64>()
+                    .unwrap(),
+                expected
+            );
+
+            // malformed csv record is skipped and logged to the reject table
+            c.update(
+                "CREATE TABLE s3_rejects (
+                  relname text,
+                  column_name text,
+                  raw text,
+                  error text,
+                  rejected_at timestamptz DEFAULT now()
+                )",
+                None,
+                &[],
+            )
+            .unwrap();
+            c.update(
+                r#"
+                CREATE FOREIGN TABLE s3_test_table_csv_invalid (
+                  name text,
+                  sex text,
+                  age text,
+                  height text,
+                  weight text
+                )
+                SERVER s3_server
+                OPTIONS (
+                    uri 's3://warehouse/test_data_invalid.csv',
+                    format 'csv',
+                    has_header 'true',
+                    on_error 'skip',
+                    reject_table 's3_rejects'
+                  )
+             "#,
+                None,
+                &[],
+            )
+            .unwrap();
+            let results = c
+                .select(
+                    "SELECT name FROM s3_test_table_csv_invalid ORDER BY name",
+                    None,
+                    &[],
+                )
+                .unwrap()
+                .filter_map(|r| r.get_by_name::<&str, _>("name").unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(results, vec!["Bert", "Carl"]);
+            let results = c
+                .select(
+                    "SELECT relname, raw FROM s3_rejects WHERE column_name IS NULL",
+                    None,
+                    &[],
+                )
+                .unwrap()
+                .filter_map(|r| {
+                    r.get_by_name::<&str, _>("relname")
+                        .unwrap()
+                        .zip(r.get_by_name::<&str, _>("raw").unwrap())
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(results, vec![("s3_test_table_csv_invalid", "Alex,M,41")]);
+
+            // supported options are listed
+            assert_eq!(
+                c.select(
+                    "SELECT array_to_string(allowed_values, ',') FROM s3_fdw_options()
+                     WHERE name = 'format' AND required",
+                    None,
+                    &[]
+                )
+                .unwrap()
+                .first()
+                .get_one::<&str>()
+                .unwrap()
+                .unwrap(),
+                "csv,jsonl,parquet"
+            );
+        });
+
+        // misspelled option is rejected
+        let result = std::panic::catch_unwind(|| {
+            Spi::connect_mut(|c| {
+                c.update(
+                    "ALTER FOREIGN TABLE s3_test_table_csv OPTIONS (ADD has_heder 'true')",
+                    None,
+                    &[],
+                )
+                .is_err()
+            })
         });
+        assert!(result.is_err());
     }
 }
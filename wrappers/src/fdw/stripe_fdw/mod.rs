@@ -43,6 +43,9 @@ enum StripeFdwError {
 
     #[error("invalid stats: {0}")]
     InvalidStats(String),
+
+    #[error("asynchronous scan failed")]
+    AsyncScanFailed,
 }
 
 impl From<StripeFdwError> for ErrorReport {
@@ -1,10 +1,11 @@
 use crate::stats;
-use pgrx::{datum::datetime_support::to_timestamp, pg_sys, JsonB};
+use pgrx::{datum::datetime_support::to_timestamp, JsonB};
 use reqwest::{self, header, StatusCode, Url};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
 use serde_json::{json, Map as JsonMap, Number, Value as JsonValue};
 use std::collections::{HashMap, HashSet};
+use std::thread::{self, JoinHandle};
 
 use supabase_wrappers::prelude::*;
 
@@ -634,6 +635,98 @@ fn pushdown_quals(
     }
 }
 
+// get the cursor of next page from response body, which is the last object's
+// id if there are more objects
+fn next_page_cursor(resp_body: &str) -> StripeFdwResult<Option<String>> {
+    let value: JsonValue = serde_json::from_str(resp_body)?;
+    let has_more = value
+        .get("has_more")
+        .and_then(|v| v.as_bool())
+        .unwrap_or_default();
+    if !has_more {
+        return Ok(None);
+    }
+    Ok(value
+        .get("data")
+        .and_then(|v| v.as_array())
+        .and_then(|v| v.last())
+        .and_then(|v| v.get("id"))
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_owned()))
+}
+
+// response bodies fetched from Stripe API
+struct FetchResult {
+    bodies: Vec<String>,
+    bytes_in: i64,
+    request_cnt: i64,
+}
+
+// fetch pages of objects from Stripe API, starting from the url of first page
+//
+// No Postgres functions can be called in this function, because it can be run
+// in a background thread for asynchronous scan.
+async fn fetch_pages(
+    client: &ClientWithMiddleware,
+    url: &Url,
+    page_cnt: i64,
+) -> StripeFdwResult<FetchResult> {
+    let mut result = FetchResult {
+        bodies: Vec::new(),
+        bytes_in: 0,
+        request_cnt: 0,
+    };
+    let mut cursor: Option<String> = None;
+    let mut page = 0;
+
+    while page < page_cnt {
+        let mut page_url = url.clone();
+        if let Some(ref cursor) = cursor {
+            page_url
+                .query_pairs_mut()
+                .append_pair("starting_after", cursor);
+        }
+
+        // make api call
+        result.request_cnt += 1;
+        let resp = client.get(page_url).send().await?;
+        result.bytes_in += resp.content_length().unwrap_or(0) as i64;
+
+        // if it is 404 error, we should treat it as an empty result rather
+        // than a request error
+        if resp.status() == StatusCode::NOT_FOUND {
+            break;
+        }
+
+        let body = resp
+            .error_for_status()
+            .map_err(reqwest_middleware::Error::from)?
+            .text()
+            .await
+            .map_err(reqwest_middleware::Error::from)?;
+        if body.is_empty() {
+            break;
+        }
+
+        cursor = next_page_cursor(&body)?;
+        result.bodies.push(body);
+        if cursor.is_none() {
+            break;
+        }
+
+        page += 1;
+    }
+
+    Ok(result)
+}
+
+// a scan running in background thread for asynchronous execution
+struct ScanTask {
+    obj: String,
+    columns: Vec<Column>,
+    handle: JoinHandle<StripeFdwResult<FetchResult>>,
+}
+
 // get stats metadata
 #[inline]
 fn get_stats_metadata() -> JsonB {
@@ -671,15 +764,25 @@ pub(crate) struct StripeFdw {
     base_url: Url,
     client: Option<ClientWithMiddleware>,
     scan_result: Option<Vec<Row>>,
+    scan_task: Option<ScanTask>,
     obj: String,
     rowid_col: String,
     iter_idx: usize,
     table_config: TableConfig,
+    pages_fetched: i64,
+    bytes_in: i64,
 }
 
 impl StripeFdw {
     const FDW_NAME: &'static str = "StripeFdw";
 
+    // default cost of one API request, which has a much higher latency than
+    // processing rows locally
+    const REQUEST_COST: f64 = 100.0;
+
+    // maximum page size limit for Stripe API
+    const PAGE_SIZE: f64 = 100.0;
+
     fn build_url(
         &self,
         obj: &str,
@@ -701,6 +804,59 @@ impl StripeFdw {
         Ok(Some(url))
     }
 
+    // get the url of first page and the number of pages to fetch, return None
+    // if nothing needs to be fetched
+    fn prepare_scan(
+        &self,
+        obj: &str,
+        quals: &[Qual],
+        limit: &Option<Limit>,
+    ) -> StripeFdwResult<Option<(Url, i64)>> {
+        let page_size = Self::PAGE_SIZE as i64;
+        let page_cnt = if let Some(limit) = limit {
+            if limit.count == 0 {
+                return Ok(None);
+            }
+            (limit.offset + limit.count) / page_size + 1
+        } else {
+            // if no limit specified, fetch all records
+            i64::MAX
+        };
+        let url = self.build_url(obj, quals, page_size, &None)?;
+        Ok(url.map(|url| (url, page_cnt)))
+    }
+
+    // convert the fetched response bodies to scan result and save stats
+    fn save_scan_result(
+        &mut self,
+        obj: &str,
+        columns: &[Column],
+        fetched: FetchResult,
+    ) -> StripeFdwResult<()> {
+        let mut stats_metadata = get_stats_metadata();
+        for _ in 0..fetched.request_cnt {
+            inc_stats_request_cnt(&mut stats_metadata)?;
+        }
+        stats::inc_stats(Self::FDW_NAME, stats::Metric::BytesIn, fetched.bytes_in);
+        self.pages_fetched = fetched.request_cnt;
+        self.bytes_in = fetched.bytes_in;
+
+        let mut result = Vec::new();
+        for body in &fetched.bodies {
+            let (rows, _, _) = self.resp_to_rows(obj, body, columns)?;
+            result.extend(rows);
+        }
+
+        // save stats
+        stats::inc_stats(Self::FDW_NAME, stats::Metric::RowsIn, result.len() as i64);
+        stats::inc_stats(Self::FDW_NAME, stats::Metric::RowsOut, result.len() as i64);
+        set_stats_metadata(stats_metadata);
+
+        self.scan_result = Some(result);
+
+        Ok(())
+    }
+
     // convert response body text to rows
     fn resp_to_rows(
         &self,
@@ -715,6 +871,146 @@ impl StripeFdw {
             .ok_or_else(|| StripeFdwError::ObjectNotImplemented(obj.to_string()))?;
         body_to_rows(resp_body, cols, tgt_cols)
     }
+
+    // fill the RETURNING row with the object in response body
+    fn fill_returning(
+        &self,
+        resp_body: &str,
+        columns: &[Column],
+        returning: &mut Row,
+    ) -> StripeFdwResult<()> {
+        let (rows, _, _) = self.resp_to_rows(&self.obj, resp_body, columns)?;
+        if let Some(row) = rows.into_iter().next() {
+            returning.replace_with(row);
+        }
+        Ok(())
+    }
+
+    // create an object and return the response body
+    fn insert_object(&mut self, src: &Row) -> StripeFdwResult<Option<String>> {
+        if let Some(ref mut client) = self.client {
+            let url = self.base_url.join(&self.obj)?;
+            let body = row_to_body(src)?;
+            if body.is_null() {
+                return Ok(None);
+            }
+
+            let mut stats_metadata = get_stats_metadata();
+
+            inc_stats_request_cnt(&mut stats_metadata)?;
+
+            // call Stripe API
+            let body = block_on_interruptible(&self.rt, client.post(url).form(&body).send())
+                .and_then(|resp| {
+                    resp.error_for_status()
+                        .and_then(|resp| {
+                            stats::inc_stats(
+                                Self::FDW_NAME,
+                                stats::Metric::BytesIn,
+                                resp.content_length().unwrap_or(0) as i64,
+                            );
+                            block_on_interruptible(&self.rt, resp.text())
+                        })
+                        .map_err(reqwest_middleware::Error::from)
+                })?;
+
+            let json: JsonValue = serde_json::from_str(&body)?;
+            if let Some(id) = json.get("id").and_then(|v| v.as_str()) {
+                report_info(&format!("inserted {} {}", self.obj, id));
+            }
+
+            set_stats_metadata(stats_metadata);
+            return Ok(Some(body));
+        }
+        Ok(None)
+    }
+
+    // update an object and return the response body
+    fn update_object(&mut self, rowid: &Cell, new_row: &Row) -> StripeFdwResult<Option<String>> {
+        if let Some(ref mut client) = self.client {
+            let mut stats_metadata = get_stats_metadata();
+
+            match rowid {
+                Cell::String(rowid) => {
+                    let url = self.base_url.join(&format!("{}/", self.obj))?.join(rowid)?;
+                    let body = row_to_body(new_row)?;
+                    if body.is_null() {
+                        return Ok(None);
+                    }
+
+                    inc_stats_request_cnt(&mut stats_metadata)?;
+
+                    // call Stripe API
+                    let body =
+                        block_on_interruptible(&self.rt, client.post(url).form(&body).send())
+                            .and_then(|resp| {
+                                resp.error_for_status()
+                                    .and_then(|resp| {
+                                        stats::inc_stats(
+                                            Self::FDW_NAME,
+                                            stats::Metric::BytesIn,
+                                            resp.content_length().unwrap_or(0) as i64,
+                                        );
+                                        block_on_interruptible(&self.rt, resp.text())
+                                    })
+                                    .map_err(reqwest_middleware::Error::from)
+                            })?;
+
+                    let json: JsonValue = serde_json::from_str(&body)?;
+                    if let Some(id) = json.get("id").and_then(|v| v.as_str()) {
+                        report_info(&format!("updated {} {}", self.obj, id));
+                    }
+
+                    set_stats_metadata(stats_metadata);
+                    Ok(Some(body))
+                }
+                _ => unreachable!(),
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    // delete an object and return the response body
+    fn delete_object(&mut self, rowid: &Cell) -> StripeFdwResult<Option<String>> {
+        if let Some(ref mut client) = self.client {
+            let mut stats_metadata = get_stats_metadata();
+
+            match rowid {
+                Cell::String(rowid) => {
+                    let url = self.base_url.join(&format!("{}/", self.obj))?.join(rowid)?;
+
+                    inc_stats_request_cnt(&mut stats_metadata)?;
+
+                    // call Stripe API
+                    let body = block_on_interruptible(&self.rt, client.delete(url).send())
+                        .and_then(|resp| {
+                            resp.error_for_status()
+                                .and_then(|resp| {
+                                    stats::inc_stats(
+                                        Self::FDW_NAME,
+                                        stats::Metric::BytesIn,
+                                        resp.content_length().unwrap_or(0) as i64,
+                                    );
+                                    block_on_interruptible(&self.rt, resp.text())
+                                })
+                                .map_err(reqwest_middleware::Error::from)
+                        })?;
+
+                    let json: JsonValue = serde_json::from_str(&body)?;
+                    if let Some(id) = json.get("id").and_then(|v| v.as_str()) {
+                        report_info(&format!("deleted {} {}", self.obj, id));
+                    }
+
+                    set_stats_metadata(stats_metadata);
+                    Ok(Some(body))
+                }
+                _ => unreachable!(),
+            }
+        } else {
+            Ok(None)
+        }
+    }
 }
 
 impl ForeignDataWrapper<StripeFdwError> for StripeFdw {
@@ -734,15 +1030,15 @@ impl ForeignDataWrapper<StripeFdwError> for StripeFdw {
             })
             .unwrap_or_else(|| "https://api.stripe.com/v1/".to_string());
         let api_version = server.options.get("api_version").map(|t| t.as_str());
-        let client = match server.options.get("api_key") {
+        // credentials can be from user mapping or server options
+        let cred_opts = server.credential_options(&["api_key", "api_key_id", "api_key_name"]);
+        let client = match cred_opts.get("api_key") {
             Some(api_key) => Some(create_client(api_key, api_version)),
-            None => server
-                .options
+            None => cred_opts
                 .get("api_key_id")
                 .and_then(|key_id| get_vault_secret(key_id))
                 .or_else(|| {
-                    server
-                        .options
+                    cred_opts
                         .get("api_key_name")
                         .and_then(|key_name| get_vault_secret_by_name(key_name))
                 })
@@ -764,10 +1060,90 @@ impl ForeignDataWrapper<StripeFdwError> for StripeFdw {
             base_url: Url::parse(&base_url)?,
             client,
             scan_result: None,
+            scan_task: None,
             obj: String::default(),
             rowid_col: String::default(),
             iter_idx: 0,
             table_config: create_table_config(),
+            pages_fetched: 0,
+            bytes_in: 0,
+        })
+    }
+
+    fn get_scan_pushdown(
+        &mut self,
+        quals: &[Qual],
+        _sorts: &[Sort],
+        _limit: &Option<Limit>,
+        options: &HashMap<String, String>,
+    ) -> StripeFdwResult<Option<Pushdown>> {
+        let obj = require_option("object", options)?;
+        let fields = self
+            .table_config
+            .get(obj)
+            .map(|v| v.1.clone())
+            .ok_or_else(|| StripeFdwError::ObjectNotImplemented(obj.to_string()))?;
+
+        // same rule as pushdown_quals(), only `=` on the supported fields and
+        // a single id qual can be applied by Stripe API
+        let is_eq = |qual: &Qual| qual.operator == "=" && !qual.use_or;
+        let quals = quals
+            .iter()
+            .enumerate()
+            .filter(|(_, qual)| {
+                if quals.len() == 1 && qual.field == "id" && is_eq(qual) {
+                    return matches!(qual.value, Value::Cell(Cell::String(_)));
+                }
+                fields.contains(&qual.field.as_str())
+                    && is_eq(qual)
+                    && matches!(qual.value, Value::Cell(Cell::Bool(_) | Cell::String(_)))
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        Ok(Some(Pushdown {
+            quals,
+            ..Default::default()
+        }))
+    }
+
+    fn get_path_costs(
+        &mut self,
+        _quals: &[Qual],
+        _sorts: &[Sort],
+        limit: &Option<Limit>,
+        rows: f64,
+        options: &HashMap<String, String>,
+    ) -> StripeFdwResult<(f64, f64)> {
+        // the cost is mainly decided by the number of API requests, one for
+        // each page of objects
+        let request_cost = require_option_f64_or("fdw_startup_cost", options, Self::REQUEST_COST)?;
+        let request_cost = require_option_f64_or("startup_cost", options, request_cost)?;
+        let tuple_cost = require_option_f64_or("fdw_tuple_cost", options, DEFAULT_FDW_TUPLE_COST)?;
+        let rows = limit
+            .as_ref()
+            .map(|limit| rows.min((limit.offset + limit.count) as f64))
+            .unwrap_or(rows);
+        let pages = (rows / Self::PAGE_SIZE).ceil().max(1.0);
+        Ok((request_cost, request_cost * pages + rows * tuple_cost))
+    }
+
+    fn get_lookup_columns(
+        &mut self,
+        options: &HashMap<String, String>,
+    ) -> StripeFdwResult<Vec<String>> {
+        let obj = require_option("object", options)?;
+
+        // objects with id column can be retrieved by id in a single request
+        let has_id = self
+            .table_config
+            .get(obj)
+            .map(|v| v.2.iter().any(|(col, _)| *col == "id"))
+            .unwrap_or(false);
+        Ok(if has_id {
+            vec!["id".to_string()]
+        } else {
+            Vec::new()
         })
     }
 
@@ -784,83 +1160,73 @@ impl ForeignDataWrapper<StripeFdwError> for StripeFdw {
         self.iter_idx = 0;
 
         if let Some(client) = &self.client {
-            let page_size = 100; // maximum page size limit for Stripe API
-            let page_cnt = if let Some(limit) = limit {
-                if limit.count == 0 {
-                    return Ok(());
-                }
-                (limit.offset + limit.count) / page_size + 1
-            } else {
-                // if no limit specified, fetch all records
-                i64::MAX
+            let Some((url, page_cnt)) = self.prepare_scan(obj, quals, limit)? else {
+                return Ok(());
             };
-            let mut page = 0;
-            let mut result = Vec::new();
-            let mut cursor: Option<String> = None;
-            let mut stats_metadata = get_stats_metadata();
-
-            while page < page_cnt {
-                // build url
-                let url = self.build_url(obj, quals, page_size, &cursor)?;
-                let Some(url) = url else {
-                    return Ok(());
-                };
-
-                inc_stats_request_cnt(&mut stats_metadata)?;
-
-                // make api call
-                let body = self.rt.block_on(client.get(url).send()).and_then(|resp| {
-                    stats::inc_stats(
-                        Self::FDW_NAME,
-                        stats::Metric::BytesIn,
-                        resp.content_length().unwrap_or(0) as i64,
-                    );
+            let fetched = block_on_interruptible(&self.rt, fetch_pages(client, &url, page_cnt))?;
+            self.save_scan_result(obj, columns, fetched)?;
+        }
 
-                    if resp.status() == StatusCode::NOT_FOUND {
-                        // if it is 404 error, we should treat it as an empty
-                        // result rather than a request error
-                        return Ok(String::new());
-                    }
+        Ok(())
+    }
 
-                    resp.error_for_status()
-                        .and_then(|resp| self.rt.block_on(resp.text()))
-                        .map_err(reqwest_middleware::Error::from)
-                })?;
-                if body.is_empty() {
-                    break;
-                }
+    fn is_async_capable(&mut self, options: &HashMap<String, String>) -> StripeFdwResult<bool> {
+        Ok(require_option_or("async_capable", options, "false") == "true")
+    }
 
-                // convert response body to rows
-                let (rows, starting_after, has_more) = self.resp_to_rows(obj, &body, columns)?;
-                if rows.is_empty() {
-                    break;
-                }
-                result.extend(rows);
-                match has_more {
-                    Some(has_more) => {
-                        if !has_more {
-                            break;
-                        }
-                    }
-                    None => break,
-                }
-                cursor = starting_after;
+    fn begin_async_scan(
+        &mut self,
+        quals: &[Qual],
+        columns: &[Column],
+        _sorts: &[Sort],
+        limit: &Option<Limit>,
+        options: &HashMap<String, String>,
+        notifier: &AsyncNotifier,
+    ) -> StripeFdwResult<()> {
+        let obj = require_option("object", options)?;
 
-                page += 1;
-            }
+        self.iter_idx = 0;
 
-            // save stats
-            stats::inc_stats(Self::FDW_NAME, stats::Metric::RowsIn, result.len() as i64);
-            stats::inc_stats(Self::FDW_NAME, stats::Metric::RowsOut, result.len() as i64);
-            set_stats_metadata(stats_metadata);
+        let scan = match &self.client {
+            Some(client) => self
+                .prepare_scan(obj, quals, limit)?
+                .map(|scan| (client.clone(), scan)),
+            None => None,
+        };
+        let Some((client, (url, page_cnt))) = scan else {
+            notifier.notify();
+            return Ok(());
+        };
 
-            self.scan_result = Some(result);
-        }
+        // fetch pages in a background thread with its own runtime, because the
+        // FDW's runtime can only be driven by current thread
+        let notifier = notifier.clone();
+        let handle = thread::spawn(move || {
+            let fetched = create_async_runtime()
+                .map_err(StripeFdwError::from)
+                .and_then(|rt| rt.block_on(fetch_pages(&client, &url, page_cnt)));
+            notifier.notify();
+            fetched
+        });
+        self.scan_task = Some(ScanTask {
+            obj: obj.to_string(),
+            columns: columns.to_vec(),
+            handle,
+        });
 
         Ok(())
     }
 
     fn iter_scan(&mut self, row: &mut Row) -> StripeFdwResult<Option<()>> {
+        // wait for the asynchronous scan to finish
+        if let Some(task) = self.scan_task.take() {
+            let fetched = task
+                .handle
+                .join()
+                .map_err(|_| StripeFdwError::AsyncScanFailed)??;
+            self.save_scan_result(&task.obj, &task.columns, fetched)?;
+        }
+
         if let Some(ref mut result) = self.scan_result {
             if self.iter_idx < result.len() {
                 row.replace_with(result[self.iter_idx].clone());
@@ -878,9 +1244,42 @@ impl ForeignDataWrapper<StripeFdwError> for StripeFdw {
 
     fn end_scan(&mut self) -> StripeFdwResult<()> {
         self.scan_result.take();
+        self.scan_task.take();
         Ok(())
     }
 
+    fn explain_scan(
+        &mut self,
+        quals: &[Qual],
+        _columns: &[Column],
+        _sorts: &[Sort],
+        _limit: &Option<Limit>,
+        options: &HashMap<String, String>,
+        explain: &ExplainOptions,
+    ) -> StripeFdwResult<Vec<ExplainProperty>> {
+        let mut props = Vec::new();
+        if explain.verbose {
+            let obj = require_option("object", options)?;
+            if let Some(url) = self.build_url(obj, quals, Self::PAGE_SIZE as i64, &None)? {
+                props.push(ExplainProperty::Text(
+                    "Remote URL".to_string(),
+                    url.to_string(),
+                ));
+            }
+        }
+        if explain.analyze {
+            props.push(ExplainProperty::Integer(
+                "Pages Fetched".to_string(),
+                self.pages_fetched,
+            ));
+            props.push(ExplainProperty::Integer(
+                "Bytes In".to_string(),
+                self.bytes_in,
+            ));
+        }
+        Ok(props)
+    }
+
     fn begin_modify(&mut self, options: &HashMap<String, String>) -> StripeFdwResult<()> {
         self.obj = require_option("object", options)?.to_string();
         self.rowid_col = require_option("rowid_column", options)?.to_string();
@@ -888,124 +1287,50 @@ impl ForeignDataWrapper<StripeFdwError> for StripeFdw {
     }
 
     fn insert(&mut self, src: &Row) -> StripeFdwResult<()> {
-        if let Some(ref mut client) = self.client {
-            let url = self.base_url.join(&self.obj)?;
-            let body = row_to_body(src)?;
-            if body.is_null() {
-                return Ok(());
-            }
-
-            let mut stats_metadata = get_stats_metadata();
-
-            inc_stats_request_cnt(&mut stats_metadata)?;
-
-            // call Stripe API
-            let body = self
-                .rt
-                .block_on(client.post(url).form(&body).send())
-                .and_then(|resp| {
-                    resp.error_for_status()
-                        .and_then(|resp| {
-                            stats::inc_stats(
-                                Self::FDW_NAME,
-                                stats::Metric::BytesIn,
-                                resp.content_length().unwrap_or(0) as i64,
-                            );
-                            self.rt.block_on(resp.text())
-                        })
-                        .map_err(reqwest_middleware::Error::from)
-                })?;
-
-            let json: JsonValue = serde_json::from_str(&body)?;
-            if let Some(id) = json.get("id").and_then(|v| v.as_str()) {
-                report_info(&format!("inserted {} {}", self.obj, id));
-            }
+        self.insert_object(src).map(|_| ())
+    }
 
-            set_stats_metadata(stats_metadata);
+    fn insert_returning(
+        &mut self,
+        src: &Row,
+        columns: &[Column],
+        returning: &mut Row,
+    ) -> StripeFdwResult<()> {
+        if let Some(body) = self.insert_object(src)? {
+            self.fill_returning(&body, columns, returning)?;
         }
         Ok(())
     }
 
     fn update(&mut self, rowid: &Cell, new_row: &Row) -> StripeFdwResult<()> {
-        if let Some(ref mut client) = self.client {
-            let mut stats_metadata = get_stats_metadata();
-
-            match rowid {
-                Cell::String(rowid) => {
-                    let url = self.base_url.join(&format!("{}/", self.obj))?.join(rowid)?;
-                    let body = row_to_body(new_row)?;
-                    if body.is_null() {
-                        return Ok(());
-                    }
-
-                    inc_stats_request_cnt(&mut stats_metadata)?;
-
-                    // call Stripe API
-                    let body = self
-                        .rt
-                        .block_on(client.post(url).form(&body).send())
-                        .and_then(|resp| {
-                            resp.error_for_status()
-                                .and_then(|resp| {
-                                    stats::inc_stats(
-                                        Self::FDW_NAME,
-                                        stats::Metric::BytesIn,
-                                        resp.content_length().unwrap_or(0) as i64,
-                                    );
-                                    self.rt.block_on(resp.text())
-                                })
-                                .map_err(reqwest_middleware::Error::from)
-                        })?;
-
-                    let json: JsonValue = serde_json::from_str(&body)?;
-                    if let Some(id) = json.get("id").and_then(|v| v.as_str()) {
-                        report_info(&format!("updated {} {}", self.obj, id));
-                    }
-                }
-                _ => unreachable!(),
-            }
+        self.update_object(rowid, new_row).map(|_| ())
+    }
 
-            set_stats_metadata(stats_metadata);
+    fn update_returning(
+        &mut self,
+        rowid: &Cell,
+        new_row: &Row,
+        columns: &[Column],
+        returning: &mut Row,
+    ) -> StripeFdwResult<()> {
+        if let Some(body) = self.update_object(rowid, new_row)? {
+            self.fill_returning(&body, columns, returning)?;
         }
         Ok(())
     }
 
     fn delete(&mut self, rowid: &Cell) -> StripeFdwResult<()> {
-        if let Some(ref mut client) = self.client {
-            let mut stats_metadata = get_stats_metadata();
-
-            match rowid {
-                Cell::String(rowid) => {
-                    let url = self.base_url.join(&format!("{}/", self.obj))?.join(rowid)?;
-
-                    inc_stats_request_cnt(&mut stats_metadata)?;
-
-                    // call Stripe API
-                    let body = self
-                        .rt
-                        .block_on(client.delete(url).send())
-                        .and_then(|resp| {
-                            resp.error_for_status()
-                                .and_then(|resp| {
-                                    stats::inc_stats(
-                                        Self::FDW_NAME,
-                                        stats::Metric::BytesIn,
-                                        resp.content_length().unwrap_or(0) as i64,
-                                    );
-                                    self.rt.block_on(resp.text())
-                                })
-                                .map_err(reqwest_middleware::Error::from)
-                        })?;
-
-                    let json: JsonValue = serde_json::from_str(&body)?;
-                    if let Some(id) = json.get("id").and_then(|v| v.as_str()) {
-                        report_info(&format!("deleted {} {}", self.obj, id));
-                    }
-                }
-                _ => unreachable!(),
-            }
+        self.delete_object(rowid).map(|_| ())
+    }
 
-            set_stats_metadata(stats_metadata);
+    fn delete_returning(
+        &mut self,
+        rowid: &Cell,
+        columns: &[Column],
+        returning: &mut Row,
+    ) -> StripeFdwResult<()> {
+        if let Some(body) = self.delete_object(rowid)? {
+            self.fill_returning(&body, columns, returning)?;
         }
         Ok(())
     }
@@ -1054,16 +1379,28 @@ impl ForeignDataWrapper<StripeFdwError> for StripeFdw {
         Ok(ret)
     }
 
-    fn validator(
-        options: Vec<Option<String>>,
-        catalog: Option<pg_sys::Oid>,
-    ) -> StripeFdwResult<()> {
-        if let Some(oid) = catalog {
-            if oid == FOREIGN_TABLE_RELATION_ID {
-                check_options_contain(&options, "object")?;
-            }
-        }
-
-        Ok(())
+    fn option_defs() -> Vec<OptionDef> {
+        const CRED: &[OptionContext] = &[OptionContext::Server, OptionContext::UserMapping];
+        vec![
+            OptionDef::new("api_key", CRED)
+                .secret()
+                .description("Stripe API key"),
+            OptionDef::new("api_key_id", CRED).description("Vault secret ID of the API key"),
+            OptionDef::new("api_key_name", CRED).description("Vault secret name of the API key"),
+            OptionDef::new("api_url", &[OptionContext::Server])
+                .default("https://api.stripe.com/v1/")
+                .description("Stripe API base URL"),
+            OptionDef::new("api_version", &[OptionContext::Server])
+                .description("Stripe API version, default is the account's default API version"),
+            OptionDef::new("object", &[OptionContext::Table])
+                .required()
+                .description("Stripe object name, for example `customers`"),
+            OptionDef::new("rowid_column", &[OptionContext::Table])
+                .description("Primary key column name, required for data modify"),
+            OptionDef::new("async_capable", &[OptionContext::Table])
+                .opt_type(OptionType::Bool)
+                .default("false")
+                .description("Whether to scan the table asynchronously under an Append node"),
+        ]
     }
 }
@@ -25,6 +25,43 @@ mod tests {
             ).unwrap();
             c.update(r#"CREATE SCHEMA IF NOT EXISTS stripe"#, None, &[])
                 .unwrap();
+            c.update(
+                r#"CREATE SERVER my_stripe_server_um
+                         FOREIGN DATA WRAPPER stripe_wrapper
+                         OPTIONS (
+                           api_url 'http://localhost:12111/v1'
+                         )"#,
+                None,
+                &[],
+            )
+            .unwrap();
+            c.update(
+                r#"CREATE USER MAPPING FOR CURRENT_USER
+                         SERVER my_stripe_server_um
+                         OPTIONS (
+                           api_key 'sk_test_51LUmojFkiV6mfx3cpEzG9VaxhA86SA4DIj3b62RKHnRC0nhPp2JBbAmQ1izsX9RKD8rlzvw2xpY54AwZtXmWciif00Qi8J0w3O'
+                         )"#,
+                None,
+                &[],
+            ).unwrap();
+            c.update(
+                r#"CREATE SERVER my_stripe_server_um2
+                         FOREIGN DATA WRAPPER stripe_wrapper
+                         OPTIONS (
+                           api_url 'http://localhost:12111/v1'
+                         )"#,
+                None,
+                &[],
+            )
+            .unwrap();
+            c.update(
+                r#"CREATE FOREIGN TABLE stripe.customers_um (id text)
+                         SERVER my_stripe_server_um
+                         OPTIONS (object 'customers')"#,
+                None,
+                &[],
+            )
+            .unwrap();
             c.update(
                 r#"IMPORT FOREIGN SCHEMA stripe FROM SERVER my_stripe_server INTO stripe"#,
                 None,
@@ -128,6 +165,66 @@ mod tests {
                 )]
             );
 
+            // test api key is from user mapping
+            let results = c
+                .select("SELECT id FROM stripe.customers_um", None, &[])
+                .unwrap()
+                .filter_map(|r| r.get_by_name::<&str, _>("id").unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(results, vec!["cus_QXg1o8vcGmoR32"]);
+
+            // test user mapping of the view owner is used when the table is
+            // queried through a view, current user has no user mapping
+            c.update("CREATE ROLE stripe_view_owner", None, &[])
+                .unwrap();
+            c.update(
+                r#"CREATE USER MAPPING FOR stripe_view_owner
+                         SERVER my_stripe_server_um2
+                         OPTIONS (
+                           api_key 'sk_test_51LUmojFkiV6mfx3cpEzG9VaxhA86SA4DIj3b62RKHnRC0nhPp2JBbAmQ1izsX9RKD8rlzvw2xpY54AwZtXmWciif00Qi8J0w3O'
+                         )"#,
+                None,
+                &[],
+            ).unwrap();
+            c.update(
+                r#"CREATE FOREIGN TABLE stripe.customers_um2 (id text)
+                         SERVER my_stripe_server_um2
+                         OPTIONS (object 'customers')"#,
+                None,
+                &[],
+            )
+            .unwrap();
+            c.update(
+                "CREATE VIEW stripe.customers_um2_view AS SELECT id FROM stripe.customers_um2",
+                None,
+                &[],
+            )
+            .unwrap();
+            c.update(
+                "ALTER VIEW stripe.customers_um2_view OWNER TO stripe_view_owner",
+                None,
+                &[],
+            )
+            .unwrap();
+            c.update(
+                "GRANT USAGE ON SCHEMA stripe TO stripe_view_owner",
+                None,
+                &[],
+            )
+            .unwrap();
+            c.update(
+                "GRANT SELECT ON stripe.customers_um2 TO stripe_view_owner",
+                None,
+                &[],
+            )
+            .unwrap();
+            let results = c
+                .select("SELECT id FROM stripe.customers_um2_view", None, &[])
+                .unwrap()
+                .filter_map(|r| r.get_by_name::<&str, _>("id").unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(results, vec!["cus_QXg1o8vcGmoR32"]);
+
             let results = c
                 .select("SELECT attrs->>'id' as id FROM stripe.customers", None, &[])
                 .unwrap()
@@ -135,6 +232,101 @@ mod tests {
                 .collect::<Vec<_>>();
             assert_eq!(results, vec!["cus_QXg1o8vcGmoR32"]);
 
+            // test quals applied by Stripe API are shown in EXPLAIN
+            let results = c
+                .select(
+                    "EXPLAIN SELECT * FROM stripe.customers WHERE email = 'test@test.com' AND name = 'foo'",
+                    None,
+                    &[],
+                )
+                .unwrap()
+                .filter_map(|r| r.get_by_name::<&str, _>("QUERY PLAN").unwrap())
+                .filter(|line| line.contains("Remote Filter"))
+                .collect::<Vec<_>>();
+            assert_eq!(results.len(), 1);
+            assert!(results[0].ends_with("Remote Filter: email = 'test@test.com'"));
+
+            // test lookup by id as the inner side of nested loop join, the
+            // scan is restarted for each outer row with the parameter changed,
+            // and stripe-mock reflects the requested id in the response
+            c.update("SET enable_hashjoin = off", None, &[]).unwrap();
+            c.update("SET enable_mergejoin = off", None, &[]).unwrap();
+            let sql = r#"SELECT c.id
+                FROM (VALUES (1, 'cus_QXg1o8vcGmoR32'), (2, 'cus_QXg1o8vcGmoR33')) AS v(n, id)
+                JOIN stripe.customers c ON c.id = v.id
+                ORDER BY v.n"#;
+            let plan = c
+                .select(&format!("EXPLAIN (COSTS OFF) {}", sql), None, &[])
+                .unwrap()
+                .filter_map(|r| r.get_by_name::<&str, _>("QUERY PLAN").unwrap())
+                .map(|line| line.trim().to_string())
+                .collect::<Vec<_>>();
+            assert!(plan.iter().any(|line| line.starts_with("Nested Loop")));
+            assert!(!plan.iter().any(|line| line.starts_with("Join Filter")));
+            let scan_pos = plan
+                .iter()
+                .position(|line| line.contains("Foreign Scan on customers c"))
+                .expect("foreign scan in plan");
+            assert!(plan[scan_pos + 1..]
+                .iter()
+                .any(|line| line.starts_with("Filter:") && line.contains("v.id")));
+            let results = c
+                .select(sql, None, &[])
+                .unwrap()
+                .filter_map(|r| r.get_by_name::<&str, _>("id").unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(results, vec!["cus_QXg1o8vcGmoR32", "cus_QXg1o8vcGmoR33"]);
+
+            // the text parameter is not evaluated before the outer relation
+            // produces a row, including when it produces no rows at all
+            c.update("CREATE TEMP TABLE stripe_cus_ids (id text)", None, &[])
+                .unwrap();
+            let sql = r#"SELECT c.id
+                FROM stripe_cus_ids v
+                JOIN stripe.customers c ON c.id = v.id
+                ORDER BY v.id"#;
+            assert!(c.select(sql, None, &[]).unwrap().is_empty());
+            c.update(
+                "INSERT INTO stripe_cus_ids VALUES ('cus_QXg1o8vcGmoR33'), ('cus_QXg1o8vcGmoR34')",
+                None,
+                &[],
+            )
+            .unwrap();
+            let results = c
+                .select(sql, None, &[])
+                .unwrap()
+                .filter_map(|r| r.get_by_name::<&str, _>("id").unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(results, vec!["cus_QXg1o8vcGmoR33", "cus_QXg1o8vcGmoR34"]);
+            c.update("RESET enable_hashjoin", None, &[]).unwrap();
+            c.update("RESET enable_mergejoin", None, &[]).unwrap();
+
+            // test asynchronous scans under Append
+            c.update(
+                "ALTER FOREIGN TABLE stripe.customers OPTIONS (ADD async_capable 'true')",
+                None,
+                &[],
+            )
+            .unwrap();
+            let results = c
+                .select(
+                    r#"SELECT id FROM stripe.customers
+                    UNION ALL
+                    SELECT id FROM stripe.customers"#,
+                    None,
+                    &[],
+                )
+                .unwrap()
+                .filter_map(|r| r.get_by_name::<&str, _>("id").unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(results, vec!["cus_QXg1o8vcGmoR32", "cus_QXg1o8vcGmoR32"]);
+            c.update(
+                "ALTER FOREIGN TABLE stripe.customers OPTIONS (DROP async_capable)",
+                None,
+                &[],
+            )
+            .unwrap();
+
             let results = c
                 .select(
                     "SELECT id, display_name FROM stripe.billing_meters",
@@ -440,6 +632,21 @@ mod tests {
                 )]
             );
 
+            // test insert with RETURNING, the id is generated by Stripe so it
+            // must come from the created object
+            let id = c
+                .update(
+                    "INSERT INTO stripe.customers(email) VALUES ('test@test.com') RETURNING id",
+                    None,
+                    &[],
+                )
+                .unwrap()
+                .first()
+                .get_one::<&str>()
+                .unwrap()
+                .expect("RETURNING id should not be null");
+            assert!(id.starts_with("cus_"));
+
             // Stripe mock container is currently stateless, so we cannot test
             // data modify for now but will keep the code below for future use.
             //
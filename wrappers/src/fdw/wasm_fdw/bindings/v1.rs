@@ -53,9 +53,11 @@ impl TryFrom<GuestCell> for HostCell {
     }
 }
 
-impl From<&HostCell> for GuestCell {
-    fn from(value: &HostCell) -> Self {
-        match value {
+impl TryFrom<&HostCell> for GuestCell {
+    type Error = WasmError;
+
+    fn try_from(value: &HostCell) -> Result<Self, Self::Error> {
+        let ret = match value {
             HostCell::Bool(v) => Self::Bool(*v),
             HostCell::I8(v) => Self::I8(*v),
             HostCell::I16(v) => Self::I16(*v),
@@ -79,18 +81,32 @@ impl From<&HostCell> for GuestCell {
                 Self::Timestamptz(v.into_inner() + PG_EPOCH_MS)
             }
             HostCell::Json(v) => Self::Json(v.0.to_string()),
-            _ => todo!("Add array type support for Wasm FDW"),
-        }
+            // these types are passed to guest as their text representation
+            HostCell::Inet(v) | HostCell::Cidr(v) | HostCell::MacAddr(v) => Self::String(v.clone()),
+            HostCell::Enum(_, v) | HostCell::TypedText(_, v) => Self::String(v.clone()),
+            _ => {
+                return Err(WasmError::msg(format!(
+                    "unsupported cell type for Wasm FDW: {:?}",
+                    value
+                )))
+            }
+        };
+        Ok(ret)
     }
 }
 
-impl From<HostValue> for GuestValue {
-    fn from(value: HostValue) -> Self {
+impl TryFrom<HostValue> for GuestValue {
+    type Error = WasmError;
+
+    fn try_from(value: HostValue) -> Result<Self, Self::Error> {
         match value {
-            HostValue::Cell(c) => Self::Cell(GuestCell::from(&c)),
+            HostValue::Cell(c) => GuestCell::try_from(&c).map(Self::Cell),
             HostValue::Array(a) => {
-                let a: Vec<GuestCell> = a.iter().map(GuestCell::from).collect();
-                Self::Array(a)
+                let a = a
+                    .iter()
+                    .map(GuestCell::try_from)
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Self::Array(a))
             }
         }
     }
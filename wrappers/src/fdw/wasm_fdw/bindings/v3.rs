@@ -0,0 +1,5 @@
+use wasmtime::component::bindgen;
+
+bindgen!("wrappers" in "../wasm-wrappers/wit/v3");
+
+impl_type_conversions!();
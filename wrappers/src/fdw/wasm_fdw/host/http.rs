@@ -4,6 +4,7 @@ use reqwest::{
 };
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+use supabase_wrappers::prelude::block_on_interruptible;
 
 use super::super::bindings::v1::supabase::wrappers::http::{
     Headers as GuestHeaders, HttpError as GuestHttpError,
@@ -66,25 +67,24 @@ const _: () = {
         // make a http request
         fn http_request(&mut self, req: http::Request) -> http::HttpResult {
             let client = create_client(&req.headers)?;
-            let resp = self
-                .rt
-                .block_on(
-                    match req.method {
-                        http::Method::Get => client.get(req.url),
-                        http::Method::Post => client.post(req.url),
-                        http::Method::Put => client.put(req.url),
-                        http::Method::Patch => client.patch(req.url),
-                        http::Method::Delete => client.delete(req.url),
-                    }
-                    .body(req.body)
-                    .send(),
-                )
-                .map_err(|e| e.to_string())?;
+            let resp = block_on_interruptible(
+                &self.rt,
+                match req.method {
+                    http::Method::Get => client.get(req.url),
+                    http::Method::Post => client.post(req.url),
+                    http::Method::Put => client.put(req.url),
+                    http::Method::Patch => client.patch(req.url),
+                    http::Method::Delete => client.delete(req.url),
+                }
+                .body(req.body)
+                .send(),
+            )
+            .map_err(|e| e.to_string())?;
 
             let url = resp.url().to_string();
             let status_code = resp.status().as_u16();
             let headers = header_map_to_guest(resp.headers());
-            let body = self.rt.block_on(resp.text()).map_err(|e| e.to_string())?;
+            let body = block_on_interruptible(&self.rt, resp.text()).map_err(|e| e.to_string())?;
             Ok(http::Response {
                 url,
                 status_code,
@@ -185,3 +185,68 @@ const _: () = {
         }
     }
 };
+
+const _: () = {
+    use super::super::bindings::v2::supabase::wrappers::http as http_v2;
+    use super::super::bindings::v3::supabase::wrappers::http;
+
+    impl From<http::Method> for http_v2::Method {
+        fn from(m: http::Method) -> Self {
+            match m {
+                http::Method::Get => http_v2::Method::Get,
+                http::Method::Post => http_v2::Method::Post,
+                http::Method::Put => http_v2::Method::Put,
+                http::Method::Patch => http_v2::Method::Patch,
+                http::Method::Delete => http_v2::Method::Delete,
+            }
+        }
+    }
+
+    impl From<http::Request> for http_v2::Request {
+        fn from(r: http::Request) -> Self {
+            Self {
+                method: r.method.into(),
+                url: r.url,
+                headers: r.headers,
+                body: r.body,
+            }
+        }
+    }
+
+    impl From<http_v2::Response> for http::Response {
+        fn from(r: http_v2::Response) -> Self {
+            Self {
+                url: r.url,
+                status_code: r.status_code,
+                headers: r.headers,
+                body: r.body,
+            }
+        }
+    }
+
+    impl http::Host for FdwHost {
+        fn get(&mut self, req: http::Request) -> http::HttpResult {
+            http_v2::Host::get(self, req.into()).map(|r| r.into())
+        }
+
+        fn post(&mut self, req: http::Request) -> http::HttpResult {
+            http_v2::Host::post(self, req.into()).map(|r| r.into())
+        }
+
+        fn put(&mut self, req: http::Request) -> http::HttpResult {
+            http_v2::Host::put(self, req.into()).map(|r| r.into())
+        }
+
+        fn patch(&mut self, req: http::Request) -> http::HttpResult {
+            http_v2::Host::patch(self, req.into()).map(|r| r.into())
+        }
+
+        fn delete(&mut self, req: http::Request) -> http::HttpResult {
+            http_v2::Host::delete(self, req.into()).map(|r| r.into())
+        }
+
+        fn error_for_status(&mut self, resp: http::Response) -> Result<(), http::HttpError> {
+            error_for_status(resp.status_code, &resp.url)
+        }
+    }
+};
@@ -52,3 +52,20 @@ const _: () = {
         }
     }
 };
+
+const _: () = {
+    use super::super::bindings::v2::supabase::wrappers::jwt as jwt_v2;
+    use super::super::bindings::v3::supabase::wrappers::jwt;
+
+    impl jwt::Host for FdwHost {
+        fn encode(
+            &mut self,
+            payload: Vec<(String, String)>,
+            algo: String,
+            key: String,
+            ttl_hours: u32,
+        ) -> jwt::JwtResult {
+            jwt_v2::Host::encode(self, payload, algo, key, ttl_hours)
+        }
+    }
+};
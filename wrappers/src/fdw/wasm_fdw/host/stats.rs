@@ -52,3 +52,34 @@ const _: () = {
         }
     }
 };
+
+const _: () = {
+    use super::super::bindings::v2::supabase::wrappers::stats as stats_v2;
+    use super::super::bindings::v3::supabase::wrappers::stats;
+
+    impl From<stats::Metric> for stats_v2::Metric {
+        fn from(m: stats::Metric) -> Self {
+            match m {
+                stats::Metric::CreateTimes => stats_v2::Metric::CreateTimes,
+                stats::Metric::RowsIn => stats_v2::Metric::RowsIn,
+                stats::Metric::RowsOut => stats_v2::Metric::RowsOut,
+                stats::Metric::BytesIn => stats_v2::Metric::BytesIn,
+                stats::Metric::BytesOut => stats_v2::Metric::BytesOut,
+            }
+        }
+    }
+
+    impl stats::Host for FdwHost {
+        fn inc_stats(&mut self, fdw_name: String, metric: stats::Metric, inc: i64) {
+            stats_v2::Host::inc_stats(self, fdw_name, metric.into(), inc);
+        }
+
+        fn get_metadata(&mut self, fdw_name: String) -> Option<String> {
+            stats_v2::Host::get_metadata(self, fdw_name)
+        }
+
+        fn set_metadata(&mut self, fdw_name: String, metadata: Option<String>) {
+            stats_v2::Host::set_metadata(self, fdw_name, metadata);
+        }
+    }
+};
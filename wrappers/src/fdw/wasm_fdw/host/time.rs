@@ -1,5 +1,6 @@
 use chrono::DateTime;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use supabase_wrappers::prelude::block_on_interruptible;
 
 use super::FdwHost;
 
@@ -33,7 +34,8 @@ const _: () = {
         }
 
         fn sleep(&mut self, millis: u64) {
-            std::thread::sleep(std::time::Duration::from_millis(millis));
+            // sleep on runtime, so it can be interrupted by query cancellation
+            block_on_interruptible(&self.rt, tokio::time::sleep(Duration::from_millis(millis)));
         }
     }
 };
@@ -64,3 +66,30 @@ const _: () = {
         }
     }
 };
+
+const _: () = {
+    use super::super::bindings::v2::supabase::wrappers::time as time_v2;
+    use super::super::bindings::v3::supabase::wrappers::time;
+
+    impl time::Host for FdwHost {
+        fn epoch_secs(&mut self) -> i64 {
+            time_v2::Host::epoch_secs(self)
+        }
+
+        fn parse_from_rfc3339(&mut self, s: String) -> time::TimeResult {
+            time_v2::Host::parse_from_rfc3339(self, s)
+        }
+
+        fn parse_from_str(&mut self, s: String, fmt: String) -> time::TimeResult {
+            time_v2::Host::parse_from_str(self, s, fmt)
+        }
+
+        fn epoch_ms_to_rfc3339(&mut self, msecs: i64) -> Result<String, time::TimeError> {
+            time_v2::Host::epoch_ms_to_rfc3339(self, msecs)
+        }
+
+        fn sleep(&mut self, millis: u64) {
+            time_v2::Host::sleep(self, millis)
+        }
+    }
+};
@@ -397,6 +397,27 @@ mod tests {
                 .filter_map(|r| r.get_by_name::<&str, _>("user_id").unwrap())
                 .collect::<Vec<_>>();
             assert_eq!(results, vec!["8527", "8528"]);
+
+            // unknown options are passed to the Wasm package
+            c.update(
+                "ALTER FOREIGN TABLE hubspot_table OPTIONS (ADD any_option 'foo')",
+                None,
+                &[],
+            )
+            .unwrap();
+        });
+
+        // but the values of common options are still validated
+        let result = std::panic::catch_unwind(|| {
+            Spi::connect_mut(|c| {
+                c.update(
+                    "ALTER FOREIGN TABLE hubspot_table OPTIONS (ADD fdw_tuple_cost 'abc')",
+                    None,
+                    &[],
+                )
+                .is_err()
+            })
         });
+        assert!(result.is_err());
     }
 }
@@ -24,12 +24,20 @@ use super::bindings::v2::{
     },
     Wrappers as WrappersV2,
 };
+use super::bindings::v3::{
+    supabase::wrappers::types::{
+        Cell as GuestCellV3, HostContext as HostContextV3, HostRow as HostRowV3,
+        ImportForeignSchemaStmt as GuestImportForeignSchemaStmtV3, Row as GuestRowV3,
+    },
+    Wrappers as WrappersV3,
+};
 use super::host::FdwHost;
 use super::{WasmFdwError, WasmFdwResult};
 
 enum Bindings {
     V1(WrappersV1),
     V2(WrappersV2),
+    V3(WrappersV3),
 }
 
 // check minimal host version requirement, e.g, ">=1.2.3"
@@ -94,11 +102,10 @@ fn download_from_warg(
         ..Default::default()
     };
 
-    let client = rt.block_on(warg::FileSystemClient::new_with_config(
-        Some(&url),
-        &config,
-        None,
-    ))?;
+    let client = block_on_interruptible(
+        &rt,
+        warg::FileSystemClient::new_with_config(Some(&url), &config, None),
+    )?;
 
     let pkg_name = warg_protocol::registry::PackageName::new(name)
         .map_err(|e| format!("invalid package name '{}': {}", name, e))?;
@@ -106,8 +113,7 @@ fn download_from_warg(
     let ver = semver::VersionReq::parse(version)
         .map_err(|e| format!("invalid version requirement '{}': {}", version, e))?;
 
-    let pkg = rt
-        .block_on(client.download(&pkg_name, &ver))?
+    let pkg = block_on_interruptible(rt, client.download(&pkg_name, &ver))?
         .ok_or_else(|| format!("{}@{} not found on {}", name, version, url))?;
 
     load_component_from_file(engine, pkg.path)
@@ -177,8 +183,7 @@ fn download_and_verify(
     url: reqwest::Url,
     expected_checksum: &str,
 ) -> WasmFdwResult<Bytes> {
-    let resp = rt
-        .block_on(reqwest::get(url.clone()))
+    let resp = block_on_interruptible(rt, reqwest::get(url.clone()))
         .map_err(|_| "failed to download component".to_string())?;
 
     if !resp.status().is_success() {
@@ -187,8 +192,7 @@ fn download_and_verify(
             .into());
     }
 
-    let bytes = rt
-        .block_on(resp.bytes())
+    let bytes = block_on_interruptible(rt, resp.bytes())
         .map_err(|_| "failed to read component data".to_string())?;
 
     let actual_checksum = hex::encode(Sha256::digest(&bytes));
@@ -210,7 +214,7 @@ fn save_to_cache(path: &Path, bytes: &[u8]) -> WasmFdwResult<()> {
 }
 
 #[wrappers_fdw(
-    version = "0.1.5",
+    version = "0.1.6",
     author = "Supabase",
     website = "https://github.com/supabase/wrappers/tree/main/wrappers/src/fdw/wasm_fdw",
     error_type = "WasmFdwError"
@@ -221,6 +225,23 @@ pub(crate) struct WasmFdw {
 }
 
 impl WasmFdw {
+    // check if the cell can be passed to the guest
+    fn check_cell(&self, cell: &Cell) -> WasmFdwResult<()> {
+        match &self.bindings {
+            Bindings::V1(_) => GuestCellV1::try_from(cell).map(|_| ())?,
+            Bindings::V2(_) => GuestCellV2::try_from(cell).map(|_| ())?,
+            Bindings::V3(_) => GuestCellV3::try_from(cell).map(|_| ())?,
+        }
+        Ok(())
+    }
+
+    fn check_row(&self, row: &Row) -> WasmFdwResult<()> {
+        for cell in row.cells.iter().flatten() {
+            self.check_cell(cell)?;
+        }
+        Ok(())
+    }
+
     fn call_host_version_requirement(&mut self) -> WasmFdwResult<String> {
         let ret = match &self.bindings {
             Bindings::V1(b) => b
@@ -229,6 +250,9 @@ impl WasmFdw {
             Bindings::V2(b) => b
                 .supabase_wrappers_routines()
                 .call_host_version_requirement(&mut self.store)?,
+            Bindings::V3(b) => b
+                .supabase_wrappers_routines()
+                .call_host_version_requirement(&mut self.store)?,
         };
         Ok(ret.to_string())
     }
@@ -245,6 +269,11 @@ impl WasmFdw {
                 b.supabase_wrappers_routines()
                     .call_init(&mut self.store, ctx)??;
             }
+            Bindings::V3(b) => {
+                let ctx = HostContextV3::new(self.store.data_mut());
+                b.supabase_wrappers_routines()
+                    .call_init(&mut self.store, ctx)??;
+            }
         }
         Ok(())
     }
@@ -261,6 +290,11 @@ impl WasmFdw {
                 b.supabase_wrappers_routines()
                     .call_begin_scan(&mut self.store, ctx)??;
             }
+            Bindings::V3(b) => {
+                let ctx = HostContextV3::new(self.store.data_mut());
+                b.supabase_wrappers_routines()
+                    .call_begin_scan(&mut self.store, ctx)??;
+            }
         }
         Ok(())
     }
@@ -279,6 +313,12 @@ impl WasmFdw {
                 b.supabase_wrappers_routines()
                     .call_iter_scan(&mut self.store, ctx, host_row)??
             }
+            Bindings::V3(b) => {
+                let ctx = HostContextV3::new(self.store.data_mut());
+                let host_row = HostRowV3::new(self.store.data_mut());
+                b.supabase_wrappers_routines()
+                    .call_iter_scan(&mut self.store, ctx, host_row)??
+            }
         };
         Ok(ret)
     }
@@ -295,6 +335,11 @@ impl WasmFdw {
                 b.supabase_wrappers_routines()
                     .call_re_scan(&mut self.store, ctx)??;
             }
+            Bindings::V3(b) => {
+                let ctx = HostContextV3::new(self.store.data_mut());
+                b.supabase_wrappers_routines()
+                    .call_re_scan(&mut self.store, ctx)??;
+            }
         }
         Ok(())
     }
@@ -311,6 +356,11 @@ impl WasmFdw {
                 b.supabase_wrappers_routines()
                     .call_end_scan(&mut self.store, ctx)??;
             }
+            Bindings::V3(b) => {
+                let ctx = HostContextV3::new(self.store.data_mut());
+                b.supabase_wrappers_routines()
+                    .call_end_scan(&mut self.store, ctx)??;
+            }
         }
         Ok(())
     }
@@ -327,6 +377,11 @@ impl WasmFdw {
                 b.supabase_wrappers_routines()
                     .call_begin_modify(&mut self.store, ctx)??;
             }
+            Bindings::V3(b) => {
+                let ctx = HostContextV3::new(self.store.data_mut());
+                b.supabase_wrappers_routines()
+                    .call_begin_modify(&mut self.store, ctx)??;
+            }
         }
         Ok(())
     }
@@ -345,6 +400,17 @@ impl WasmFdw {
                 b.supabase_wrappers_routines()
                     .call_insert(&mut self.store, ctx, host_row)??;
             }
+            Bindings::V3(b) => {
+                let ctx = HostContextV3::new(self.store.data_mut());
+                let host_row = HostRowV3::new(self.store.data_mut());
+                let returning = Resource::<GuestRowV3>::new_own(FdwHost::RETURNING_ROW_REP);
+                b.supabase_wrappers_routines().call_insert(
+                    &mut self.store,
+                    ctx,
+                    host_row,
+                    returning,
+                )??;
+            }
         }
         Ok(())
     }
@@ -354,7 +420,7 @@ impl WasmFdw {
             Bindings::V1(b) => {
                 let ctx = HostContextV1::new(self.store.data_mut());
                 let host_row = HostRowV1::new(self.store.data_mut());
-                let cell = GuestCellV1::from(rowid);
+                let cell = GuestCellV1::try_from(rowid)?;
                 b.supabase_wrappers_routines().call_update(
                     &mut self.store,
                     ctx,
@@ -365,7 +431,7 @@ impl WasmFdw {
             Bindings::V2(b) => {
                 let ctx = HostContextV2::new(self.store.data_mut());
                 let host_row = HostRowV2::new(self.store.data_mut());
-                let cell = GuestCellV2::from(rowid);
+                let cell = GuestCellV2::try_from(rowid)?;
                 b.supabase_wrappers_routines().call_update(
                     &mut self.store,
                     ctx,
@@ -373,6 +439,19 @@ impl WasmFdw {
                     host_row,
                 )??;
             }
+            Bindings::V3(b) => {
+                let ctx = HostContextV3::new(self.store.data_mut());
+                let host_row = HostRowV3::new(self.store.data_mut());
+                let cell = GuestCellV3::try_from(rowid)?;
+                let returning = Resource::<GuestRowV3>::new_own(FdwHost::RETURNING_ROW_REP);
+                b.supabase_wrappers_routines().call_update(
+                    &mut self.store,
+                    ctx,
+                    &cell,
+                    host_row,
+                    returning,
+                )??;
+            }
         }
         Ok(())
     }
@@ -381,16 +460,27 @@ impl WasmFdw {
         match &self.bindings {
             Bindings::V1(b) => {
                 let ctx = HostContextV1::new(self.store.data_mut());
-                let cell = GuestCellV1::from(rowid);
+                let cell = GuestCellV1::try_from(rowid)?;
                 b.supabase_wrappers_routines()
                     .call_delete(&mut self.store, ctx, &cell)??;
             }
             Bindings::V2(b) => {
                 let ctx = HostContextV2::new(self.store.data_mut());
-                let cell = GuestCellV2::from(rowid);
+                let cell = GuestCellV2::try_from(rowid)?;
                 b.supabase_wrappers_routines()
                     .call_delete(&mut self.store, ctx, &cell)??;
             }
+            Bindings::V3(b) => {
+                let ctx = HostContextV3::new(self.store.data_mut());
+                let cell = GuestCellV3::try_from(rowid)?;
+                let returning = Resource::<GuestRowV3>::new_own(FdwHost::RETURNING_ROW_REP);
+                b.supabase_wrappers_routines().call_delete(
+                    &mut self.store,
+                    ctx,
+                    &cell,
+                    returning,
+                )??;
+            }
         }
         Ok(())
     }
@@ -407,6 +497,11 @@ impl WasmFdw {
                 b.supabase_wrappers_routines()
                     .call_end_modify(&mut self.store, ctx)??;
             }
+            Bindings::V3(b) => {
+                let ctx = HostContextV3::new(self.store.data_mut());
+                b.supabase_wrappers_routines()
+                    .call_end_modify(&mut self.store, ctx)??;
+            }
         }
         Ok(())
     }
@@ -428,6 +523,15 @@ impl WasmFdw {
                     &stmt,
                 )??
             }
+            Bindings::V3(b) => {
+                let ctx = HostContextV3::new(self.store.data_mut());
+                let stmt = GuestImportForeignSchemaStmtV3::from(stmt);
+                b.supabase_wrappers_routines().call_import_foreign_schema(
+                    &mut self.store,
+                    ctx,
+                    &stmt,
+                )??
+            }
         };
         Ok(ret)
     }
@@ -445,12 +549,18 @@ impl ForeignDataWrapper<WasmFdwError> for WasmFdw {
 
         let rt = create_async_runtime()?;
 
-        let mut config = Config::new();
-        config.wasm_component_model(true);
-        let engine = Engine::new(&config)?;
+        // engine and the compiled component are cached and reused across
+        // statements, only the store is created for each instance
+        let (engine, component) = server.get_or_create_cached(&(), || -> WasmFdwResult<_> {
+            let mut config = Config::new();
+            config.wasm_component_model(true);
+            let engine = Engine::new(&config)?;
+
+            let component =
+                download_component(&rt, &engine, pkg_url, pkg_name, pkg_version, pkg_checksum)?;
 
-        let component =
-            download_component(&rt, &engine, pkg_url, pkg_name, pkg_version, pkg_checksum)?;
+            Ok((engine, component))
+        })?;
 
         let mut fdw_host = FdwHost::new(rt);
         fdw_host.svr_opts.clone_from(&server.options);
@@ -458,12 +568,14 @@ impl ForeignDataWrapper<WasmFdwError> for WasmFdw {
         let mut linker = Linker::new(&engine);
         WrappersV1::add_to_linker(&mut linker, |host: &mut FdwHost| host)?;
         WrappersV2::add_to_linker(&mut linker, |host: &mut FdwHost| host)?;
+        WrappersV3::add_to_linker(&mut linker, |host: &mut FdwHost| host)?;
 
         let mut store = Store::new(&engine, fdw_host);
         let bindings = WrappersV1::instantiate(&mut store, &component, &linker)
             .map(Bindings::V1)
+            .or_else(|_| WrappersV2::instantiate(&mut store, &component, &linker).map(Bindings::V2))
             .or_else(|_| {
-                WrappersV2::instantiate(&mut store, &component, &linker).map(Bindings::V2)
+                WrappersV3::instantiate(&mut store, &component, &linker).map(Bindings::V3)
             })?;
 
         let mut wasm_fdw = Self { store, bindings };
@@ -486,8 +598,19 @@ impl ForeignDataWrapper<WasmFdwError> for WasmFdw {
         limit: &Option<Limit>,
         options: &HashMap<String, String>,
     ) -> WasmFdwResult<()> {
+        // the quals whose value cannot be passed to the guest are not pushed
+        // down, they are still evaluated by Postgres locally
+        let quals = quals
+            .iter()
+            .filter(|qual| match &qual.value {
+                Value::Cell(cell) => self.check_cell(cell).is_ok(),
+                Value::Array(cells) => cells.iter().all(|cell| self.check_cell(cell).is_ok()),
+            })
+            .cloned()
+            .collect();
+
         let fdw_state = self.store.data_mut();
-        fdw_state.quals = quals.to_vec();
+        fdw_state.quals = quals;
         fdw_state.columns = columns.to_vec();
         fdw_state.sorts = sorts.to_vec();
         fdw_state.limit.clone_from(limit);
@@ -522,17 +645,64 @@ impl ForeignDataWrapper<WasmFdwError> for WasmFdw {
     }
 
     fn insert(&mut self, src: &Row) -> WasmFdwResult<()> {
-        self.store.data_mut().row = src.clone();
-        self.call_insert()
+        self.insert_returning(src, &[], &mut Row::new())
+    }
+
+    fn insert_returning(
+        &mut self,
+        src: &Row,
+        columns: &[Column],
+        returning: &mut Row,
+    ) -> WasmFdwResult<()> {
+        self.check_row(src)?;
+
+        let fdw_state = self.store.data_mut();
+        fdw_state.row = src.clone();
+        fdw_state.columns = columns.to_vec();
+        fdw_state.returning.clear();
+        self.call_insert()?;
+        returning.replace_with(self.store.data().returning.clone());
+        Ok(())
     }
 
     fn update(&mut self, rowid: &Cell, new_row: &Row) -> WasmFdwResult<()> {
-        self.store.data_mut().row = new_row.clone();
-        self.call_update(rowid)
+        self.update_returning(rowid, new_row, &[], &mut Row::new())
+    }
+
+    fn update_returning(
+        &mut self,
+        rowid: &Cell,
+        new_row: &Row,
+        columns: &[Column],
+        returning: &mut Row,
+    ) -> WasmFdwResult<()> {
+        self.check_row(new_row)?;
+
+        let fdw_state = self.store.data_mut();
+        fdw_state.row = new_row.clone();
+        fdw_state.columns = columns.to_vec();
+        fdw_state.returning.clear();
+        self.call_update(rowid)?;
+        returning.replace_with(self.store.data().returning.clone());
+        Ok(())
     }
 
     fn delete(&mut self, rowid: &Cell) -> WasmFdwResult<()> {
-        self.call_delete(rowid)
+        self.delete_returning(rowid, &[], &mut Row::new())
+    }
+
+    fn delete_returning(
+        &mut self,
+        rowid: &Cell,
+        columns: &[Column],
+        returning: &mut Row,
+    ) -> WasmFdwResult<()> {
+        let fdw_state = self.store.data_mut();
+        fdw_state.columns = columns.to_vec();
+        fdw_state.returning.clear();
+        self.call_delete(rowid)?;
+        returning.replace_with(self.store.data().returning.clone());
+        Ok(())
     }
 
     fn end_modify(&mut self) -> WasmFdwResult<()> {
@@ -549,15 +719,25 @@ impl ForeignDataWrapper<WasmFdwError> for WasmFdw {
         self.call_import_foreign_schema(stmt)
     }
 
-    fn validator(options: Vec<Option<String>>, catalog: Option<pg_sys::Oid>) -> WasmFdwResult<()> {
-        if let Some(oid) = catalog {
-            if oid == FOREIGN_SERVER_RELATION_ID {
-                check_options_contain(&options, "fdw_package_url")?;
-                check_options_contain(&options, "fdw_package_name")?;
-                check_options_contain(&options, "fdw_package_version")?;
-            }
-        }
-
-        Ok(())
+    fn option_defs() -> Vec<OptionDef> {
+        vec![
+            OptionDef::new("fdw_package_url", &[OptionContext::Server])
+                .required()
+                .description("URL of the Wasm package"),
+            OptionDef::new("fdw_package_name", &[OptionContext::Server])
+                .required()
+                .description("Name of the Wasm package"),
+            OptionDef::new("fdw_package_version", &[OptionContext::Server])
+                .required()
+                .description("Version of the Wasm package"),
+            OptionDef::new("fdw_package_checksum", &[OptionContext::Server])
+                .description("SHA-256 checksum of the Wasm package"),
+            // the other options are passed to the Wasm package as they are
+            OptionDef::any(&[
+                OptionContext::Server,
+                OptionContext::Table,
+                OptionContext::Import,
+            ]),
+        ]
     }
 }